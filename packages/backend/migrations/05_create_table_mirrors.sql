@@ -0,0 +1,7 @@
+CREATE TABLE mirrors (
+    ref UUID PRIMARY KEY REFERENCES refs (id),
+    sourceUrl TEXT NOT NULL,
+    subscribedAt TIMESTAMPTZ NOT NULL,
+    lastFetched TIMESTAMPTZ,
+    lastError TEXT
+);
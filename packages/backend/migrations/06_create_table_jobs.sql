@@ -0,0 +1,16 @@
+CREATE TABLE jobs (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    kind TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('queued', 'running', 'succeeded', 'dead')),
+    attempts INT NOT NULL,
+    maxAttempts INT NOT NULL,
+    runAt TIMESTAMPTZ NOT NULL,
+    lockedAt TIMESTAMPTZ,
+    lastError TEXT,
+    result JSONB,
+    createdAt TIMESTAMPTZ NOT NULL,
+    finishedAt TIMESTAMPTZ
+);
+
+CREATE INDEX jobs_runnable ON jobs (runAt) WHERE status = 'queued';
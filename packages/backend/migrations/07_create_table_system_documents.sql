@@ -0,0 +1,5 @@
+CREATE TABLE system_documents (
+    slug TEXT PRIMARY KEY,
+    ref UUID NOT NULL UNIQUE REFERENCES refs (id),
+    version INT NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE ref_views (
+    ref UUID NOT NULL REFERENCES refs (id),
+    viewer TEXT NOT NULL,
+    viewedAt TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX ref_views_by_ref ON ref_views (ref, viewedAt);
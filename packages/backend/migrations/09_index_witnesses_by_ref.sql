@@ -0,0 +1 @@
+CREATE INDEX witnesses_by_ref ON witnesses (forRef);
@@ -0,0 +1 @@
+DROP INDEX witnesses_by_ref;
@@ -0,0 +1,7 @@
+CREATE TABLE pins (
+    ref UUID NOT NULL REFERENCES refs (id),
+    snapshot INT NOT NULL REFERENCES snapshots (id),
+    note TEXT,
+    pinnedAt TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (ref, snapshot)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE attachments (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    ref UUID NOT NULL REFERENCES refs (id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    contentType TEXT NOT NULL,
+    size INT NOT NULL CHECK (size >= 0),
+    createdAt TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX attachments_by_ref ON attachments (ref);
@@ -0,0 +1,5 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX refs_title_search ON refs USING GIN (to_tsvector('simple', COALESCE(title, '')));
+CREATE INDEX refs_title_trigrams ON refs USING GIN (title gin_trgm_ops);
+CREATE INDEX system_documents_slug_trigrams ON system_documents USING GIN (slug gin_trgm_ops);
@@ -0,0 +1,5 @@
+DROP INDEX system_documents_slug_trigrams;
+DROP INDEX refs_title_trigrams;
+DROP INDEX refs_title_search;
+
+DROP EXTENSION IF EXISTS pg_trgm;
@@ -0,0 +1,8 @@
+CREATE TABLE autosave_log (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    ref UUID NOT NULL REFERENCES refs (id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    loggedAt TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX autosave_log_by_ref ON autosave_log (ref, id);
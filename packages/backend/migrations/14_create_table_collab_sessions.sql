@@ -0,0 +1,12 @@
+CREATE TABLE collab_sessions (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    ref UUID NOT NULL REFERENCES refs (id) ON DELETE CASCADE,
+    startedAt TIMESTAMPTZ NOT NULL,
+    endedAt TIMESTAMPTZ NOT NULL,
+    peakPeers INT NOT NULL,
+    meanPeers REAL NOT NULL,
+    edits INT NOT NULL,
+    participants JSONB NOT NULL
+);
+
+CREATE INDEX collab_sessions_by_ref ON collab_sessions (ref, startedAt);
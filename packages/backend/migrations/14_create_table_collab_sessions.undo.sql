@@ -0,0 +1 @@
+DROP TABLE collab_sessions;
@@ -0,0 +1,6 @@
+CREATE TABLE permalinks (
+    hash BYTEA PRIMARY KEY,
+    ref UUID NOT NULL REFERENCES refs (id),
+    snapshot INT NOT NULL REFERENCES snapshots (id),
+    createdAt TIMESTAMPTZ NOT NULL
+);
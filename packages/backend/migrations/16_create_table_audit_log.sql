@@ -0,0 +1,9 @@
+CREATE TABLE audit_log (
+    id INT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    ref UUID REFERENCES refs (id) ON DELETE CASCADE,
+    action TEXT NOT NULL,
+    details JSONB NOT NULL,
+    at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX audit_log_by_ref ON audit_log (ref, at);
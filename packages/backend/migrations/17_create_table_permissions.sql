@@ -0,0 +1,15 @@
+CREATE TABLE users (
+    id TEXT PRIMARY KEY,
+    email TEXT,
+    createdAt TIMESTAMPTZ NOT NULL,
+    lastSeen TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE permissions (
+    ref UUID NOT NULL REFERENCES refs (id) ON DELETE CASCADE,
+    userId TEXT NOT NULL,
+    level TEXT NOT NULL CHECK (level IN ('viewer', 'editor', 'owner')),
+    PRIMARY KEY (ref, userId)
+);
+
+CREATE INDEX permissions_by_user ON permissions (userId);
@@ -0,0 +1,2 @@
+DROP TABLE permissions;
+DROP TABLE users;
@@ -0,0 +1,26 @@
+-- Content of a snapshot as JSON, or null if it is not valid JSON.
+CREATE FUNCTION try_jsonb(content TEXT) RETURNS JSONB AS $$
+BEGIN
+    RETURN content::JSONB;
+EXCEPTION WHEN invalid_text_representation THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE STRICT;
+
+-- Searchable text of a document: its name, its type, and its rich-text cells.
+CREATE FUNCTION snapshot_search_vector(content TEXT) RETURNS TSVECTOR AS $$
+    SELECT to_tsvector('simple', COALESCE(doc ->> 'name', '') || ' ' || COALESCE(doc ->> 'type', ''))
+        || jsonb_to_tsvector(
+            'simple',
+            COALESCE(
+                jsonb_path_query_array(doc, '$.notebook.cells[*] ? (@.tag == "rich-text").content'),
+                '[]'
+            ),
+            '["string"]'
+        )
+    FROM (SELECT try_jsonb(content) AS doc) AS parsed
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE INDEX snapshots_content_search ON snapshots USING GIN (snapshot_search_vector(content));
+CREATE INDEX snapshots_content_type ON snapshots ((try_jsonb(content) ->> 'type'));
+CREATE INDEX refs_by_last_updated ON refs (lastUpdated DESC);
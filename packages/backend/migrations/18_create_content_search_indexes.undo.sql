@@ -0,0 +1,5 @@
+DROP INDEX refs_by_last_updated;
+DROP INDEX snapshots_content_type;
+DROP INDEX snapshots_content_search;
+DROP FUNCTION snapshot_search_vector;
+DROP FUNCTION try_jsonb;
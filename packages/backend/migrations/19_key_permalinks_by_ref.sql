@@ -0,0 +1,9 @@
+-- Permalinks were keyed by the hash of their content alone, so refs with the same
+-- content shared a permalink. Each ref now has its own, whose token also depends
+-- on the ref. Existing permalinks keep their tokens, so that citations still resolve.
+ALTER TABLE permalinks ADD COLUMN token BYTEA;
+UPDATE permalinks SET token = hash;
+ALTER TABLE permalinks ALTER COLUMN token SET NOT NULL;
+ALTER TABLE permalinks DROP CONSTRAINT permalinks_pkey;
+ALTER TABLE permalinks ADD PRIMARY KEY (token);
+ALTER TABLE permalinks ADD CONSTRAINT permalinks_ref_hash_key UNIQUE (ref, hash);
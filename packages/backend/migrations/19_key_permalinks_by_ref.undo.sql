@@ -0,0 +1,6 @@
+-- Only permalinks created before this migration have tokens equal to their hash.
+DELETE FROM permalinks WHERE token <> hash;
+ALTER TABLE permalinks DROP CONSTRAINT permalinks_ref_hash_key;
+ALTER TABLE permalinks DROP CONSTRAINT permalinks_pkey;
+ALTER TABLE permalinks ADD PRIMARY KEY (hash);
+ALTER TABLE permalinks DROP COLUMN token;
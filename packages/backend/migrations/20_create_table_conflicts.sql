@@ -0,0 +1,8 @@
+-- Live documents that conflict with a concurrent write to their autosave, along
+-- with their live content, which is not saved anywhere else until the conflict
+-- is resolved.
+CREATE TABLE conflicts (
+    ref UUID PRIMARY KEY REFERENCES refs (id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    detectedAt TIMESTAMPTZ NOT NULL
+);
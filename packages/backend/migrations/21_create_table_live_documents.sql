@@ -0,0 +1,10 @@
+-- Automerge documents of refs that were evicted from memory, which hold the
+-- history of their live edits. Each document is kept with its ID, so that clients
+-- still syncing it can be recognized, and with its content when it was saved.
+CREATE TABLE live_documents (
+    ref UUID PRIMARY KEY REFERENCES refs (id) ON DELETE CASCADE,
+    documentId TEXT NOT NULL,
+    data BYTEA NOT NULL,
+    content TEXT NOT NULL,
+    savedAt TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,30 @@
+-- Search covers only the autosave of each ref, so the content of older snapshots
+-- need not be indexed. The searchable text and the type of a ref's autosave are
+-- instead kept on the ref itself, where they are indexed along with its title.
+DROP INDEX snapshots_content_search;
+DROP INDEX snapshots_content_type;
+
+ALTER TABLE refs ADD COLUMN searchVector TSVECTOR, ADD COLUMN docType TEXT;
+
+CREATE FUNCTION refs_update_search() RETURNS TRIGGER AS $$
+BEGIN
+    SELECT snapshot_search_vector(content), try_jsonb(content) ->> 'type'
+    INTO NEW.searchVector, NEW.docType
+    FROM snapshots
+    WHERE id = NEW.autosave;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER refs_update_search
+BEFORE INSERT OR UPDATE OF autosave ON refs
+FOR EACH ROW EXECUTE FUNCTION refs_update_search();
+
+UPDATE refs
+SET searchVector = snapshot_search_vector(snapshots.content),
+    docType = try_jsonb(snapshots.content) ->> 'type'
+FROM snapshots
+WHERE snapshots.id = refs.autosave;
+
+CREATE INDEX refs_content_search ON refs USING GIN (searchVector);
+CREATE INDEX refs_by_doc_type ON refs (docType, lastUpdated DESC);
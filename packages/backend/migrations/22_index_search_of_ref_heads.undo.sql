@@ -0,0 +1,8 @@
+DROP INDEX refs_by_doc_type;
+DROP INDEX refs_content_search;
+DROP TRIGGER refs_update_search ON refs;
+DROP FUNCTION refs_update_search;
+ALTER TABLE refs DROP COLUMN docType, DROP COLUMN searchVector;
+
+CREATE INDEX snapshots_content_search ON snapshots USING GIN (snapshot_search_vector(content));
+CREATE INDEX snapshots_content_type ON snapshots ((try_jsonb(content) ->> 'type'));
@@ -0,0 +1,61 @@
+import assert from "node:assert";
+import * as crypto from "node:crypto";
+import { it, test } from "node:test";
+import { AuthError, type Jwk, TokenVerifier, bearerToken, hasAccess } from "./auth.js";
+
+const issuer = "https://securetoken.google.com/catcolab-test";
+const audience = "catcolab-test";
+
+const { privateKey, publicKey } = crypto.generateKeyPairSync("rsa", { modulusLength: 2048 });
+const jwk: Jwk = { ...publicKey.export({ format: "jwk" }), kid: "key1" };
+
+function sign(payload: Record<string, unknown>, kid = "key1"): string {
+    const encode = (x: unknown) => Buffer.from(JSON.stringify(x)).toString("base64url");
+    const data = `${encode({ alg: "RS256", kid })}.${encode(payload)}`;
+    const signature = crypto.sign("sha256", Buffer.from(data), privateKey);
+    return `${data}.${signature.toString("base64url")}`;
+}
+
+const now = Math.floor(Date.now() / 1000);
+const claims = { iss: issuer, aud: audience, sub: "alice", exp: now + 3600, iat: now };
+
+test("Authentication", async (_t) => {
+    let fetches = 0;
+    const verifier = new TokenVerifier(issuer, audience, async () => {
+        fetches++;
+        return [jwk];
+    });
+
+    await it("verifies tokens signed by the issuer", async () => {
+        const verified = await verifier.verify(sign({ ...claims, email: "alice@example.org" }));
+        assert.deepStrictEqual(verified, { sub: "alice", email: "alice@example.org" });
+        await verifier.verify(sign(claims));
+        assert.strictEqual(fetches, 1);
+    });
+
+    await it("rejects tokens that should not be trusted", async () => {
+        await assert.rejects(verifier.verify(sign({ ...claims, iss: "https://evil" })), AuthError);
+        await assert.rejects(verifier.verify(sign({ ...claims, aud: "other" })), AuthError);
+        await assert.rejects(verifier.verify(sign({ ...claims, exp: now - 3600 })), AuthError);
+        await assert.rejects(verifier.verify(sign({ ...claims, sub: "" })), AuthError);
+        await assert.rejects(verifier.verify(sign(claims, "key2")), AuthError);
+        await assert.rejects(verifier.verify("not.a.token"), AuthError);
+
+        const [header, , signature] = sign(claims).split(".");
+        const forged = Buffer.from(JSON.stringify({ ...claims, sub: "bob" })).toString("base64url");
+        await assert.rejects(verifier.verify(`${header}.${forged}.${signature}`), AuthError);
+    });
+
+    await it("orders levels of access", () => {
+        assert.ok(hasAccess("owner", "editor"));
+        assert.ok(hasAccess("viewer", "viewer"));
+        assert.ok(!hasAccess("viewer", "editor"));
+        assert.ok(!hasAccess(null, "viewer"));
+    });
+
+    await it("reads bearer tokens", () => {
+        assert.strictEqual(bearerToken("Bearer abc"), "abc");
+        assert.strictEqual(bearerToken("Basic abc"), undefined);
+        assert.strictEqual(bearerToken(undefined), undefined);
+    });
+});
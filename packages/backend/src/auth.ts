@@ -0,0 +1,173 @@
+import * as crypto from "node:crypto";
+
+/** Levels of access to a ref, each including the ones before it.
+
+- "viewer": read the document and its history
+- "editor": also change it
+- "owner": also share it with others
+ */
+export const accessLevels = ["viewer", "editor", "owner"] as const;
+
+export type AccessLevel = (typeof accessLevels)[number];
+
+/** Whether a granted level of access is enough for a required one. */
+export function hasAccess(granted: AccessLevel | null, required: AccessLevel): boolean {
+    return granted !== null && accessLevels.indexOf(granted) >= accessLevels.indexOf(required);
+}
+
+/** Error raised for a bearer token that is malformed, expired, or not trusted. */
+export class AuthError extends Error {}
+
+/** Claims of a verified ID token that the backend relies on. */
+export type Claims = {
+    /** Identifier of the user, unique for the issuer. */
+    sub: string;
+    email?: string;
+};
+
+/** Public key in a JSON Web Key Set, as served by an identity provider. */
+export type Jwk = crypto.JsonWebKey & { kid?: string };
+
+/** Leeway for the clocks of the issuer and the backend, in seconds. */
+const CLOCK_SKEW_SECONDS = 60;
+
+/** Minimum time between fetches of the keys of the issuer, in milliseconds. */
+const KEY_REFRESH_MS = 60 * 1000;
+
+/** Verifies ID tokens issued by an OpenID Connect provider, such as Firebase.
+
+Tokens must be JWTs signed with RS256 or ES256 by one of the keys of the issuer,
+and must be issued for the given audience. Keys are fetched on first use and
+again whenever a token is signed with an unknown key, which happens when the
+provider rotates them.
+ */
+export class TokenVerifier {
+    private keys = new Map<string, crypto.KeyObject>();
+    private fetchedAt = 0;
+
+    constructor(
+        readonly issuer: string,
+        readonly audience: string,
+        readonly fetchKeys: () => Promise<Jwk[]> = () => discoverKeys(issuer),
+        readonly now: () => number = Date.now,
+    ) {}
+
+    /** Create a verifier from `AUTH_ISSUER` and `AUTH_AUDIENCE`, if authentication
+    is enabled.
+
+    For Firebase, the issuer is `https://securetoken.google.com/<project-id>` and
+    the audience is the project ID.
+     */
+    static fromEnv(): TokenVerifier | null {
+        const issuer = process.env.AUTH_ISSUER;
+        if (!issuer) {
+            return null;
+        }
+        const audience = process.env.AUTH_AUDIENCE;
+        if (!audience) {
+            throw new AuthError("AUTH_AUDIENCE must be set along with AUTH_ISSUER");
+        }
+        return new TokenVerifier(issuer, audience);
+    }
+
+    async verify(token: string): Promise<Claims> {
+        const [encodedHeader = "", encodedPayload = "", encodedSignature = "", ...rest] =
+            token.split(".");
+        if (rest.length > 0) {
+            throw new AuthError("Token is not a JWT");
+        }
+        const header = decodeSegment(encodedHeader);
+        const payload = decodeSegment(encodedPayload);
+
+        const key = await this.key(String(header.kid));
+        const data = Buffer.from(`${encodedHeader}.${encodedPayload}`);
+        const signature = Buffer.from(encodedSignature, "base64url");
+        let valid: boolean;
+        if (header.alg === "RS256" && key.asymmetricKeyType === "rsa") {
+            valid = crypto.verify("sha256", data, key, signature);
+        } else if (header.alg === "ES256" && key.asymmetricKeyType === "ec") {
+            valid = crypto.verify("sha256", data, { key, dsaEncoding: "ieee-p1363" }, signature);
+        } else {
+            throw new AuthError(`Unsupported signing algorithm: ${header.alg}`);
+        }
+        if (!valid) {
+            throw new AuthError("Invalid token signature");
+        }
+
+        const now = this.now() / 1000;
+        if (payload.iss !== this.issuer) {
+            throw new AuthError("Token was issued by another issuer");
+        }
+        const audiences = Array.isArray(payload.aud) ? payload.aud : [payload.aud];
+        if (!audiences.includes(this.audience)) {
+            throw new AuthError("Token was issued for another audience");
+        }
+        if (typeof payload.exp !== "number" || payload.exp + CLOCK_SKEW_SECONDS < now) {
+            throw new AuthError("Token has expired");
+        }
+        if (typeof payload.nbf === "number" && payload.nbf - CLOCK_SKEW_SECONDS > now) {
+            throw new AuthError("Token is not valid yet");
+        }
+        if (typeof payload.sub !== "string" || payload.sub === "") {
+            throw new AuthError("Token has no subject");
+        }
+        const email = typeof payload.email === "string" ? payload.email : undefined;
+        return { sub: payload.sub, email };
+    }
+
+    private async key(kid: string): Promise<crypto.KeyObject> {
+        let key = this.keys.get(kid);
+        if (!key && this.now() - this.fetchedAt >= KEY_REFRESH_MS) {
+            this.fetchedAt = this.now();
+            const keys = new Map<string, crypto.KeyObject>();
+            for (const jwk of await this.fetchKeys()) {
+                if (jwk.kid) {
+                    keys.set(jwk.kid, crypto.createPublicKey({ key: jwk, format: "jwk" }));
+                }
+            }
+            this.keys = keys;
+            key = keys.get(kid);
+        }
+        if (!key) {
+            throw new AuthError(`Token was signed with unknown key ${kid}`);
+        }
+        return key;
+    }
+}
+
+function decodeSegment(segment: string): Record<string, unknown> {
+    let value: unknown;
+    try {
+        value = JSON.parse(Buffer.from(segment, "base64url").toString("utf8"));
+    } catch {
+        value = null;
+    }
+    if (typeof value !== "object" || value === null) {
+        throw new AuthError("Token is not a JWT");
+    }
+    return value as Record<string, unknown>;
+}
+
+/** Fetch the signing keys of an issuer through OpenID Connect discovery. */
+async function discoverKeys(issuer: string): Promise<Jwk[]> {
+    const configUrl = `${issuer.replace(/\/$/, "")}/.well-known/openid-configuration`;
+    const config = await fetchJson(configUrl);
+    if (typeof config.jwks_uri !== "string") {
+        throw new AuthError(`No key set in the configuration of issuer ${issuer}`);
+    }
+    const { keys } = await fetchJson(config.jwks_uri);
+    return Array.isArray(keys) ? keys : [];
+}
+
+async function fetchJson(url: string): Promise<Record<string, unknown>> {
+    const response = await fetch(url);
+    if (!response.ok) {
+        throw new AuthError(`Failed to fetch ${url}: ${response.status}`);
+    }
+    return (await response.json()) as Record<string, unknown>;
+}
+
+/** Get the token of an `Authorization: Bearer` header, if any. */
+export function bearerToken(header: string | undefined): string | undefined {
+    return header?.match(/^Bearer (.+)$/)?.[1];
+}
@@ -0,0 +1,69 @@
+import type { Persistence } from "./persistence.js";
+
+/** Background task folding the autosave log into snapshots.
+
+Autosaves of live documents are appended to a log, so that the hot path never
+updates a row. The folder periodically collapses the log of each ref into a
+single snapshot, advances the autosave of the ref to it, and updates the links
+from the ref. Saving a ref folds its log first, so witnesses never lag behind,
+and readers of the folded autosaves, such as backlinks and search, flush the log.
+ */
+export class AutosaveFolder {
+    private timer?: NodeJS.Timeout;
+    private running?: Promise<number>;
+
+    constructor(
+        readonly db: Persistence,
+        /** Number of refs folded per batch. */
+        readonly batchSize = 100,
+    ) {}
+
+    /** Fold the logs of all refs with pending autosaves, returning how many were folded.
+
+    Concurrent calls share a single pass.
+     */
+    fold(): Promise<number> {
+        if (!this.running) {
+            this.running = this.foldAll().finally(() => {
+                this.running = undefined;
+            });
+        }
+        return this.running;
+    }
+
+    /** Fold every autosave logged so far, for a reader of the folded autosaves.
+
+    A pass already running may have missed the latest autosaves, so another pass
+    follows it.
+     */
+    async flush(): Promise<number> {
+        await this.running?.catch(() => undefined);
+        return await this.fold();
+    }
+
+    private async foldAll(): Promise<number> {
+        let total = 0;
+        for (;;) {
+            const folded = await this.db.foldAutosaves(this.batchSize);
+            total += folded;
+            // Refs that failed to fold stay in the log, so stop at a short batch.
+            if (folded < this.batchSize) {
+                return total;
+            }
+        }
+    }
+
+    /** Start folding at a fixed interval. */
+    start(intervalMs: number) {
+        this.stop();
+        this.timer = setInterval(() => {
+            this.fold().catch((e) => console.error("Failed to fold autosaves:", e));
+        }, intervalMs);
+        this.timer.unref();
+    }
+
+    stop() {
+        clearInterval(this.timer);
+        this.timer = undefined;
+    }
+}
@@ -0,0 +1,174 @@
+import * as crypto from "node:crypto";
+
+/** Credentials for signing requests to AWS and S3-compatible services. */
+export type AwsCredentials = {
+    accessKeyId: string;
+    secretAccessKey: string;
+    /** Token for temporary credentials, if any. */
+    sessionToken?: string;
+};
+
+/** Read AWS credentials from the standard `AWS_*` environment variables. */
+export function awsCredentialsFromEnv(): AwsCredentials {
+    return {
+        accessKeyId: process.env.AWS_ACCESS_KEY_ID ?? "",
+        secretAccessKey: process.env.AWS_SECRET_ACCESS_KEY ?? "",
+        sessionToken: process.env.AWS_SESSION_TOKEN || undefined,
+    };
+}
+
+/** Request to be signed with AWS Signature Version 4 in the query string. */
+export type PresignRequest = {
+    method: string;
+    url: URL;
+    region: string;
+    credentials: AwsCredentials;
+    /** Time at which the signature becomes valid. */
+    date: Date;
+    /** Number of seconds for which the signature is valid, at most a week. */
+    expiresSeconds: number;
+    /** Headers, besides the host, that the client must send with these exact values. */
+    headers?: Record<string, string>;
+};
+
+/** Sign a request to S3 so that anyone holding the URL can make it until it expires.
+
+Implements query-string authentication with AWS Signature Version 4, as described
+in the S3 API reference under "Authenticating Requests: Using Query Parameters".
+The payload is left unsigned, as is usual for presigned URLs.
+ */
+export function presignUrl(req: PresignRequest): URL {
+    const url = new URL(req.url);
+    const timestamp = amzDate(req.date);
+    const scope = credentialScope(timestamp, req.region, "s3");
+
+    const headers: Record<string, string> = { host: url.host };
+    for (const [name, value] of Object.entries(req.headers ?? {})) {
+        headers[name.toLowerCase()] = value.trim();
+    }
+    const headerNames = Object.keys(headers).sort();
+    const signedHeaders = headerNames.join(";");
+
+    url.searchParams.set("X-Amz-Algorithm", "AWS4-HMAC-SHA256");
+    url.searchParams.set("X-Amz-Credential", `${req.credentials.accessKeyId}/${scope}`);
+    url.searchParams.set("X-Amz-Date", timestamp);
+    url.searchParams.set("X-Amz-Expires", String(req.expiresSeconds));
+    url.searchParams.set("X-Amz-SignedHeaders", signedHeaders);
+    if (req.credentials.sessionToken) {
+        url.searchParams.set("X-Amz-Security-Token", req.credentials.sessionToken);
+    }
+
+    const query = canonicalQuery(url);
+    const canonicalRequest = [
+        req.method,
+        url.pathname,
+        query,
+        ...headerNames.map((name) => `${name}:${headers[name]}`),
+        "",
+        signedHeaders,
+        "UNSIGNED-PAYLOAD",
+    ].join("\n");
+    const signature = sign(req.credentials, timestamp, scope, canonicalRequest);
+    url.search = `${query}&X-Amz-Signature=${signature}`;
+    return url;
+}
+
+/** Request to be signed with AWS Signature Version 4 in the headers. */
+export type SignRequest = {
+    method: string;
+    url: URL;
+    region: string;
+    /** Signing name of the service, e.g. "secretsmanager". */
+    service: string;
+    credentials: AwsCredentials;
+    date: Date;
+    /** Headers to send and sign, besides the host and date. */
+    headers: Record<string, string>;
+    body: string;
+};
+
+/** Sign a request to an AWS API, returning the headers to send with it.
+
+Implements header authentication with AWS Signature Version 4, as described in
+the IAM user guide under "Create a signed AWS API request".
+ */
+export function signRequest(req: SignRequest): Record<string, string> {
+    const timestamp = amzDate(req.date);
+    const scope = credentialScope(timestamp, req.region, req.service);
+
+    const headers: Record<string, string> = { host: req.url.host, "x-amz-date": timestamp };
+    if (req.credentials.sessionToken) {
+        headers["x-amz-security-token"] = req.credentials.sessionToken;
+    }
+    for (const [name, value] of Object.entries(req.headers)) {
+        headers[name.toLowerCase()] = value.trim();
+    }
+    const headerNames = Object.keys(headers).sort();
+    const signedHeaders = headerNames.join(";");
+
+    const canonicalRequest = [
+        req.method,
+        req.url.pathname,
+        canonicalQuery(req.url),
+        ...headerNames.map((name) => `${name}:${headers[name]}`),
+        "",
+        signedHeaders,
+        sha256Hex(req.body),
+    ].join("\n");
+    const signature = sign(req.credentials, timestamp, scope, canonicalRequest);
+    const credential = `${req.credentials.accessKeyId}/${scope}`;
+    headers.authorization = [
+        `AWS4-HMAC-SHA256 Credential=${credential}`,
+        `SignedHeaders=${signedHeaders}`,
+        `Signature=${signature}`,
+    ].join(", ");
+    return headers;
+}
+
+/** Format a time as in the `X-Amz-Date` header, e.g. `20130524T000000Z`. */
+function amzDate(date: Date): string {
+    return date.toISOString().replace(/[-:]/g, "").replace(/\.\d+/, "");
+}
+
+function credentialScope(timestamp: string, region: string, service: string): string {
+    return `${timestamp.slice(0, 8)}/${region}/${service}/aws4_request`;
+}
+
+function canonicalQuery(url: URL): string {
+    return [...url.searchParams]
+        .map(([k, v]) => [uriEncode(k), uriEncode(v)])
+        .sort(([a], [b]) => (a < b ? -1 : a > b ? 1 : 0))
+        .map(([k, v]) => `${k}=${v}`)
+        .join("&");
+}
+
+/** Compute the signature of a canonical request, as a hex string. */
+function sign(
+    credentials: AwsCredentials,
+    timestamp: string,
+    scope: string,
+    canonicalRequest: string,
+): string {
+    const stringToSign = ["AWS4-HMAC-SHA256", timestamp, scope, sha256Hex(canonicalRequest)];
+    let key: Buffer = Buffer.from(`AWS4${credentials.secretAccessKey}`);
+    for (const part of scope.split("/")) {
+        key = hmac(key, part);
+    }
+    return hmac(key, stringToSign.join("\n")).toString("hex");
+}
+
+/** Percent-encode a string as required by Signature Version 4. */
+export function uriEncode(s: string): string {
+    return encodeURIComponent(s).replace(
+        /[!'()*]/g,
+        (c) => `%${c.charCodeAt(0).toString(16).toUpperCase()}`,
+    );
+}
+
+function hmac(key: string | Buffer, data: string): Buffer {
+    return crypto.createHmac("sha256", key).update(data).digest();
+}
+
+function sha256Hex(data: string): string {
+    return crypto.createHash("sha256").update(data).digest("hex");
+}
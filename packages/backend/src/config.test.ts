@@ -0,0 +1,57 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { getActivationSockets, getListenAddresses, parseListenAddress } from "./config.js";
+
+test("Listen addresses", async (_t) => {
+    await it("parses bare ports as all interfaces", () => {
+        assert.deepStrictEqual(parseListenAddress("8000"), { port: 8000 });
+        assert.deepStrictEqual(parseListenAddress(":8000"), { port: 8000 });
+    });
+
+    await it("parses IPv4 addresses and host names", () => {
+        assert.deepStrictEqual(parseListenAddress("127.0.0.1:8000"), {
+            host: "127.0.0.1",
+            port: 8000,
+        });
+        assert.deepStrictEqual(parseListenAddress("localhost:8000"), {
+            host: "localhost",
+            port: 8000,
+        });
+    });
+
+    await it("parses bracketed IPv6 addresses", () => {
+        assert.deepStrictEqual(parseListenAddress("[::]:8000"), { host: "::", port: 8000 });
+        assert.deepStrictEqual(parseListenAddress("[::1]:8000"), { host: "::1", port: 8000 });
+    });
+
+    await it("parses Unix domain socket paths", () => {
+        assert.deepStrictEqual(parseListenAddress("unix:/run/catcolab.sock"), {
+            path: "/run/catcolab.sock",
+        });
+        assert.throws(() => parseListenAddress("unix:"));
+    });
+
+    await it("rejects malformed addresses", () => {
+        assert.throws(() => parseListenAddress("::1:8000"));
+        assert.throws(() => parseListenAddress("[::1]"));
+        assert.throws(() => parseListenAddress("localhost:99999"));
+    });
+});
+
+test("Socket activation", async (_t) => {
+    await it("ignores sockets meant for another process", () => {
+        process.env.LISTEN_PID = String(process.pid + 1);
+        process.env.LISTEN_FDS = "1";
+        assert.deepStrictEqual(getActivationSockets(), []);
+    });
+
+    await it("uses sockets passed by systemd", () => {
+        process.env.LISTEN_PID = String(process.pid);
+        process.env.LISTEN_FDS = "2";
+        process.env.LISTEN_ADDRESSES = "127.0.0.1:8000";
+        assert.deepStrictEqual(getListenAddresses(8000), [{ fd: 3 }, { fd: 4 }]);
+        assert.strictEqual(process.env.LISTEN_FDS, undefined);
+        assert.deepStrictEqual(getListenAddresses(8000), [{ host: "127.0.0.1", port: 8000 }]);
+        Reflect.deleteProperty(process.env, "LISTEN_ADDRESSES");
+    });
+});
@@ -0,0 +1,96 @@
+/** Path prefix under which the backend serves all of its routes, e.g. `/catcolab`.
+
+Read from the `BASE_PATH` environment variable, for deployments behind a reverse
+proxy that forwards only part of its URL space to the backend. The prefix is
+normalized to have a leading slash and no trailing slash, so that serving from
+the root corresponds to the empty string.
+ */
+export function getBasePath(): string {
+    return (process.env.BASE_PATH ?? "").replace(/^\/*/, "/").replace(/\/+$/, "");
+}
+
+/** An address on which the backend accepts connections.
+
+This is either a TCP address, where a missing host means all interfaces, on both
+IPv4 and IPv6 where available, the path of a Unix domain socket, or a socket
+already opened by the parent process.
+ */
+export type ListenAddress = TcpAddress | UnixSocketAddress | InheritedSocket;
+
+export type TcpAddress = {
+    host?: string;
+    port: number;
+};
+
+export type UnixSocketAddress = {
+    path: string;
+};
+
+export type InheritedSocket = {
+    fd: number;
+};
+
+/** First file descriptor passed by systemd, after stdin, stdout, and stderr. */
+const SD_LISTEN_FDS_START = 3;
+
+/** Sockets passed by systemd socket activation, if the process was started that way.
+
+Follows the protocol of `sd_listen_fds(3)`. The environment variables are removed
+once read, so that they are not inherited by any child process.
+ */
+export function getActivationSockets(): InheritedSocket[] {
+    const { LISTEN_PID, LISTEN_FDS } = process.env;
+    if (!LISTEN_FDS || Number(LISTEN_PID) !== process.pid) {
+        return [];
+    }
+    for (const name of ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"]) {
+        Reflect.deleteProperty(process.env, name);
+    }
+    return Array.from({ length: Number(LISTEN_FDS) }, (_, i) => ({ fd: SD_LISTEN_FDS_START + i }));
+}
+
+/** Parse an address like `8000`, `:8000`, `127.0.0.1:8000`, `[::1]:8000`, or
+`unix:/run/catcolab/backend.sock`.
+ */
+export function parseListenAddress(spec: string): ListenAddress {
+    if (spec.trim().startsWith("unix:")) {
+        const path = spec.trim().slice("unix:".length);
+        if (!path) {
+            throw new Error(`Invalid listen address: ${spec}`);
+        }
+        return { path };
+    }
+    const match = spec.trim().match(/^(?:(?:\[([^\]]+)\]|([^:[\]]*)):)?(\d+)$/);
+    const port = Number(match?.[3]);
+    if (!match || !Number.isInteger(port) || port > 65535) {
+        throw new Error(`Invalid listen address: ${spec}`);
+    }
+    const host = match[1] ?? match[2];
+    return host ? { host, port } : { port };
+}
+
+/** Addresses on which to accept connections.
+
+When the backend is started by systemd socket activation, it uses the sockets
+that it is given and nothing else. Otherwise, addresses are read from the
+`LISTEN_ADDRESSES` environment variable as a comma-separated list, so that the
+backend can bind several interfaces at once, e.g. `0.0.0.0:8000,[::]:8000` in a
+dual-stack container. An explicit IPv6 address only accepts IPv6 connections. A
+Unix domain socket, e.g. for a reverse proxy on the same host, is accessible to
+the owner and group of the backend process. Without the variable, the backend
+listens on the default port on all interfaces.
+ */
+export function getListenAddresses(defaultPort: number | string): ListenAddress[] {
+    const activated = getActivationSockets();
+    if (activated.length > 0) {
+        return activated;
+    }
+    const specs = process.env.LISTEN_ADDRESSES;
+    if (!specs) {
+        return [{ port: Number(defaultPort) }];
+    }
+    return specs
+        .split(",")
+        .filter((spec) => spec.trim())
+        .map(parseListenAddress);
+}
@@ -0,0 +1,226 @@
+import { isDeepStrictEqual } from "node:util";
+import type * as A from "@automerge/automerge-repo";
+
+import type { DocumentContent } from "./document.js";
+import { jsonLimitViolation } from "./json_limits.js";
+import type { Persistence } from "./persistence.js";
+
+/** Difference found between a live document and its stored autosave. */
+export type Discrepancy = {
+    refId: string;
+
+    /** What kind of drift was found:
+
+    - "unsaved": the live document has changes that were never autosaved
+    - "read-only-diverged": a mirror or system document was edited live
+    - "over-limits": the unsaved changes exceed the JSON limits
+    - "stale": the autosave was advanced by another writer while the live
+      document was unchanged
+    - "conflict": both the live document and the autosave changed since they
+      last agreed
+    - "error": the document could not be checked or repaired
+     */
+    kind: "unsaved" | "read-only-diverged" | "over-limits" | "stale" | "conflict" | "error";
+
+    /** Whether the sweep repaired the drift. */
+    resolved: boolean;
+
+    detail?: string;
+};
+
+/** Live document whose autosave was advanced by another writer while it was edited.
+
+Such writers include other backend instances and operations that write the
+database directly. Live changes to the document are not autosaved until the
+conflict is resolved, so that neither side is silently lost. Instead, the live
+content is recorded with the conflict, which survives restarts.
+ */
+export type Conflict = {
+    refId: string;
+    detectedAt: Date;
+};
+
+/** Message broadcast to the clients of a live document when a conflict is found. */
+export type ConflictWarning = {
+    type: "conflict";
+    refId: string;
+};
+
+/** Outcome of a consistency sweep. */
+export type SweepReport = {
+    startedAt: Date;
+    checked: number;
+    discrepancies: Discrepancy[];
+};
+
+/** Periodic check that live documents agree with their stored autosaves.
+
+Every change to a live document is autosaved, so they only drift apart when an
+autosave fails, for instance while the database is down. The sweep persists
+unsaved changes once they are older than `thresholdMs`, so as not to race with
+saves in flight. For read-only documents, which are never autosaved from live
+edits, it resets the live document to the stored content instead.
+
+To tell drift from concurrent writes, the sweep also tracks the stored content
+that each live document last agreed with. If the autosave has moved on since
+then, the live document is reset when it has not changed in the meantime and is
+otherwise marked as conflicting.
+ */
+export class ConsistencySweep {
+    private timer?: NodeJS.Timeout;
+    private lastChanged = new Map<string, number>();
+    private synced = new Map<string, string>();
+    private writes: Promise<void> = Promise.resolve();
+
+    /** Conflicts of live documents that have not yet been resolved. */
+    readonly conflicts = new Map<string, Conflict>();
+
+    /** Report of the most recent sweep, if any. */
+    lastReport: SweepReport | null = null;
+
+    constructor(
+        readonly db: Persistence,
+        readonly docs: Map<string, A.DocHandle<unknown>>,
+        /** Called to reset a live document to its stored content. */
+        readonly onDiverged: (refId: string, content: DocumentContent) => void,
+        readonly thresholdMs = 60_000,
+    ) {}
+
+    /** Record that a live document has changed. */
+    touch(refId: string) {
+        this.lastChanged.set(refId, Date.now());
+    }
+
+    /** Record that a live document agrees with the given stored content.
+
+    Resolves once any conflict of the document has been removed from the database.
+     */
+    markSynced(refId: string, stored: string): Promise<void> {
+        this.synced.set(refId, stored);
+        if (this.conflicts.delete(refId)) {
+            return this.persist(() => this.db.deleteConflict(refId));
+        }
+        return Promise.resolve();
+    }
+
+    /** Stored content that a live document last agreed with, if known. */
+    syncedContent(refId: string): string | undefined {
+        return this.synced.get(refId);
+    }
+
+    /** Stop tracking a live document that has been evicted, along with its conflict. */
+    forget(refId: string): Promise<void> {
+        this.lastChanged.delete(refId);
+        this.synced.delete(refId);
+        if (this.conflicts.delete(refId)) {
+            return this.persist(() => this.db.deleteConflict(refId));
+        }
+        return Promise.resolve();
+    }
+
+    /** Record a conflict, with the content of the live document, and warn its clients.
+
+    If the document is already in conflict, its recorded live content is updated.
+     */
+    markConflict(refId: string, doc: unknown): Promise<void> {
+        const content = JSON.stringify(doc);
+        if (!this.conflicts.has(refId)) {
+            console.error(`Live ref ${refId} conflicts with a concurrent write`);
+            this.conflicts.set(refId, { refId, detectedAt: new Date() });
+            const warning: ConflictWarning = { type: "conflict", refId };
+            this.docs.get(refId)?.broadcast(warning);
+        }
+        return this.persist(() => this.db.recordConflict(refId, content));
+    }
+
+    /** Track a conflict recorded in the database, for a live document loaded from it. */
+    restoreConflict(conflict: Conflict) {
+        this.conflicts.set(conflict.refId, conflict);
+    }
+
+    /** Write to the database after the writes already queued, logging failures. */
+    private persist(write: () => Promise<void>): Promise<void> {
+        this.writes = this.writes
+            .then(write)
+            .catch((e) => console.error("Failed to persist conflict:", e));
+        return this.writes;
+    }
+
+    async sweep(): Promise<SweepReport> {
+        const report: SweepReport = { startedAt: new Date(), checked: 0, discrepancies: [] };
+        for (const [refId, handle] of this.docs) {
+            const doc = handle.docSync();
+            if (doc === undefined) {
+                continue;
+            }
+            report.checked++;
+            const discrepancy = await this.check(refId, doc).catch(
+                (e): Discrepancy => ({ refId, kind: "error", resolved: false, detail: String(e) }),
+            );
+            if (discrepancy) {
+                console.error(`Live ref ${refId} drifted from database:`, discrepancy);
+                report.discrepancies.push(discrepancy);
+            }
+        }
+        this.lastReport = report;
+        return report;
+    }
+
+    private async check(refId: string, doc: unknown): Promise<Discrepancy | null> {
+        const stored = await this.db.getAutosave(refId);
+        if (sameContent(doc, stored)) {
+            return null;
+        }
+        if (await this.db.isReadOnly(refId)) {
+            this.onDiverged(refId, JSON.parse(stored));
+            return { refId, kind: "read-only-diverged", resolved: true };
+        }
+        if (this.conflicts.has(refId)) {
+            return { refId, kind: "conflict", resolved: false };
+        }
+        if (Date.now() - (this.lastChanged.get(refId) ?? 0) < this.thresholdMs) {
+            return null;
+        }
+        const synced = this.synced.get(refId);
+        if (synced !== undefined && !sameContent(JSON.parse(synced), stored)) {
+            if (sameContent(doc, synced)) {
+                this.onDiverged(refId, JSON.parse(stored));
+                await this.markSynced(refId, stored);
+                return { refId, kind: "stale", resolved: true };
+            }
+            await this.markConflict(refId, doc);
+            return { refId, kind: "conflict", resolved: false };
+        }
+        const violation = jsonLimitViolation(doc);
+        if (violation) {
+            return { refId, kind: "over-limits", resolved: false, detail: violation };
+        }
+        if (!(await this.db.autosaveWithExterns(refId, doc, stored))) {
+            // The autosave moved since it was read; check again in the next sweep.
+            return null;
+        }
+        await this.markSynced(refId, JSON.stringify(doc));
+        return { refId, kind: "unsaved", resolved: true };
+    }
+
+    /** Start sweeping at a fixed interval. */
+    start(intervalMs: number) {
+        this.stop();
+        this.timer = setInterval(() => this.sweep(), intervalMs);
+        this.timer.unref();
+    }
+
+    stop() {
+        clearInterval(this.timer);
+        this.timer = undefined;
+    }
+}
+
+/** Whether a document has the given stored content.
+
+Objects are compared regardless of the order of their keys, which can differ
+between a live document and content stored by another writer.
+ */
+export function sameContent(doc: unknown, stored: string): boolean {
+    return isDeepStrictEqual(JSON.parse(JSON.stringify(doc)), JSON.parse(stored));
+}
@@ -0,0 +1,26 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { CsvError, formatCsv, parseCsv } from "./csv.js";
+
+test("CSV", async (_t) => {
+    const rows = [
+        ["name", "value"],
+        ['a "quoted", name', "1.5"],
+        ["multi\nline", ""],
+    ];
+
+    await it("round trips fields that need quoting", () => {
+        assert.deepStrictEqual(parseCsv(formatCsv(rows)), rows);
+    });
+
+    await it("accepts LF line endings and skips blank lines", () => {
+        assert.deepStrictEqual(parseCsv("a,b\n\n1,2"), [
+            ["a", "b"],
+            ["1", "2"],
+        ]);
+    });
+
+    await it("rejects unterminated quotes", () => {
+        assert.throws(() => parseCsv('a,"b\n'), CsvError);
+    });
+});
@@ -0,0 +1,65 @@
+/** Format rows as CSV, quoting fields as in RFC 4180. */
+export function formatCsv(rows: string[][]): string {
+    return rows.map((row) => row.map(quoteField).join(",")).join("\r\n") + "\r\n";
+}
+
+function quoteField(field: string): string {
+    return /[",\r\n]/.test(field) ? `"${field.replaceAll('"', '""')}"` : field;
+}
+
+/** Error raised when CSV text is malformed. */
+export class CsvError extends Error {}
+
+/** Parse CSV text into rows of fields, as in RFC 4180.
+
+Both CRLF and LF line endings are accepted, and blank lines are skipped.
+ */
+export function parseCsv(text: string): string[][] {
+    const rows: string[][] = [];
+    let row: string[] = [];
+    let field = "";
+    let quoted = false;
+    let i = 0;
+    const endRow = () => {
+        row.push(field);
+        if (row.length > 1 || row[0] !== "") {
+            rows.push(row);
+        }
+        row = [];
+        field = "";
+    };
+    while (i < text.length) {
+        const c = text[i];
+        if (quoted) {
+            if (c === '"' && text[i + 1] === '"') {
+                field += '"';
+                i += 2;
+                continue;
+            } else if (c === '"') {
+                quoted = false;
+            } else {
+                field += c;
+            }
+        } else if (c === '"' && field === "") {
+            quoted = true;
+        } else if (c === ",") {
+            row.push(field);
+            field = "";
+        } else if (c === "\n" || c === "\r") {
+            endRow();
+            if (c === "\r" && text[i + 1] === "\n") {
+                i++;
+            }
+        } else {
+            field += c;
+        }
+        i++;
+    }
+    if (quoted) {
+        throw new CsvError("Unterminated quoted field");
+    }
+    if (field !== "" || row.length > 0) {
+        endRow();
+    }
+    return rows;
+}
@@ -1,11 +1,23 @@
-import * as fs from "node:fs";
+import { type SecretSource, parseSecretSource } from "./secrets.js";
 
-export function getDatabaseUrl(): string {
+/** Source of the database URL, as configured by the environment.
+
+The URL is taken from `DATABASE_URL`, from the file at `DATABASE_URL_PATH`, or
+from the secret source specified by `DATABASE_URL_SECRET`, in that order of
+precedence. See `parseSecretSource` for the supported secret sources.
+ */
+export function databaseUrlSource(): SecretSource {
     if (process.env.DATABASE_URL) {
-        return process.env.DATABASE_URL;
+        return parseSecretSource("env:DATABASE_URL");
     } else if (process.env.DATABASE_URL_PATH) {
-        return fs.readFileSync(process.env.DATABASE_URL_PATH, { encoding: "utf-8" });
+        return parseSecretSource(`file:${process.env.DATABASE_URL_PATH}`);
+    } else if (process.env.DATABASE_URL_SECRET) {
+        return parseSecretSource(process.env.DATABASE_URL_SECRET);
     } else {
-        throw "neither DATABASE_URL nor DATABASE_URL_PATH provided";
+        throw "none of DATABASE_URL, DATABASE_URL_PATH, or DATABASE_URL_SECRET provided";
     }
 }
+
+export async function getDatabaseUrl(): Promise<string> {
+    return databaseUrlSource().read();
+}
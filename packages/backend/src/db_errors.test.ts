@@ -0,0 +1,40 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { domainError } from "./db_errors.js";
+import { DatabaseUnavailableError } from "./resilience.js";
+
+function pgError(code: string, constraint?: string): Error {
+    return Object.assign(new Error(`error ${code}`), { code, constraint });
+}
+
+test("Database errors", async (_t) => {
+    await it("maps constraint violations to client errors", () => {
+        assert.strictEqual(domainError(pgError("23503"))?.code, "NOT_FOUND");
+        assert.strictEqual(domainError(pgError("23505"))?.code, "CONFLICT");
+        assert.strictEqual(domainError(pgError("22P02"))?.code, "BAD_REQUEST");
+    });
+
+    await it("explains violations of known constraints", () => {
+        const missingLink = domainError(pgError("23503", "externs_toref_fkey"));
+        assert.strictEqual(missingLink?.code, "BAD_REQUEST");
+        assert.strictEqual(missingLink?.message, "Document links to a ref that does not exist");
+        const duplicate = domainError(pgError("23505", "refs_pkey"));
+        assert.strictEqual(duplicate?.code, "CONFLICT");
+        assert.strictEqual(duplicate?.message, "A ref with this ID already exists");
+        const snapshot = domainError(pgError("23503", "pins_snapshot_fkey"));
+        assert.strictEqual(snapshot?.message, "Snapshot does not exist");
+        const other = domainError(pgError("23505", "unknown_key"));
+        assert.strictEqual(other?.message, "Record already exists");
+    });
+
+    await it("reports an unreachable database as unavailable", () => {
+        assert.strictEqual(domainError(pgError("ECONNREFUSED"))?.code, "SERVICE_UNAVAILABLE");
+        const open = new DatabaseUnavailableError("circuit open");
+        assert.strictEqual(domainError(open)?.code, "SERVICE_UNAVAILABLE");
+    });
+
+    await it("leaves other errors alone", () => {
+        assert.strictEqual(domainError(pgError("XX000")), undefined);
+        assert.strictEqual(domainError(new Error("oops")), undefined);
+    });
+});
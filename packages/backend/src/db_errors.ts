@@ -0,0 +1,68 @@
+import * as trpc from "@trpc/server";
+import { DatabaseUnavailableError, isConnectionError } from "./resilience.js";
+
+/** Client-facing errors for violations of the constraints that clients can run into,
+keyed by constraint name.
+
+Postgres names unnamed constraints after their table and columns, such as
+`pins_snapshot_fkey` for the foreign key of `pins.snapshot`.
+ */
+const errorsByConstraint: Record<string, [trpc.TRPC_ERROR_CODE_KEY, string]> = {
+    refs_pkey: ["CONFLICT", "A ref with this ID already exists"],
+    witnesses_forref_fkey: ["NOT_FOUND", "Ref does not exist"],
+    witnesses_snapshot_fkey: ["NOT_FOUND", "Snapshot does not exist"],
+    refs_autosave_fkey: ["NOT_FOUND", "Snapshot does not exist"],
+    externs_fromref_fkey: ["NOT_FOUND", "Ref does not exist"],
+    externs_toref_fkey: ["BAD_REQUEST", "Document links to a ref that does not exist"],
+    externs_is_relation: ["BAD_REQUEST", "Document links to the same ref more than once"],
+    mirrors_pkey: ["CONFLICT", "Ref is already a mirror"],
+    mirrors_ref_fkey: ["NOT_FOUND", "Ref does not exist"],
+    system_documents_pkey: ["CONFLICT", "A system document with this slug already exists"],
+    system_documents_ref_key: ["CONFLICT", "Ref is already a system document"],
+    pins_ref_fkey: ["NOT_FOUND", "Ref does not exist"],
+    pins_snapshot_fkey: ["NOT_FOUND", "Snapshot does not exist"],
+    attachments_ref_fkey: ["NOT_FOUND", "Ref does not exist"],
+    attachments_size_check: ["BAD_REQUEST", "Attachment size must not be negative"],
+    permalinks_ref_fkey: ["NOT_FOUND", "Ref does not exist"],
+    permalinks_snapshot_fkey: ["NOT_FOUND", "Snapshot does not exist"],
+    permissions_ref_fkey: ["NOT_FOUND", "Ref does not exist"],
+    permissions_level_check: ["BAD_REQUEST", "Unknown access level"],
+};
+
+/** Client-facing errors for other database errors, keyed by SQLSTATE code. */
+const errorsBySqlState: Record<string, [trpc.TRPC_ERROR_CODE_KEY, string]> = {
+    "22001": ["BAD_REQUEST", "Value too long"],
+    "22P02": ["BAD_REQUEST", "Malformed value"],
+    "23502": ["BAD_REQUEST", "Missing required value"],
+    "23503": ["NOT_FOUND", "Referenced record does not exist"],
+    "23505": ["CONFLICT", "Record already exists"],
+    "23514": ["BAD_REQUEST", "Value violates a constraint"],
+    "40001": ["CONFLICT", "Conflicting concurrent update, please retry"],
+    "40P01": ["CONFLICT", "Conflicting concurrent update, please retry"],
+    "57014": ["TIMEOUT", "Database query took too long"],
+};
+
+/** Translate a database error into an error that a client can act on.
+
+Violations of known constraints are reported with what they mean for the client,
+and other errors by their class. Returns `undefined` if the error does not come
+from the database or has no more specific meaning than an internal server error.
+ */
+export function domainError(e: unknown): trpc.TRPCError | undefined {
+    if (e instanceof DatabaseUnavailableError || isConnectionError(e)) {
+        return new trpc.TRPCError({
+            code: "SERVICE_UNAVAILABLE",
+            message: "Database unavailable",
+            cause: e,
+        });
+    }
+    const { code, constraint } = (e ?? {}) as { code?: unknown; constraint?: unknown };
+    const known =
+        (typeof constraint === "string" ? errorsByConstraint[constraint] : undefined) ??
+        (typeof code === "string" ? errorsBySqlState[code] : undefined);
+    if (known) {
+        const [trpcCode, message] = known;
+        return new trpc.TRPCError({ code: trpcCode, message, cause: e });
+    }
+    return undefined;
+}
@@ -0,0 +1,26 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { ModelDocument, typedDocumentError } from "./document.js";
+import { loadSystemDocuments } from "./system_documents.js";
+
+test("Typed documents", async (_t) => {
+    await it("parses bundled example models", async () => {
+        for (const { content } of await loadSystemDocuments()) {
+            if (content.type === "model") {
+                const model = ModelDocument.parse(content);
+                assert.ok(model.notebook.cells.length > 0);
+            }
+        }
+    });
+
+    await it("rejects malformed documents of known types", () => {
+        const model = { type: "model", name: "M", notebook: { cells: [{ tag: "formal" }] } };
+        assert.notStrictEqual(typedDocumentError(model), null);
+        const empty = { type: "model", name: "M", notebook: { cells: [] } };
+        assert.strictEqual(typedDocumentError(empty), null);
+    });
+
+    await it("accepts documents of unknown types", () => {
+        assert.strictEqual(typedDocumentError({ type: "diagram", name: "D" }), null);
+    });
+});
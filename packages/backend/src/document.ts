@@ -0,0 +1,142 @@
+import * as z from "zod";
+import { refineJsonLimits } from "./json_limits.js";
+import { Extern } from "./links.js";
+
+/** Fields shared by every CatColab document.
+
+The full schema of each document type is owned by the frontend. The backend only
+relies on the fields that it needs for its own bookkeeping and passes everything
+else through untouched, after checking that it is within the limits in
+`json_limits.ts`.
+ */
+export const DocumentContent = z
+    .object({
+        /// The type of document, e.g. "model" or "analysis"
+        type: z.string(),
+        /// User-defined name of the document
+        name: z.string(),
+    })
+    .passthrough()
+    .superRefine(refineJsonLimits);
+
+export type DocumentContent = z.infer<typeof DocumentContent>;
+
+/** Types of document that the backend knows how to handle. */
+export const documentTypes = ["model", "analysis"];
+
+// Typed schemas for the known document types. These mirror the types in the
+// frontend's `document`, `notebook`, and `model` modules, for use where the backend
+// needs to understand content, such as when validating imported documents. Unknown
+// fields are passed through so that content written by newer frontends survives.
+
+/** Object in a model, identified by a tagged value. */
+const Ob = z
+    .object({
+        /// Kind of object, e.g. "Basic" for a generating object
+        tag: z.string(),
+        /// For a basic object, the ID of its declaration
+        content: z.unknown(),
+    })
+    .passthrough();
+
+export const ObjectDecl = z
+    .object({
+        tag: z.literal("object"),
+        id: z.string(),
+        /// Human-readable name of object
+        name: z.string(),
+        obType: z.unknown(),
+    })
+    .passthrough();
+
+export const MorphismDecl = z
+    .object({
+        tag: z.literal("morphism"),
+        id: z.string(),
+        /// Human-readable name of morphism
+        name: z.string(),
+        morType: z.unknown(),
+        dom: Ob.nullable(),
+        cod: Ob.nullable(),
+    })
+    .passthrough();
+
+export const ModelJudgment = z.discriminatedUnion("tag", [ObjectDecl, MorphismDecl]);
+
+export type ModelJudgment = z.infer<typeof ModelJudgment>;
+
+export const ModelAnalysis = z
+    .object({
+        /// Identifier of the analysis, unique relative to the theory
+        id: z.string(),
+        /// Content associated with the analysis, tagged by its kind
+        content: z.object({ tag: z.string() }).passthrough(),
+    })
+    .passthrough();
+
+export type ModelAnalysis = z.infer<typeof ModelAnalysis>;
+
+const RichTextCell = z.object({
+    tag: z.literal("rich-text"),
+    id: z.string(),
+    content: z.string(),
+});
+
+const StemCell = z.object({
+    tag: z.literal("stem"),
+    id: z.string(),
+});
+
+/** Notebook whose formal cells have content of the given type. */
+function notebook<T extends z.ZodTypeAny>(content: T) {
+    const FormalCell = z.object({
+        tag: z.literal("formal"),
+        id: z.string(),
+        content,
+    });
+    return z.object({
+        cells: z.array(z.discriminatedUnion("tag", [RichTextCell, FormalCell, StemCell])),
+    });
+}
+
+export const ModelDocument = z
+    .object({
+        type: z.literal("model"),
+        name: z.string(),
+        /// Identifier of double theory that the model is of
+        theory: z.string().optional(),
+        notebook: notebook(ModelJudgment),
+    })
+    .passthrough();
+
+export type ModelDocument = z.infer<typeof ModelDocument>;
+
+export const AnalysisDocument = z
+    .object({
+        type: z.literal("analysis"),
+        name: z.string(),
+        /// Reference to the model that the analysis is of
+        modelRef: z.object({ __extern__: Extern }),
+        notebook: notebook(ModelAnalysis),
+    })
+    .passthrough();
+
+export type AnalysisDocument = z.infer<typeof AnalysisDocument>;
+
+/** Document of one of the known types, discriminated by its `type` field. */
+export const TypedDocument = z.discriminatedUnion("type", [ModelDocument, AnalysisDocument]);
+
+export type TypedDocument = z.infer<typeof TypedDocument>;
+
+/** Check that a document of a known type has the structure of that type.
+
+Documents of other types are accepted as is, since their schema is unknown to
+the backend. Returns an error message if the check fails.
+ */
+export function typedDocumentError(content: DocumentContent): string | null {
+    if (!documentTypes.includes(content.type)) {
+        return null;
+    }
+    const result = TypedDocument.safeParse(content);
+    return result.success ? null : result.error.message;
+}
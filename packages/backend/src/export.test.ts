@@ -0,0 +1,104 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { ExportError, exportDocument, negotiateFormat, parseFormat } from "./export.js";
+import { loadSystemDocuments } from "./system_documents.js";
+
+const refId = "01912345-6789-7abc-8def-0123456789ab";
+
+const model = {
+    type: "model",
+    name: 'Pipes | "quotes"',
+    theory: "simple-olog",
+    notebook: {
+        cells: [
+            {
+                tag: "formal",
+                id: "c1",
+                content: {
+                    tag: "object",
+                    id: "x",
+                    name: "a|b",
+                    obType: { tag: "Basic", content: "Object" },
+                },
+            },
+            {
+                tag: "formal",
+                id: "c2",
+                content: {
+                    tag: "morphism",
+                    id: "f",
+                    name: "loop",
+                    dom: { tag: "Basic", content: "x" },
+                    cod: null,
+                    morType: { tag: "Hom", content: { tag: "Basic", content: "Object" } },
+                },
+            },
+        ],
+    },
+};
+
+async function olog() {
+    const docs = await loadSystemDocuments();
+    const doc = docs.find((doc) => doc.slug === "olog-pair-of-shoes");
+    assert.ok(doc);
+    return doc.content;
+}
+
+test("Export", async (_t) => {
+    await it("exports models to Graphviz DOT", async () => {
+        const dot = exportDocument(await olog(), "dot", refId);
+        assert.ok(dot.startsWith('digraph "Example: a pair of shoes" {'));
+        assert.ok(dot.includes('[label="a pair of shoes"];'));
+        assert.strictEqual(dot.match(/ -> /g)?.length, 2);
+        assert.ok(dot.includes('[label="has as left shoe"];'));
+    });
+
+    await it("exports models to JSON-LD", async () => {
+        const doc = JSON.parse(exportDocument(await olog(), "jsonld", refId));
+        assert.strictEqual(doc["@id"], `urn:uuid:${refId}`);
+        assert.strictEqual(doc.theory, "simple-olog");
+        assert.strictEqual(doc.objects.length, 2);
+        assert.strictEqual(doc.morphisms.length, 2);
+        const ids = new Set(doc.objects.map((ob: { "@id": string }) => ob["@id"]));
+        for (const mor of doc.morphisms) {
+            assert.ok(ids.has(mor.dom) && ids.has(mor.cod));
+            assert.strictEqual(mor.morType, "Hom(Object)");
+        }
+    });
+
+    await it("exports models to Markdown tables", async () => {
+        const md = exportDocument(await olog(), "markdown", refId);
+        assert.ok(md.startsWith("# Example: a pair of shoes\n"));
+        assert.ok(md.includes("| a shoe | Object |"));
+        assert.ok(md.includes("| has as left shoe | a pair of shoes | a shoe | Hom(Object) |"));
+    });
+
+    await it("escapes names and skips incomplete morphisms", () => {
+        const md = exportDocument(model, "markdown", refId);
+        assert.ok(md.includes("| a\\|b | Object |"));
+        assert.ok(md.includes("| loop | a\\|b |  | Hom(Object) |"));
+        const dot = exportDocument(model, "dot", refId);
+        assert.ok(dot.startsWith('digraph "Pipes | \\"quotes\\"" {'));
+        assert.ok(!dot.includes("->"));
+    });
+
+    await it("rejects documents other than models", () => {
+        const analysis = { type: "analysis", name: "Analysis", notebook: { cells: [] } };
+        assert.throws(() => exportDocument(analysis, "dot", refId), ExportError);
+        assert.throws(() => exportDocument({ type: "diagram" }, "markdown", refId), ExportError);
+        assert.throws(() => exportDocument({ type: "model" }, "jsonld", refId), ExportError);
+    });
+
+    await it("parses the format named by the client", () => {
+        assert.strictEqual(parseFormat("dot"), "dot");
+        assert.strictEqual(parseFormat("pdf"), null);
+        assert.strictEqual(parseFormat("toString"), null);
+    });
+
+    await it("negotiates the format", () => {
+        assert.strictEqual(negotiateFormat(undefined), "markdown");
+        assert.strictEqual(negotiateFormat("application/ld+json"), "jsonld");
+        assert.strictEqual(negotiateFormat("text/markdown;q=0.5, text/vnd.graphviz"), "dot");
+        assert.strictEqual(negotiateFormat("application/pdf"), null);
+    });
+});
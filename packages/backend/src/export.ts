@@ -0,0 +1,192 @@
+import { ModelDocument, type ModelJudgment } from "./document.js";
+
+/** Interchange formats to which documents can be exported, with their media types. */
+export const exportFormats = {
+    dot: "text/vnd.graphviz",
+    jsonld: "application/ld+json",
+    markdown: "text/markdown",
+};
+
+export type ExportFormat = keyof typeof exportFormats;
+
+/** Error raised when a document cannot be exported. */
+export class ExportError extends Error {}
+
+/** Get the export format named by the `format` query parameter, if it is one. */
+export function parseFormat(format: string): ExportFormat | null {
+    return Object.hasOwn(exportFormats, format) ? (format as ExportFormat) : null;
+}
+
+/** Choose the format of an export from the `Accept` header.
+
+Returns null if the client accepts none of the formats. A client that accepts
+anything gets Markdown.
+ */
+export function negotiateFormat(accept: string | undefined): ExportFormat | null {
+    const ranges = (accept || "*/*")
+        .split(",")
+        .map((range) => {
+            const [type = "", ...params] = range.split(";").map((s) => s.trim());
+            const q = params.find((p) => p.startsWith("q="));
+            return { type: type.toLowerCase(), q: q ? Number(q.slice(2)) : 1 };
+        })
+        .filter((range) => range.q > 0)
+        .sort((a, b) => b.q - a.q);
+    for (const { type } of ranges) {
+        if (type === "*/*" || type === "text/*") {
+            return "markdown";
+        }
+        const match = Object.entries(exportFormats).find(([_, mediaType]) => mediaType === type);
+        if (match) {
+            return match[0] as ExportFormat;
+        }
+    }
+    return null;
+}
+
+/** Objects and morphisms declared in a model, in the order of its notebook. */
+type ModelContents = {
+    model: ModelDocument;
+    objects: Extract<ModelJudgment, { tag: "object" }>[];
+    morphisms: Extract<ModelJudgment, { tag: "morphism" }>[];
+    names: Map<string, string>;
+};
+
+function modelContents(content: unknown): ModelContents {
+    const type = (content as { type?: unknown } | null)?.type;
+    if (type !== "model") {
+        throw new ExportError(`Cannot export documents of type ${String(type)}`);
+    }
+    const result = ModelDocument.safeParse(content);
+    if (!result.success) {
+        throw new ExportError("Model is malformed");
+    }
+    const model = result.data;
+    const judgments = model.notebook.cells.flatMap((cell) =>
+        cell.tag === "formal" ? [cell.content] : [],
+    );
+    const objects = judgments.filter((j) => j.tag === "object");
+    const morphisms = judgments.filter((j) => j.tag === "morphism");
+    const names = new Map<string, string>(judgments.map((j) => [j.id, j.name]));
+    return { model, objects, morphisms, names };
+}
+
+/** ID of the object declared by a basic object of a model, if it is one. */
+export function basicObjectId(ob: unknown): string | null {
+    const { tag, content } = (ob ?? {}) as { tag?: unknown; content?: unknown };
+    return tag === "Basic" && typeof content === "string" ? content : null;
+}
+
+/** Human-readable label of an object or morphism type. */
+export function typeLabel(type: unknown): string {
+    const { tag, content } = (type ?? {}) as { tag?: unknown; content?: unknown };
+    if (tag === "Basic" && typeof content === "string") {
+        return content;
+    }
+    if (typeof tag === "string") {
+        return content === undefined ? tag : `${tag}(${typeLabel(content)})`;
+    }
+    return "";
+}
+
+/** Export a document in an interchange format.
+
+Only models can be exported. Morphisms whose domain or codomain is missing, or
+is not a basic object, are left out of formats that draw them as edges.
+ */
+export function exportDocument(content: unknown, format: ExportFormat, refId: string): string {
+    const contents = modelContents(content);
+    switch (format) {
+        case "dot":
+            return modelToDot(contents);
+        case "jsonld":
+            return JSON.stringify(modelToJsonLd(contents, refId), null, 2);
+        case "markdown":
+            return modelToMarkdown(contents);
+    }
+}
+
+function dotString(s: string): string {
+    return `"${s.replaceAll("\\", "\\\\").replaceAll('"', '\\"').replaceAll("\n", "\\n")}"`;
+}
+
+function modelToDot({ model, objects, morphisms }: ModelContents): string {
+    const lines = [`digraph ${dotString(model.name)} {`];
+    for (const ob of objects) {
+        lines.push(`    ${dotString(ob.id)} [label=${dotString(ob.name)}];`);
+    }
+    for (const mor of morphisms) {
+        const dom = basicObjectId(mor.dom);
+        const cod = basicObjectId(mor.cod);
+        if (dom !== null && cod !== null) {
+            const edge = `${dotString(dom)} -> ${dotString(cod)}`;
+            lines.push(`    ${edge} [label=${dotString(mor.name)}];`);
+        }
+    }
+    lines.push("}");
+    return `${lines.join("\n")}\n`;
+}
+
+/** Vocabulary of the terms used in JSON-LD exports. */
+const jsonLdContext = {
+    "@vocab": "https://catcolab.org/vocab#",
+    name: "https://schema.org/name",
+    dom: { "@type": "@id" },
+    cod: { "@type": "@id" },
+};
+
+function modelToJsonLd({ model, objects, morphisms }: ModelContents, refId: string) {
+    const base = `urn:uuid:${refId}`;
+    const ref = (id: string | null) => (id === null ? null : `${base}#${id}`);
+    return {
+        "@context": jsonLdContext,
+        "@id": base,
+        "@type": "Model",
+        name: model.name,
+        theory: model.theory ?? null,
+        objects: objects.map((ob) => ({
+            "@id": ref(ob.id),
+            "@type": "Object",
+            name: ob.name,
+            obType: typeLabel(ob.obType),
+        })),
+        morphisms: morphisms.map((mor) => ({
+            "@id": ref(mor.id),
+            "@type": "Morphism",
+            name: mor.name,
+            morType: typeLabel(mor.morType),
+            dom: ref(basicObjectId(mor.dom)),
+            cod: ref(basicObjectId(mor.cod)),
+        })),
+    };
+}
+
+function markdownCell(s: string): string {
+    return s.replaceAll("\\", "\\\\").replaceAll("|", "\\|").replaceAll("\n", " ");
+}
+
+function markdownTable(header: string[], rows: string[][]): string[] {
+    return [header, header.map(() => "---"), ...rows].map(
+        (row) => `| ${row.map(markdownCell).join(" | ")} |`,
+    );
+}
+
+function modelToMarkdown({ model, objects, morphisms, names }: ModelContents): string {
+    const obName = (ob: unknown) => names.get(basicObjectId(ob) ?? "") ?? "";
+    const objectRows = objects.map((ob) => [ob.name, typeLabel(ob.obType)]);
+    const morphismRows = morphisms.map((mor) => [
+        mor.name,
+        obName(mor.dom),
+        obName(mor.cod),
+        typeLabel(mor.morType),
+    ]);
+    const morphismHeader = ["Name", "Domain", "Codomain", "Type"];
+
+    const lines = [`# ${markdownCell(model.name)}`, ""];
+    if (model.theory) {
+        lines.push(`Theory: ${markdownCell(model.theory)}`, "");
+    }
+    lines.push("## Objects", "", ...markdownTable(["Name", "Type"], objectRows));
+    lines.push("", "## Morphisms", "", ...markdownTable(morphismHeader, morphismRows));
+    return `${lines.join("\n")}\n`;
+}
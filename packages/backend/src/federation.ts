@@ -0,0 +1,108 @@
+import { DocumentContent, typedDocumentError } from "./document.js";
+import { type Extern, traverseExterns } from "./links.js";
+import type { Persistence } from "./persistence.js";
+import { RemoteFetchError, fetchRemoteJson } from "./remote_fetch.js";
+
+/** Fetch a document from a remote URL and check that it is a CatColab document. */
+export async function fetchRemoteDocument(url: string): Promise<DocumentContent> {
+    const result = DocumentContent.safeParse(await fetchRemoteJson(url));
+    if (!result.success) {
+        throw new RemoteFetchError(`Not a CatColab document: ${result.error.message}`);
+    }
+    const typeError = typedDocumentError(result.data);
+    if (typeError) {
+        throw new RemoteFetchError(`Malformed ${result.data.type} document: ${typeError}`);
+    }
+    return result.data;
+}
+
+/** Check that a remote document only refers to refs on this instance.
+
+Links are recorded as foreign keys when the document is saved, so a link to an
+unknown ref, such as one on the instance that the document came from, could never
+be saved.
+ */
+export async function checkRemoteExterns(db: Persistence, content: DocumentContent) {
+    const externs: Extern[] = [];
+    traverseExterns(content, (e) => externs.push(e));
+    for (const e of externs) {
+        if (!(await db.hasRef(e.refId))) {
+            throw new RemoteFetchError(`Document refers to unknown ref ${e.refId}`);
+        }
+    }
+}
+
+/** Read-only mirrors of documents hosted elsewhere, such as on other instances.
+
+A mirror is an ordinary ref whose content is owned by a remote source. It is
+refreshed periodically, or on demand when the source calls the refresh webhook.
+Like any document, a mirror may only link to refs on this instance.
+Local edits to a mirror are never persisted and are overwritten by the next
+change at the source.
+ */
+export class Federation {
+    private timer?: NodeJS.Timeout;
+
+    constructor(
+        readonly db: Persistence,
+        /** Called when the content of a mirror changes, to update live documents. */
+        readonly onUpdate: (refId: string, content: DocumentContent) => void,
+    ) {}
+
+    /** Subscribe to a remote document, returning the ref of the new mirror.
+
+    The mirror belongs to the given owner, or to no one if authentication is off.
+     */
+    async subscribe(sourceUrl: string, owner: string | null = null): Promise<string> {
+        const content = await fetchRemoteDocument(sourceUrl);
+        await checkRemoteExterns(this.db, content);
+        const refId = await this.db.newRef(content.name, owner);
+        await this.db.newMirror(refId, sourceUrl);
+        await this.db.autosaveWithExterns(refId, content);
+        return refId;
+    }
+
+    /** Fetch a mirror from its source, returning whether its content changed. */
+    async refresh(refId: string): Promise<boolean> {
+        const mirror = await this.db.getMirror(refId);
+        if (!mirror) {
+            throw new Error(`Ref ${refId} is not a mirror`);
+        }
+        let content: DocumentContent;
+        try {
+            content = await fetchRemoteDocument(mirror.sourceurl);
+            await checkRemoteExterns(this.db, content);
+        } catch (e) {
+            await this.db.setMirrorStatus(refId, e instanceof Error ? e.message : String(e));
+            throw e;
+        }
+        const serialized = JSON.stringify(content);
+        const changed = serialized !== (await this.db.getAutosave(refId));
+        if (changed) {
+            await this.db.autosaveWithExterns(refId, content);
+            this.onUpdate(refId, content);
+        }
+        await this.db.setMirrorStatus(refId, null);
+        return changed;
+    }
+
+    async refreshAll(): Promise<void> {
+        for (const mirror of await this.db.allMirrors()) {
+            await this.refresh(mirror.ref).catch((e) => {
+                console.error(`failed to refresh mirror ${mirror.ref}: ${e}`);
+            });
+        }
+    }
+
+    /** Start refreshing all mirrors at a fixed interval. */
+    start(intervalMs: number) {
+        this.stop();
+        this.timer = setInterval(() => this.refreshAll(), intervalMs);
+        this.timer.unref();
+    }
+
+    stop() {
+        clearInterval(this.timer);
+        this.timer = undefined;
+    }
+}
@@ -0,0 +1,53 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { MessageRateLimiter, defaultFloodControlOptions } from "./flood_control.js";
+
+test("Flood control", async (_t) => {
+    const options = {
+        ...defaultFloodControlOptions,
+        messagesPerSecond: 10,
+        burst: 5,
+        baseBackoffMs: 1000,
+        maxStrikes: 2,
+    };
+
+    await it("accepts bursts up to the limit and refills over time", () => {
+        let now = 0;
+        const limiter = new MessageRateLimiter(options, () => now);
+        for (let i = 0; i < 5; i++) {
+            assert.strictEqual(limiter.check(), "accept");
+        }
+        assert.strictEqual(limiter.check(), "drop");
+        now = 1000;
+        assert.strictEqual(limiter.check(), "accept");
+    });
+
+    await it("backs off further with each violation and then disconnects", () => {
+        let now = 0;
+        const limiter = new MessageRateLimiter({ ...options, messagesPerSecond: 0 }, () => now);
+        for (let i = 0; i < 5; i++) {
+            limiter.check();
+        }
+        assert.strictEqual(limiter.check(), "drop");
+        now = 999;
+        assert.strictEqual(limiter.check(), "drop");
+        now = 1000;
+        assert.strictEqual(limiter.check(), "drop");
+        now = 2999;
+        assert.strictEqual(limiter.check(), "drop");
+        now = 3000;
+        assert.strictEqual(limiter.check(), "disconnect");
+    });
+
+    await it("forgives violations once the client calms down", () => {
+        let now = 0;
+        const limiter = new MessageRateLimiter(options, () => now);
+        for (const start of [0, 10_000, 20_000]) {
+            now = start;
+            for (let i = 0; i < 5; i++) {
+                assert.strictEqual(limiter.check(), "accept");
+            }
+            assert.strictEqual(limiter.check(), "drop");
+        }
+    });
+});
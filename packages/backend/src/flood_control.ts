@@ -0,0 +1,99 @@
+import type * as ws from "ws";
+
+/** Limits on the messages that a client may send over a sync socket. */
+export type FloodControlOptions = {
+    /** Sustained number of messages allowed per second. */
+    messagesPerSecond: number;
+
+    /** Number of messages that may be sent in a burst above the sustained rate. */
+    burst: number;
+
+    /** Maximum size of a single message, in bytes. */
+    maxMessageBytes: number;
+
+    /** Time for which messages are dropped after the first violation, doubling
+    with each further violation. */
+    baseBackoffMs: number;
+
+    /** Number of violations after which the client is disconnected. */
+    maxStrikes: number;
+};
+
+export const defaultFloodControlOptions: FloodControlOptions = {
+    messagesPerSecond: 50,
+    burst: 500,
+    maxMessageBytes: 16 * 1024 * 1024,
+    baseBackoffMs: 1000,
+    maxStrikes: 5,
+};
+
+/** What to do with a message from a client. */
+export type Verdict = "accept" | "drop" | "disconnect";
+
+/** Token bucket limiting the rate of messages from one client, with escalating
+backoff for clients that keep exceeding it.
+
+When the bucket is empty, messages are dropped for a backoff period that doubles
+with each violation, and after `maxStrikes` violations the client should be
+disconnected. Violations are forgiven once the bucket has refilled completely.
+ */
+export class MessageRateLimiter {
+    private tokens: number;
+    private lastRefill: number;
+    private strikes = 0;
+    private blockedUntil = 0;
+
+    constructor(
+        readonly options: FloodControlOptions = defaultFloodControlOptions,
+        readonly now: () => number = Date.now,
+    ) {
+        this.tokens = options.burst;
+        this.lastRefill = now();
+    }
+
+    check(): Verdict {
+        const { messagesPerSecond, burst, baseBackoffMs, maxStrikes } = this.options;
+        const t = this.now();
+        const refill = ((t - this.lastRefill) / 1000) * messagesPerSecond;
+        this.tokens = Math.min(burst, this.tokens + refill);
+        this.lastRefill = t;
+        if (this.tokens >= burst) {
+            this.strikes = 0;
+        }
+        if (t < this.blockedUntil) {
+            return "drop";
+        }
+        if (this.tokens >= 1) {
+            this.tokens -= 1;
+            return "accept";
+        }
+        this.strikes++;
+        if (this.strikes > maxStrikes) {
+            return "disconnect";
+        }
+        this.blockedUntil = t + baseBackoffMs * 2 ** (this.strikes - 1);
+        return "drop";
+    }
+}
+
+/** Apply flood control to the messages received on a WebSocket.
+
+Messages over the limit are dropped before they reach any listener, such as the
+Automerge network adapter. Messages that are too large are rejected by the
+`maxPayload` option of the WebSocket server instead.
+ */
+export function limitMessages(socket: ws.WebSocket, limiter: MessageRateLimiter) {
+    const emit = socket.emit.bind(socket);
+    socket.emit = ((event: string | symbol, ...args: unknown[]) => {
+        if (event === "message") {
+            const verdict = limiter.check();
+            if (verdict === "disconnect") {
+                socket.close(1008, "Too many messages");
+            }
+            if (verdict !== "accept") {
+                return false;
+            }
+        }
+        return emit(event, ...args);
+    }) as typeof socket.emit;
+}
@@ -1,9 +1,21 @@
+import { databaseUrlSource } from "./database_url.js";
+import { RotatingSecret } from "./secrets.js";
 import { Server } from "./server.js";
 
-const server = new Server();
+const databaseUrl = new RotatingSecret(databaseUrlSource());
+await databaseUrl.load();
+
+const server = new Server(databaseUrl.value);
 
 export type AppRouter = typeof server.appRouter;
 
+// Pick up rotated database credentials without restarting.
+databaseUrl.onChange((url) => {
+    console.log(`Database URL changed in ${databaseUrl.source.description}, reconnecting`);
+    server.db.setUrl(url).catch((e) => console.error("Failed to close old database pool:", e));
+});
+databaseUrl.start(Number(process.env.DATABASE_URL_REFRESH_SECONDS || 300) * 1000);
+
 process.once("SIGINT", (_code) => {
     console.log("SIGINT received...");
     server.close();
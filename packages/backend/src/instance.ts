@@ -0,0 +1,45 @@
+import * as fs from "node:fs/promises";
+import { getDatabaseUrl } from "./database_url.js";
+import { ArchiveError, InstanceArchive } from "./instance_archive.js";
+import { Persistence } from "./persistence.js";
+import { withQueryLimits } from "./query_limits.js";
+
+// Export the whole database to a file, or import such a file into a fresh database:
+//
+//     npm run instance -- export catcolab.json
+//     npm run instance -- import catcolab.json
+async function main([command, file]: string[]) {
+    if (!(command === "export" || command === "import") || !file) {
+        console.error("usage: instance (export|import) <file>");
+        process.exit(2);
+    }
+    const db = new Persistence(await getDatabaseUrl());
+    try {
+        if (command === "export") {
+            const archive = await db.exportInstance();
+            await fs.writeFile(file, JSON.stringify(archive));
+            const refs = archive.tables.refs?.length ?? 0;
+            console.info(`exported ${refs} refs to ${file}`);
+        } else {
+            const archive = InstanceArchive.parse(
+                JSON.parse(await fs.readFile(file, { encoding: "utf-8" })),
+            );
+            const imported = await db.importInstance(archive);
+            for (const [table, count] of Object.entries(imported)) {
+                console.info(`imported ${count} rows into ${table}`);
+            }
+        }
+    } catch (e) {
+        if (e instanceof ArchiveError) {
+            console.error(e.message);
+            process.exitCode = 1;
+        } else {
+            throw e;
+        }
+    } finally {
+        await db.close();
+    }
+}
+
+// Statements over whole tables may run for longer than any request.
+await withQueryLimits({ timeoutMs: 0 }, () => main(process.argv.slice(2)));
@@ -0,0 +1,142 @@
+import type pg from "pg";
+import { z } from "zod";
+
+/** Tables included in an instance archive, in an order that respects their
+foreign keys. Tables with identity columns keep their IDs on import.
+ */
+const archiveTables = [
+    { name: "snapshots", identity: true },
+    { name: "refs", identity: false },
+    { name: "witnesses", identity: true },
+    { name: "externs", identity: false },
+    { name: "mirrors", identity: false },
+    { name: "jobs", identity: true },
+    { name: "system_documents", identity: false },
+    { name: "ref_views", identity: false },
+    { name: "pins", identity: false },
+    { name: "attachments", identity: true },
+    { name: "autosave_log", identity: true },
+    { name: "collab_sessions", identity: true },
+    { name: "permalinks", identity: false },
+    { name: "audit_log", identity: true },
+    { name: "users", identity: false },
+    { name: "permissions", identity: false },
+    { name: "conflicts", identity: false },
+    { name: "live_documents", identity: false },
+];
+
+/** Portable archive of the whole database of an instance.
+
+Rows are stored as JSON objects keyed by column name. The archive records the
+migrations applied to the source, and can only be imported into a database with
+the same migrations. Objects in attachment storage are not included and must be
+copied separately.
+ */
+export const InstanceArchive = z.object({
+    format: z.literal("catcolab-instance"),
+    version: z.literal(1),
+    exportedAt: z.string(),
+    migrations: z.array(z.string()),
+    tables: z.record(z.string(), z.array(z.record(z.string(), z.unknown()))),
+});
+
+export type InstanceArchive = z.infer<typeof InstanceArchive>;
+
+/** Error raised when an archive cannot be imported into a database. */
+export class ArchiveError extends Error {}
+
+async function appliedMigrations(client: pg.ClientBase): Promise<string[]> {
+    const result = await client.query("SELECT name FROM migrations");
+    return result.rows.map((row) => row.name as string).sort();
+}
+
+/** Export every table of the database, in a transaction that sees a consistent
+snapshot of it.
+ */
+export async function exportInstance(client: pg.ClientBase): Promise<InstanceArchive> {
+    await client.query("BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY");
+    try {
+        const tables: InstanceArchive["tables"] = {};
+        for (const { name } of archiveTables) {
+            const result = await client.query(`
+                SELECT COALESCE(json_agg(t), '[]'::json) AS data
+                FROM (SELECT * FROM ${name} ORDER BY 1) t
+            `);
+            tables[name] = result.rows[0].data;
+        }
+        const migrations = await appliedMigrations(client);
+        await client.query("COMMIT");
+        return {
+            format: "catcolab-instance",
+            version: 1,
+            exportedAt: new Date().toISOString(),
+            migrations,
+            tables,
+        };
+    } catch (e) {
+        await client.query("ROLLBACK");
+        throw e;
+    }
+}
+
+/** Import an archive into a fresh database, preserving all IDs.
+
+A database is fresh if it holds no refs besides system documents, which are
+replaced by those in the archive. Returns the number of rows imported into each
+table.
+ */
+export async function importInstance(
+    client: pg.ClientBase,
+    archive: InstanceArchive,
+): Promise<Record<string, number>> {
+    const unknown = Object.keys(archive.tables).filter(
+        (name) => !archiveTables.some((table) => table.name === name),
+    );
+    if (unknown.length > 0) {
+        throw new ArchiveError(`Archive contains unknown tables: ${unknown.join(", ")}`);
+    }
+    await client.query("BEGIN");
+    try {
+        const migrations = await appliedMigrations(client);
+        if (migrations.join() !== [...archive.migrations].sort().join()) {
+            throw new ArchiveError("Archive was exported from a database with other migrations");
+        }
+        // Lock out writers until the import is committed.
+        const tableNames = archiveTables.map((table) => table.name).join(", ");
+        await client.query(`LOCK TABLE ${tableNames}`);
+        const used = await client.query(`
+            SELECT EXISTS (
+                SELECT 1 FROM refs WHERE id NOT IN (SELECT ref FROM system_documents)
+            ) AS used
+        `);
+        if (used.rows[0].used) {
+            throw new ArchiveError("Can only import into a database without documents");
+        }
+        await client.query(`TRUNCATE ${tableNames} RESTART IDENTITY`);
+
+        const counts: Record<string, number> = {};
+        for (const { name, identity } of archiveTables) {
+            const rows = archive.tables[name] ?? [];
+            const overriding = identity ? "OVERRIDING SYSTEM VALUE" : "";
+            const insert = `
+                INSERT INTO ${name} ${overriding}
+                SELECT * FROM json_populate_recordset(NULL::${name}, $1::json)
+            `;
+            const result = await client.query(insert, [JSON.stringify(rows)]);
+            counts[name] = result.rowCount ?? 0;
+            if (identity) {
+                // Continue numbering after the imported IDs.
+                await client.query(`
+                    SELECT setval(pg_get_serial_sequence('${name}', 'id'), MAX(id) + 1, false)
+                    FROM ${name}
+                    HAVING COUNT(*) > 0
+                `);
+            }
+        }
+        await client.query("COMMIT");
+        return counts;
+    } catch (e) {
+        await client.query("ROLLBACK");
+        throw e;
+    }
+}
@@ -0,0 +1,152 @@
+import type { Json, Persistence } from "./persistence.js";
+
+/** Function that performs a job of some kind, returning its result. */
+export type JobHandler = (payload: Json) => Promise<Json>;
+
+/** Error thrown by a job handler when retrying the job cannot help. */
+export class PermanentJobError extends Error {}
+
+export type JobQueueOptions = {
+    /** Number of jobs to run at once in this process. */
+    concurrency: number;
+
+    /** How often an idle worker checks for new jobs, in milliseconds. */
+    pollIntervalMs: number;
+
+    /** Delay before the first retry, in seconds, doubling with each attempt. */
+    backoffSeconds: number;
+
+    /** Time after which a running job is presumed lost and requeued, in seconds. */
+    staleSeconds: number;
+};
+
+export const defaultJobQueueOptions: JobQueueOptions = {
+    concurrency: 2,
+    pollIntervalMs: 1000,
+    backoffSeconds: 5,
+    staleSeconds: 600,
+};
+
+/** Queue of background jobs, stored in the database so that it survives restarts.
+
+Jobs are claimed with `SKIP LOCKED`, so any number of backend instances can run
+workers against the same queue. Failed jobs are retried with exponential
+backoff until they run out of attempts, after which they are left in the
+database as dead jobs, to be inspected and retried manually. Jobs whose worker
+stops reporting back are retried in the same way.
+ */
+export class JobQueue {
+    private handlers = new Map<string, JobHandler>();
+    private running = false;
+    private workers: Promise<void>[] = [];
+    private wake: (() => void)[] = [];
+    private staleTimer?: NodeJS.Timeout;
+
+    constructor(
+        readonly db: Persistence,
+        readonly options: JobQueueOptions = defaultJobQueueOptions,
+    ) {}
+
+    /** Register the handler for jobs of a given kind. */
+    register(kind: string, handler: JobHandler) {
+        this.handlers.set(kind, handler);
+    }
+
+    async enqueue(kind: string, payload: Json, maxAttempts = 3): Promise<number> {
+        if (!this.handlers.has(kind)) {
+            throw new Error(`No handler for jobs of kind ${kind}`);
+        }
+        const id = await this.db.enqueueJob(kind, payload, maxAttempts);
+        this.wake.shift()?.();
+        return id;
+    }
+
+    start() {
+        if (this.running) {
+            return;
+        }
+        this.running = true;
+        for (let i = 0; i < this.options.concurrency; i++) {
+            this.workers.push(this.work());
+        }
+        this.staleTimer = setInterval(() => this.requeueStale(), this.options.staleSeconds * 1000);
+        this.staleTimer.unref();
+        this.requeueStale();
+    }
+
+    /** Stop the workers, waiting for the jobs that they are running to finish. */
+    async stop() {
+        this.running = false;
+        clearInterval(this.staleTimer);
+        for (const wake of this.wake.splice(0)) {
+            wake();
+        }
+        await Promise.all(this.workers.splice(0));
+    }
+
+    private async work() {
+        while (this.running) {
+            const job = await this.db.claimJob().catch((e) => {
+                console.error(`failed to claim job: ${e}`);
+                return null;
+            });
+            if (job) {
+                await this.runJob(job.id, job.kind, job.payload, job.attempts);
+            } else {
+                await this.idle();
+            }
+        }
+    }
+
+    private async runJob(id: number, kind: string, payload: Json, attempts: number) {
+        const handler = this.handlers.get(kind);
+        try {
+            if (!handler) {
+                throw new PermanentJobError(`No handler for jobs of kind ${kind}`);
+            }
+            const result = (await handler(payload)) ?? null;
+            if (!(await this.db.completeJob(id, attempts, result))) {
+                console.warn(`job ${id} (${kind}) was requeued before attempt ${attempts} ended`);
+            }
+        } catch (e) {
+            const message = e instanceof Error ? e.message : String(e);
+            console.error(`job ${id} (${kind}) failed on attempt ${attempts}: ${message}`);
+            const backoff = this.options.backoffSeconds * 2 ** (attempts - 1);
+            await this.db
+                .failJob(id, attempts, message, backoff, e instanceof PermanentJobError)
+                .catch((e) => console.error(`failed to record failure of job ${id}: ${e}`));
+        }
+    }
+
+    /** Wait until polling again or until a job is enqueued by this process. */
+    private idle(): Promise<void> {
+        return new Promise((resolve) => {
+            const timer = setTimeout(done, this.options.pollIntervalMs);
+            const wake = this.wake;
+            function done() {
+                clearTimeout(timer);
+                const i = wake.indexOf(done);
+                if (i >= 0) {
+                    wake.splice(i, 1);
+                }
+                resolve();
+            }
+            wake.push(done);
+        });
+    }
+
+    private async requeueStale() {
+        const jobs = await this.db.requeueStaleJobs(this.options.staleSeconds).catch((e) => {
+            console.error(`failed to requeue stale jobs: ${e}`);
+            return [];
+        });
+        const ids = (status: string) =>
+            jobs.filter((job) => job.status === status).map((job) => job.id);
+        if (ids("queued").length > 0) {
+            console.warn(`requeued stale jobs: ${ids("queued").join(", ")}`);
+        }
+        if (ids("dead").length > 0) {
+            console.warn(`stale jobs out of attempts: ${ids("dead").join(", ")}`);
+        }
+    }
+}
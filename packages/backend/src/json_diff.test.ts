@@ -0,0 +1,37 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { diffJson } from "./json_diff.js";
+
+test("JSON diff", async (_t) => {
+    await it("is empty for equal values", () => {
+        assert.deepStrictEqual(diffJson({ a: [1, { b: null }] }, { a: [1, { b: null }] }), []);
+    });
+
+    await it("diffs objects key by key", () => {
+        assert.deepStrictEqual(diffJson({ a: 1, b: 2 }, { a: 3, c: 4 }), [
+            { op: "replace", path: "/a", value: 3 },
+            { op: "remove", path: "/b" },
+            { op: "add", path: "/c", value: 4 },
+        ]);
+    });
+
+    await it("diffs arrays element by element", () => {
+        assert.deepStrictEqual(diffJson([1, 2, 3], [1, 5]), [
+            { op: "replace", path: "/1", value: 5 },
+            { op: "remove", path: "/2" },
+        ]);
+        assert.deepStrictEqual(diffJson([1], [1, 2]), [{ op: "add", path: "/1", value: 2 }]);
+    });
+
+    await it("escapes keys in paths", () => {
+        assert.deepStrictEqual(diffJson({}, { "a/b~c": 1 }), [
+            { op: "add", path: "/a~1b~0c", value: 1 },
+        ]);
+    });
+
+    await it("replaces values of different types", () => {
+        assert.deepStrictEqual(diffJson({ a: [1] }, { a: { 0: 1 } }), [
+            { op: "replace", path: "/a", value: { 0: 1 } },
+        ]);
+    });
+});
@@ -0,0 +1,59 @@
+/** Operation of a JSON Patch, as in RFC 6902. */
+export type JsonPatchOp =
+    | { op: "add"; path: string; value: unknown }
+    | { op: "remove"; path: string }
+    | { op: "replace"; path: string; value: unknown };
+
+/** Compute a JSON Patch transforming one JSON value into another.
+
+Objects are compared key by key and arrays element by element, so applying the
+patch to `before` yields `after`. The patch is not guaranteed to be minimal: an
+insertion at the front of an array, for instance, replaces every element.
+ */
+export function diffJson(before: unknown, after: unknown, path = ""): JsonPatchOp[] {
+    if (Array.isArray(before) && Array.isArray(after)) {
+        const ops: JsonPatchOp[] = [];
+        const common = Math.min(before.length, after.length);
+        for (let i = 0; i < common; i++) {
+            ops.push(...diffJson(before[i], after[i], `${path}/${i}`));
+        }
+        for (let i = common; i < after.length; i++) {
+            ops.push({ op: "add", path: `${path}/${i}`, value: after[i] });
+        }
+        // Remove from the end, so that earlier indices stay valid.
+        for (let i = before.length - 1; i >= common; i--) {
+            ops.push({ op: "remove", path: `${path}/${i}` });
+        }
+        return ops;
+    }
+    if (isObject(before) && isObject(after)) {
+        const ops: JsonPatchOp[] = [];
+        for (const key of Object.keys(before)) {
+            const child = `${path}/${escapePointer(key)}`;
+            if (Object.hasOwn(after, key)) {
+                ops.push(...diffJson(before[key], after[key], child));
+            } else {
+                ops.push({ op: "remove", path: child });
+            }
+        }
+        for (const key of Object.keys(after)) {
+            if (!Object.hasOwn(before, key)) {
+                ops.push({ op: "add", path: `${path}/${escapePointer(key)}`, value: after[key] });
+            }
+        }
+        return ops;
+    }
+    if (before === after) {
+        return [];
+    }
+    return [{ op: "replace", path, value: after }];
+}
+
+function isObject(x: unknown): x is Record<string, unknown> {
+    return typeof x === "object" && x !== null && !Array.isArray(x);
+}
+
+/** Escape a key for use in a JSON Pointer, as in RFC 6901. */
+function escapePointer(key: string): string {
+    return key.replaceAll("~", "~0").replaceAll("/", "~1");
+}
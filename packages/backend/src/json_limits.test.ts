@@ -0,0 +1,29 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { jsonLimitViolation } from "./json_limits.js";
+
+test("JSON limits", async (_t) => {
+    const limits = { maxDepth: 3, maxStringLength: 5, maxKeys: 2 };
+
+    await it("accepts values within limits", () => {
+        assert.strictEqual(jsonLimitViolation({ a: [1, "abc"], b: null }, limits), null);
+    });
+
+    await it("rejects deeply nested values", () => {
+        let deep: unknown = 0;
+        for (let i = 0; i < 100_000; i++) {
+            deep = [deep];
+        }
+        assert.match(jsonLimitViolation(deep) ?? "", /depth/);
+        assert.match(jsonLimitViolation({ a: { b: { c: {} } } }, limits) ?? "", /depth/);
+    });
+
+    await it("rejects long strings, including keys", () => {
+        assert.match(jsonLimitViolation(["abcdef"], limits) ?? "", /length/);
+        assert.match(jsonLimitViolation({ abcdef: 1 }, limits) ?? "", /length/);
+    });
+
+    await it("rejects objects with too many keys", () => {
+        assert.match(jsonLimitViolation({ a: 1, b: 2, c: 3 }, limits) ?? "", /keys/);
+    });
+});
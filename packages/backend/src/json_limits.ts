@@ -0,0 +1,80 @@
+import * as z from "zod";
+import type { Json } from "./queries.js";
+
+/** Limits on the shape of JSON values accepted from clients. */
+export type JsonLimits = {
+    /** Maximum nesting depth of arrays and objects. */
+    maxDepth: number;
+
+    /** Maximum length of a string, including object keys. */
+    maxStringLength: number;
+
+    /** Maximum number of keys in a single object. */
+    maxKeys: number;
+};
+
+export const defaultJsonLimits: JsonLimits = {
+    maxDepth: 64,
+    maxStringLength: 1024 * 1024,
+    maxKeys: 10_000,
+};
+
+/** Message broadcast to the clients of a live document when a change to it is
+rejected for exceeding the limits.
+ */
+export type LimitExceededWarning = {
+    type: "limit-exceeded";
+    refId: string;
+    violation: string;
+};
+
+/** Find the first way in which a JSON value exceeds the given limits.
+
+The value is traversed with an explicit stack, so that pathologically deep
+values are rejected instead of overflowing the call stack. Returns a
+description of the violation, or `null` if the value is within limits.
+ */
+export function jsonLimitViolation(
+    value: unknown,
+    limits: JsonLimits = defaultJsonLimits,
+): string | null {
+    const stack: [unknown, number][] = [[value, 0]];
+    while (stack.length > 0) {
+        const [v, depth] = stack.pop() as [unknown, number];
+        if (typeof v === "string") {
+            if (v.length > limits.maxStringLength) {
+                return `String exceeds maximum length of ${limits.maxStringLength}`;
+            }
+        } else if (typeof v === "object" && v !== null) {
+            if (depth >= limits.maxDepth) {
+                return `Value exceeds maximum nesting depth of ${limits.maxDepth}`;
+            }
+            if (Array.isArray(v)) {
+                for (const item of v) {
+                    stack.push([item, depth + 1]);
+                }
+                continue;
+            }
+            const keys = Object.keys(v);
+            if (keys.length > limits.maxKeys) {
+                return `Object exceeds maximum of ${limits.maxKeys} keys`;
+            }
+            for (const key of keys) {
+                stack.push([key, depth + 1]);
+                stack.push([(v as Record<string, unknown>)[key], depth + 1]);
+            }
+        }
+    }
+    return null;
+}
+
+/** Refinement rejecting values that exceed the default JSON limits. */
+export function refineJsonLimits(value: unknown, ctx: z.RefinementCtx) {
+    const violation = jsonLimitViolation(value);
+    if (violation) {
+        ctx.addIssue({ code: z.ZodIssueCode.custom, message: violation });
+    }
+}
+
+/** Arbitrary JSON value within the default limits. */
+export const BoundedJson = z.custom<Json>().superRefine(refineJsonLimits);
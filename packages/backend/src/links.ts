@@ -1,6 +1,6 @@
 import * as z from "zod";
 
-const Extern = z.object({
+export const Extern = z.object({
     /// The ref that the extern is pointing to
     /// In the future, we might make this more finegrained and
     /// have externs point to ref + witness, or to snapshot
@@ -0,0 +1,38 @@
+import assert from "node:assert";
+import { EventEmitter } from "node:events";
+import { it, test } from "node:test";
+import type * as A from "@automerge/automerge-repo";
+import type express from "express";
+import { streamLiveContent } from "./live_view.js";
+
+test("Live view", async (_t) => {
+    let doc = { name: "v1" };
+    const handle = Object.assign(new EventEmitter(), { docSync: () => doc });
+    const req = new EventEmitter();
+    const written: string[] = [];
+    const res = { writeHead: () => {}, write: (chunk: string) => written.push(chunk) };
+    streamLiveContent(
+        handle as unknown as A.DocHandle<unknown>,
+        req as unknown as express.Request,
+        res as unknown as express.Response,
+        { throttleMs: 10, heartbeatMs: 60_000 },
+    );
+
+    await it("sends the content when the stream opens", () => {
+        assert.deepStrictEqual(written, ['event: content\ndata: {"name":"v1"}\n\n']);
+    });
+
+    await it("coalesces bursts of changes", async () => {
+        doc = { name: "v2" };
+        handle.emit("change");
+        handle.emit("change");
+        await new Promise((resolve) => setTimeout(resolve, 30));
+        assert.strictEqual(written.length, 2);
+        assert.strictEqual(written[1], 'event: content\ndata: {"name":"v2"}\n\n');
+    });
+
+    await it("stops listening when the client disconnects", () => {
+        req.emit("close");
+        assert.strictEqual(handle.listenerCount("change"), 0);
+    });
+});
@@ -0,0 +1,63 @@
+import type * as A from "@automerge/automerge-repo";
+import type express from "express";
+
+/** Options for streaming the content of a live document. */
+export type LiveViewOptions = {
+    /** Minimum time between two content updates, in milliseconds. */
+    throttleMs: number;
+
+    /** Time between keep-alive comments, in milliseconds. */
+    heartbeatMs: number;
+};
+
+export const defaultLiveViewOptions: LiveViewOptions = {
+    throttleMs: 250,
+    heartbeatMs: 30_000,
+};
+
+/** Stream the content of a live document as Server-Sent Events.
+
+Each `content` event carries the full JSON content of the document: once when
+the stream opens and then after every change, with bursts of changes coalesced.
+The stream is read-only and needs no Automerge support on the client, which suits
+dashboards, projectors, and embeds that only follow a document.
+ */
+export function streamLiveContent(
+    handle: A.DocHandle<unknown>,
+    req: express.Request,
+    res: express.Response,
+    options: LiveViewOptions = defaultLiveViewOptions,
+) {
+    res.writeHead(200, {
+        "content-type": "text/event-stream",
+        "cache-control": "no-cache",
+        connection: "keep-alive",
+        // Prevent buffering by reverse proxies such as nginx.
+        "x-accel-buffering": "no",
+    });
+
+    let lastSent = 0;
+    let pending: NodeJS.Timeout | undefined;
+    const send = () => {
+        pending = undefined;
+        lastSent = Date.now();
+        res.write(`event: content\ndata: ${JSON.stringify(handle.docSync())}\n\n`);
+    };
+    const onChange = () => {
+        if (pending) {
+            return;
+        }
+        const wait = lastSent + options.throttleMs - Date.now();
+        pending = setTimeout(send, Math.max(0, wait));
+    };
+
+    const heartbeat = setInterval(() => res.write(": keep-alive\n\n"), options.heartbeatMs);
+    handle.on("change", onChange);
+    req.on("close", () => {
+        handle.off("change", onChange);
+        clearInterval(heartbeat);
+        clearTimeout(pending);
+    });
+
+    send();
+}
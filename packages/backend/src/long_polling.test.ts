@@ -0,0 +1,157 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import type * as A from "@automerge/automerge-repo";
+import { defaultFloodControlOptions } from "./flood_control.js";
+import { LongPollingServerAdapter } from "./long_polling.js";
+import type { SyncDecision } from "./sync_namespaces.js";
+
+const serverId = "server" as A.PeerId;
+const peerId = "peer" as A.PeerId;
+
+function connectedAdapter(floodControl = defaultFloodControlOptions) {
+    const adapter = new LongPollingServerAdapter({
+        pollTimeoutMs: 20,
+        sessionTimeoutMs: 1000,
+        floodControl,
+    });
+    adapter.connect(serverId, {});
+    return adapter;
+}
+
+function join(
+    adapter: LongPollingServerAdapter,
+    user: string | null = null,
+    maySync?: (documentId: string) => SyncDecision,
+) {
+    const joined = adapter.join(peerId, {}, user, maySync);
+    assert.ok(joined);
+    return joined;
+}
+
+function messageTo(targetId: A.PeerId, documentId = "doc"): A.Message {
+    return { type: "sync", senderId: serverId, targetId, documentId } as unknown as A.Message;
+}
+
+test("Long polling", async (_t) => {
+    await it("announces peers that join", () => {
+        const adapter = connectedAdapter();
+        const candidates: unknown[] = [];
+        adapter.on("peer-candidate", (candidate) => candidates.push(candidate));
+        const joined = adapter.join(peerId, { isEphemeral: true }, null);
+        assert.strictEqual(joined?.peerId, serverId);
+        assert.deepStrictEqual(candidates, [{ peerId, peerMetadata: { isEphemeral: true } }]);
+    });
+
+    await it("returns messages until they are acknowledged", async () => {
+        const adapter = connectedAdapter();
+        const { sessionId } = join(adapter);
+        adapter.send(messageTo(peerId));
+        adapter.send(messageTo("other" as A.PeerId));
+        adapter.send(messageTo(peerId));
+        const first = await adapter.poll(sessionId, 0);
+        assert.strictEqual(first?.seq, 2);
+        assert.strictEqual(first?.messages.length, 2);
+        const again = await adapter.poll(sessionId, 1);
+        assert.strictEqual(again?.messages.length, 1);
+        const acked = await adapter.poll(sessionId, 2);
+        assert.deepStrictEqual(acked, { seq: 2, messages: [] });
+    });
+
+    await it("holds polls open until there are messages", async () => {
+        const adapter = connectedAdapter();
+        const { sessionId } = join(adapter);
+        const polled = adapter.poll(sessionId, 0);
+        adapter.send(messageTo(peerId));
+        assert.strictEqual((await polled)?.messages.length, 1);
+    });
+
+    await it("delivers messages from the peer of a session", () => {
+        const adapter = connectedAdapter();
+        const received: unknown[] = [];
+        adapter.on("message", (message) => received.push(message));
+        const { sessionId } = join(adapter);
+        const message = { type: "request", senderId: peerId, targetId: serverId };
+        const spoofed = { ...message, senderId: "someone-else" };
+        assert.strictEqual(adapter.deliver(sessionId, [message, spoofed, "junk"]), "accepted");
+        assert.deepStrictEqual(received, [message]);
+        assert.strictEqual(adapter.deliver("unknown", [message]), "unknown-session");
+    });
+
+    await it("drops messages about documents that may not be synced", () => {
+        const adapter = connectedAdapter();
+        const received: unknown[] = [];
+        adapter.on("message", (message) => received.push(message));
+        const { sessionId } = join(adapter, null, (documentId) =>
+            documentId === "granted" ? "write" : null,
+        );
+        const message = (documentId: string) => ({
+            type: "request",
+            senderId: peerId,
+            targetId: serverId,
+            documentId,
+        });
+        adapter.deliver(sessionId, [message("granted"), message("secret")]);
+        assert.deepStrictEqual(received, [message("granted")]);
+    });
+
+    await it("ends sessions that sync retired documents", async () => {
+        const adapter = connectedAdapter();
+        const disconnected: unknown[] = [];
+        adapter.on("peer-disconnected", (event) => disconnected.push(event.peerId));
+        const { sessionId } = join(adapter, null, () => "retired");
+        const message = { type: "request", senderId: peerId, targetId: serverId, documentId: "d" };
+        assert.strictEqual(adapter.deliver(sessionId, [message]), "retired");
+        assert.deepStrictEqual(disconnected, [peerId]);
+        assert.strictEqual(await adapter.poll(sessionId, 0), undefined);
+    });
+
+    await it("ends sessions that flood the server", () => {
+        const floodControl = { ...defaultFloodControlOptions, burst: 1, maxStrikes: 0 };
+        const adapter = connectedAdapter(floodControl);
+        const { sessionId } = join(adapter);
+        const message = { type: "request", senderId: peerId, targetId: serverId };
+        assert.strictEqual(adapter.deliver(sessionId, [message, message]), "flooded");
+    });
+
+    await it("replaces the session of a user's peer that joins again", async () => {
+        const adapter = connectedAdapter();
+        const disconnected: unknown[] = [];
+        adapter.on("peer-disconnected", (event) => disconnected.push(event.peerId));
+        const first = join(adapter, "alice");
+        const second = join(adapter, "alice");
+        assert.deepStrictEqual(disconnected, [peerId]);
+        assert.strictEqual(await adapter.poll(first.sessionId, 0), undefined);
+        adapter.send(messageTo(peerId));
+        assert.strictEqual((await adapter.poll(second.sessionId, 0))?.messages.length, 1);
+        adapter.leave(second.sessionId);
+        assert.deepStrictEqual(disconnected, [peerId, peerId]);
+    });
+
+    await it("refuses to take over the session of another user's peer", async () => {
+        const adapter = connectedAdapter();
+        const { sessionId } = join(adapter, "alice");
+        assert.strictEqual(adapter.join(peerId, {}, "mallory"), null);
+        assert.strictEqual(adapter.join(peerId, {}, null), null);
+        adapter.send(messageTo(peerId));
+        assert.strictEqual((await adapter.poll(sessionId, 0))?.messages.length, 1);
+    });
+
+    await it("refuses to take over the session of an anonymous peer", () => {
+        const adapter = connectedAdapter();
+        join(adapter, null);
+        assert.strictEqual(adapter.join(peerId, {}, null), null);
+        assert.strictEqual(adapter.join(peerId, {}, "alice"), null);
+    });
+
+    await it("ends sessions of users whose access was revoked", () => {
+        const adapter = connectedAdapter();
+        const { sessionId } = join(adapter, "alice");
+        const message = { type: "request", senderId: peerId, targetId: serverId, documentId: "d" };
+        adapter.deliver(sessionId, [message]);
+        adapter.revoke("d", (user) => user === "bob");
+        adapter.revoke("other", (user) => user === "alice");
+        assert.strictEqual(adapter.deliver(sessionId, []), "accepted");
+        adapter.revoke("d", (user) => user === "alice");
+        assert.strictEqual(adapter.deliver(sessionId, []), "unknown-session");
+    });
+});
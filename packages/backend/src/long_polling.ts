@@ -0,0 +1,257 @@
+import * as crypto from "node:crypto";
+import * as A from "@automerge/automerge-repo";
+import {
+    type FloodControlOptions,
+    MessageRateLimiter,
+    defaultFloodControlOptions,
+} from "./flood_control.js";
+import { type SyncDecision, screenMessage } from "./sync_namespaces.js";
+
+/** Timing of long-polling sessions. */
+export type LongPollingOptions = {
+    /** Time for which a poll is held open while there is nothing to send. */
+    pollTimeoutMs: number;
+
+    /** Time after the last request of a session at which it is ended. */
+    sessionTimeoutMs: number;
+
+    floodControl: FloodControlOptions;
+};
+
+export const defaultLongPollingOptions: LongPollingOptions = {
+    pollTimeoutMs: 25_000,
+    sessionTimeoutMs: 60_000,
+    floodControl: defaultFloodControlOptions,
+};
+
+/** Messages to a long-polling peer, up to the sequence number of the last one. */
+export type PollResult = { seq: number; messages: A.Message[] };
+
+/** Outcome of delivering messages from a long-polling peer. */
+export type Delivery = "accepted" | "unknown-session" | "retired" | "flooded";
+
+type Session = {
+    id: string;
+    peerId: A.PeerId;
+    user: string | null;
+    documents: Set<string>;
+    maySync?: (documentId: string) => SyncDecision;
+    limiter: MessageRateLimiter;
+    outbox: { seq: number; message: A.Message }[];
+    lastSeq: number;
+    wake?: () => void;
+    expiry?: NodeJS.Timeout;
+};
+
+/** Network adapter for sync peers that cannot open a WebSocket, such as browsers
+behind proxies that block them.
+
+A peer joins to open a session, whose ID it then uses to send messages and to
+poll for the messages addressed to it. A poll is held open until there are
+messages or `pollTimeoutMs` has passed, and it acknowledges the messages that
+the previous poll returned, so that messages lost with a dropped response are
+sent again. A session that is not polled for `sessionTimeoutMs` ends, and its
+peer is disconnected. Messages are subject to the same restrictions and flood
+control as those received over WebSockets.
+ */
+export class LongPollingServerAdapter extends A.NetworkAdapter {
+    private sessions = new Map<string, Session>();
+    private sessionsByPeer = new Map<A.PeerId, Session>();
+    private ready = false;
+
+    constructor(readonly options: LongPollingOptions = defaultLongPollingOptions) {
+        super();
+    }
+
+    isReady(): boolean {
+        return this.ready;
+    }
+
+    whenReady(): Promise<void> {
+        return this.ready
+            ? Promise.resolve()
+            : new Promise((resolve) => this.once("ready", () => resolve()));
+    }
+
+    connect(peerId: A.PeerId, peerMetadata?: A.PeerMetadata) {
+        this.peerId = peerId;
+        this.peerMetadata = peerMetadata;
+        this.ready = true;
+        this.emit("ready", { network: this });
+    }
+
+    send(message: A.Message) {
+        const session = this.sessionsByPeer.get(message.targetId);
+        if (!session) {
+            return;
+        }
+        session.lastSeq++;
+        session.outbox.push({ seq: session.lastSeq, message });
+        session.wake?.();
+    }
+
+    disconnect() {
+        for (const session of [...this.sessions.values()]) {
+            this.end(session);
+        }
+        this.ready = false;
+        this.emit("close");
+    }
+
+    /** Open a session for a peer, on behalf of a user or of no one if null.
+
+    A previous session of the same peer is replaced only if it belongs to the same
+    signed-in user. Otherwise the join is refused, returning null, so that no one
+    can take over the messages addressed to another peer.
+
+    If given, `maySync` decides which documents the peer may sync, and whether it
+    may change them.
+     */
+    join(
+        peerId: A.PeerId,
+        peerMetadata: A.PeerMetadata,
+        user: string | null,
+        maySync?: (documentId: string) => SyncDecision,
+    ) {
+        const previous = this.sessionsByPeer.get(peerId);
+        if (previous) {
+            if (previous.user === null || previous.user !== user) {
+                return null;
+            }
+            this.end(previous);
+        }
+        const session: Session = {
+            id: crypto.randomUUID(),
+            peerId,
+            user,
+            documents: new Set(),
+            maySync,
+            limiter: new MessageRateLimiter(this.options.floodControl),
+            outbox: [],
+            lastSeq: 0,
+        };
+        this.sessions.set(session.id, session);
+        this.sessionsByPeer.set(peerId, session);
+        this.touch(session);
+        this.emit("peer-candidate", { peerId, peerMetadata });
+        return { sessionId: session.id, peerId: this.peerId, peerMetadata: this.peerMetadata };
+    }
+
+    /** Deliver messages sent by the peer of a session to the repo.
+
+    Messages that the peer may not send are dropped. If it syncs a retired
+    document or floods the server, its session is ended.
+     */
+    deliver(sessionId: string, messages: unknown[]): Delivery {
+        const session = this.sessions.get(sessionId);
+        if (!session) {
+            return "unknown-session";
+        }
+        this.touch(session);
+        for (const message of messages) {
+            if (!isMessage(message) || message.senderId !== session.peerId) {
+                continue;
+            }
+            const verdict = session.limiter.check();
+            if (verdict === "disconnect") {
+                this.end(session);
+                return "flooded";
+            }
+            if (verdict === "drop") {
+                continue;
+            }
+            const { documentId } = message as { documentId?: unknown };
+            if (typeof documentId === "string") {
+                session.documents.add(documentId);
+            }
+            const screened = session.maySync ? screenMessage(message, session.maySync) : "accept";
+            if (screened === "retired") {
+                this.end(session);
+                return "retired";
+            }
+            if (screened === "accept") {
+                this.emit("message", message);
+            }
+        }
+        return "accepted";
+    }
+
+    /** Wait for messages to the peer of a session, after those up to `ack`.
+
+    Returns undefined if the session has ended. An earlier poll of the same
+    session that is still waiting returns at once, without messages.
+     */
+    async poll(
+        sessionId: string,
+        ack: number,
+        signal?: AbortSignal,
+    ): Promise<PollResult | undefined> {
+        const session = this.sessions.get(sessionId);
+        if (!session) {
+            return undefined;
+        }
+        session.outbox = session.outbox.filter(({ seq }) => seq > ack);
+        if (session.outbox.length === 0) {
+            session.wake?.();
+            await new Promise<void>((resolve) => {
+                const timeout = setTimeout(done, this.options.pollTimeoutMs);
+                function done() {
+                    clearTimeout(timeout);
+                    if (session.wake === done) {
+                        session.wake = undefined;
+                    }
+                    signal?.removeEventListener("abort", done);
+                    resolve();
+                }
+                session.wake = done;
+                signal?.addEventListener("abort", done);
+            });
+        }
+        if (this.sessions.get(sessionId) !== session) {
+            return undefined;
+        }
+        this.touch(session);
+        const messages = session.outbox.map(({ message }) => message);
+        return { seq: session.outbox.at(-1)?.seq ?? ack, messages };
+    }
+
+    /** End the sessions that synced a document on behalf of the affected users. */
+    revoke(documentId: string, affects: (user: string | null) => boolean) {
+        for (const session of [...this.sessions.values()]) {
+            if (session.documents.has(documentId) && affects(session.user)) {
+                this.end(session);
+            }
+        }
+    }
+
+    /** End a session at the request of its peer. */
+    leave(sessionId: string) {
+        const session = this.sessions.get(sessionId);
+        if (session) {
+            this.end(session);
+        }
+    }
+
+    private touch(session: Session) {
+        clearTimeout(session.expiry);
+        const { pollTimeoutMs, sessionTimeoutMs } = this.options;
+        session.expiry = setTimeout(() => this.end(session), pollTimeoutMs + sessionTimeoutMs);
+        session.expiry.unref();
+    }
+
+    private end(session: Session) {
+        if (this.sessions.get(session.id) !== session) {
+            return;
+        }
+        clearTimeout(session.expiry);
+        this.sessions.delete(session.id);
+        this.sessionsByPeer.delete(session.peerId);
+        session.wake?.();
+        this.emit("peer-disconnected", { peerId: session.peerId });
+    }
+}
+
+function isMessage(message: unknown): message is A.Message {
+    const { type, senderId } = (message ?? {}) as Record<string, unknown>;
+    return typeof type === "string" && typeof senderId === "string";
+}
@@ -5,7 +5,7 @@ import * as migration from "./migration.js";
 
 async function main() {
     const client = new pg.Client({
-        connectionString: getDatabaseUrl(),
+        connectionString: await getDatabaseUrl(),
     });
 
     await client.connect();
@@ -0,0 +1,56 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { ExportError } from "./export.js";
+import { notebookToPdf, wrapText } from "./notebook_pdf.js";
+import { loadSystemDocuments } from "./system_documents.js";
+
+async function olog() {
+    const docs = await loadSystemDocuments();
+    const doc = docs.find((doc) => doc.slug === "olog-pair-of-shoes");
+    assert.ok(doc);
+    return doc.content;
+}
+
+function count(pdf: string, op: string): number {
+    return pdf.split(op).length - 1;
+}
+
+test("Notebook PDF", async (_t) => {
+    await it("renders the declarations of a model with its diagram", async () => {
+        const pdf = notebookToPdf(await olog()).toString("ascii");
+        assert.ok(pdf.includes("/Title (Example: a pair of shoes)"));
+        assert.ok(pdf.includes("(a pair of shoes : Object) Tj"));
+        const morphism = "has as left shoe : a pair of shoes -> a shoe \\(Hom\\(Object\\)\\)";
+        assert.ok(pdf.includes(`(${morphism}) Tj`));
+        // One box for each object, and one arrowhead for each morphism.
+        assert.strictEqual(count(pdf, " re S"), 2);
+        assert.strictEqual(count(pdf, " h f"), 2);
+    });
+
+    await it("flows long text onto new pages", () => {
+        const text = "Lorem ipsum dolor sit amet. ".repeat(400);
+        const doc = {
+            type: "model",
+            name: "Notes",
+            notebook: { cells: [{ tag: "rich-text", id: "t", content: text }] },
+        };
+        const pdf = notebookToPdf(doc).toString("ascii");
+        const pages = Number(pdf.match(/\/Count (\d+)/)?.[1]);
+        assert.ok(pages > 1);
+        assert.strictEqual(count(pdf, " re S"), 0);
+    });
+
+    await it("rejects content that is not a document", () => {
+        assert.throws(() => notebookToPdf("snapshot"), ExportError);
+        assert.throws(() => notebookToPdf({ type: "model" }), ExportError);
+        const malformed = { type: "model", name: "Bad", notebook: { cells: "none" } };
+        assert.throws(() => notebookToPdf(malformed), ExportError);
+    });
+
+    await it("wraps text at word boundaries, breaking long words", () => {
+        assert.deepStrictEqual(wrapText("aa bb cc", 30, 10, "regular"), ["aa bb", "cc"]);
+        const lines = wrapText("x".repeat(30), 50, 10, "regular");
+        assert.ok(lines.length > 1);
+        assert.strictEqual(lines.join(""), "x".repeat(30));
+    });
+});
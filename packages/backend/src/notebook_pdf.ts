@@ -0,0 +1,274 @@
+import { ExportError, basicObjectId, typeLabel } from "./export.js";
+import { type Font, PdfDocument, type PdfPage, type Point, pageSize, textWidth } from "./pdf.js";
+
+/** Margin around the content of each page, in points. */
+const MARGIN = 56;
+
+/** Height of the diagram drawn at the top of a notebook, in points. */
+const DIAGRAM_HEIGHT = 280;
+
+const contentWidth = pageSize.width - 2 * MARGIN;
+
+type Judgment = {
+    tag: string;
+    id?: unknown;
+    name?: unknown;
+    obType?: unknown;
+    morType?: unknown;
+    dom?: unknown;
+    cod?: unknown;
+    content?: unknown;
+};
+
+type Cell = { tag?: unknown; content?: unknown };
+
+/** Render a notebook document, such as a model, to PDF.
+
+The title of the document is followed by a diagram of the objects and morphisms
+declared in its notebook, if there are any, and then by its cells in order:
+rich-text cells as paragraphs and formal cells as the declarations they hold.
+Documents of any type can be rendered, and content that the renderer does not
+understand is left out.
+ */
+export function notebookToPdf(content: unknown): Buffer {
+    const { type, name, theory, notebook } = (content ?? {}) as Record<string, unknown>;
+    if (typeof type !== "string" || typeof name !== "string") {
+        throw new ExportError("Not a CatColab document");
+    }
+    const cells = ((notebook as { cells?: unknown } | null)?.cells ?? []) as Cell[];
+    if (!Array.isArray(cells)) {
+        throw new ExportError(`Malformed ${type} document`);
+    }
+    const judgments = cells.flatMap((cell) => {
+        const judgment = cell?.tag === "formal" ? (cell.content as Judgment | null) : null;
+        return judgment && typeof judgment.tag === "string" ? [judgment] : [];
+    });
+    const names = new Map<unknown, string>(judgments.map((j) => [j.id, displayName(j.name)]));
+
+    const layout = new Layout(new PdfDocument(name || "Untitled"));
+    layout.paragraph(name || "Untitled", 20, "bold", 6);
+    const kind = type.charAt(0).toUpperCase() + type.slice(1);
+    layout.paragraph(typeof theory === "string" ? `${kind} · ${theory}` : kind, 10, "italic", 12);
+
+    const diagram = diagramOf(judgments);
+    if (diagram.nodes.length > 0) {
+        layout.diagram(diagram);
+    }
+    for (const cell of cells) {
+        if (cell?.tag === "rich-text" && typeof cell.content === "string") {
+            // Block markers of rich text stand in for the breaks between paragraphs.
+            for (const text of cell.content.split(/[\n\uFFFC]+/)) {
+                if (text.trim()) {
+                    layout.paragraph(text.trim(), 11, "regular", 6);
+                }
+            }
+        } else if (cell?.tag === "formal") {
+            const line = judgmentLine(cell.content as Judgment | null, names);
+            if (line) {
+                layout.paragraph(line, 11, "regular", 4, 12);
+            }
+        }
+    }
+    return layout.doc.toBuffer();
+}
+
+function displayName(name: unknown): string {
+    return typeof name === "string" && name.trim() ? name : "(unnamed)";
+}
+
+/** Describe a formal cell in one line, such as `f : x -> y (Hom(Object))`. */
+function judgmentLine(judgment: Judgment | null, names: Map<unknown, string>): string | null {
+    if (!judgment || typeof judgment.tag !== "string") {
+        return null;
+    }
+    const name = displayName(judgment.name);
+    switch (judgment.tag) {
+        case "object": {
+            const type = typeLabel(judgment.obType);
+            return type ? `${name} : ${type}` : name;
+        }
+        case "morphism": {
+            const end = (ob: unknown) => names.get(basicObjectId(ob)) ?? "?";
+            const type = typeLabel(judgment.morType);
+            const arrow = `${name} : ${end(judgment.dom)} -> ${end(judgment.cod)}`;
+            return type ? `${arrow} (${type})` : arrow;
+        }
+        default: {
+            // Analyses and other formal content are only named by their kind.
+            const kind = (judgment.content as { tag?: unknown } | null)?.tag;
+            return typeof kind === "string" ? `${judgment.tag}: ${kind}` : judgment.tag;
+        }
+    }
+}
+
+type Diagram = {
+    nodes: { id: string; label: string }[];
+    edges: { dom: string; cod: string; label: string }[];
+};
+
+/** Objects of a notebook as nodes, and morphisms between them as edges. */
+function diagramOf(judgments: Judgment[]): Diagram {
+    const nodes = judgments.flatMap((j) =>
+        j.tag === "object" && typeof j.id === "string"
+            ? [{ id: j.id, label: displayName(j.name) }]
+            : [],
+    );
+    const ids = new Set(nodes.map((node) => node.id));
+    const edges = judgments.flatMap((j) => {
+        const dom = basicObjectId(j.dom);
+        const cod = basicObjectId(j.cod);
+        return j.tag === "morphism" && dom !== null && cod !== null && ids.has(dom) && ids.has(cod)
+            ? [{ dom, cod, label: typeof j.name === "string" ? j.name : "" }]
+            : [];
+    });
+    return { nodes, edges };
+}
+
+/** Flow of content down the pages of a document, starting new pages as needed. */
+class Layout {
+    page: PdfPage;
+    y: number;
+
+    constructor(readonly doc: PdfDocument) {
+        this.page = doc.addPage();
+        this.y = pageSize.height - MARGIN;
+    }
+
+    /** Make room for content of the given height, on a new page if need be. */
+    reserve(height: number) {
+        if (this.y - height < MARGIN) {
+            this.page = this.doc.addPage();
+            this.y = pageSize.height - MARGIN;
+        }
+    }
+
+    paragraph(text: string, size: number, font: Font, spaceAfter: number, indent = 0) {
+        const lineHeight = size * 1.3;
+        for (const line of wrapText(text, contentWidth - indent, size, font)) {
+            this.reserve(lineHeight);
+            this.y -= lineHeight;
+            this.page.text(MARGIN + indent, this.y + size * 0.25, line, size, font);
+        }
+        this.y -= spaceAfter;
+    }
+
+    /** Draw a diagram with its nodes placed around an ellipse. */
+    diagram({ nodes, edges }: Diagram) {
+        this.reserve(DIAGRAM_HEIGHT);
+        const top = this.y;
+        const center = { x: pageSize.width / 2, y: top - DIAGRAM_HEIGHT / 2 };
+        const [rx, ry] = [contentWidth / 2 - 60, DIAGRAM_HEIGHT / 2 - 30];
+        const boxes = new Map<string, Box>();
+        for (const [i, node] of nodes.entries()) {
+            const angle = Math.PI / 2 - (2 * Math.PI * i) / nodes.length;
+            const at =
+                nodes.length === 1
+                    ? center
+                    : { x: center.x + rx * Math.cos(angle), y: center.y + ry * Math.sin(angle) };
+            const box = { ...at, hw: textWidth(node.label, 10) / 2 + 6, hh: 10 };
+            boxes.set(node.id, box);
+            this.page.rect(box.x - box.hw, box.y - box.hh, 2 * box.hw, 2 * box.hh);
+            this.page.text(box.x - box.hw + 6, box.y - 3.5, node.label, 10);
+        }
+
+        // Edges between the same two nodes are bent apart from one another.
+        const parallel = new Map<string, number>();
+        const counts = new Map<string, number>();
+        for (const { dom, cod } of edges) {
+            const key = [dom, cod].sort().join(" ");
+            counts.set(key, (counts.get(key) ?? 0) + 1);
+        }
+        for (const { dom, cod, label } of edges) {
+            const from = boxes.get(dom);
+            const to = boxes.get(cod);
+            if (!from || !to) {
+                continue;
+            }
+            const key = [dom, cod].sort().join(" ");
+            const index = parallel.get(key) ?? 0;
+            parallel.set(key, index + 1);
+            if (dom === cod) {
+                this.loop(from, index, label);
+            } else {
+                const bend = (index - ((counts.get(key) ?? 1) - 1) / 2) * 28;
+                this.edge(from, to, dom < cod ? bend : -bend, label);
+            }
+        }
+        this.y = top - DIAGRAM_HEIGHT - 12;
+    }
+
+    private edge(from: Box, to: Box, bend: number, label: string) {
+        const [dx, dy] = [to.x - from.x, to.y - from.y];
+        const length = Math.hypot(dx, dy) || 1;
+        const mid = { x: (from.x + to.x) / 2, y: (from.y + to.y) / 2 };
+        const control = { x: mid.x - (bend * dy) / length, y: mid.y + (bend * dx) / length };
+        const start = clipToBox(from, control);
+        const end = clipToBox(to, control);
+        this.page.curve(start, control, end);
+        this.arrowhead(control, end);
+        // The label goes by the middle of the curve.
+        const x = (start.x + 2 * control.x + end.x) / 4;
+        const y = (start.y + 2 * control.y + end.y) / 4;
+        this.page.text(x + 3, y + 3, label, 8, "italic");
+    }
+
+    private loop(box: Box, index: number, label: string) {
+        const height = 24 + 12 * index;
+        const from = { x: box.x - 6, y: box.y + box.hh };
+        const to = { x: box.x + 6, y: box.y + box.hh };
+        const c1 = { x: box.x - 24, y: from.y + height };
+        const c2 = { x: box.x + 24, y: to.y + height };
+        this.page.cubic(from, c1, c2, to);
+        this.arrowhead(c2, to);
+        this.page.text(box.x + 14, from.y + height * 0.75, label, 8, "italic");
+    }
+
+    /** Fill an arrowhead at the end of a line coming from the given direction. */
+    private arrowhead(from: Point, tip: Point) {
+        const angle = Math.atan2(tip.y - from.y, tip.x - from.x);
+        const side = (turn: number) => ({
+            x: tip.x - 7 * Math.cos(angle + turn),
+            y: tip.y - 7 * Math.sin(angle + turn),
+        });
+        this.page.polygon([tip, side(0.4), side(-0.4)]);
+    }
+}
+
+/** Node of a diagram: a box around its center, with half its width and height. */
+type Box = Point & { hw: number; hh: number };
+
+/** Point where the line from the center of a box toward another point leaves it. */
+function clipToBox(box: Box, toward: Point): Point {
+    const [dx, dy] = [toward.x - box.x, toward.y - box.y];
+    const t = Math.min(Math.abs(box.hw / dx), Math.abs(box.hh / dy), 1);
+    return { x: box.x + t * dx, y: box.y + t * dy };
+}
+
+/** Break text into lines that fit in a width, breaking words only if they must. */
+export function wrapText(text: string, width: number, size: number, font: Font): string[] {
+    const lines: string[] = [];
+    let line = "";
+    for (const word of text.split(/\s+/).filter((word) => word)) {
+        const candidate = line ? `${line} ${word}` : word;
+        if (textWidth(candidate, size, font) <= width) {
+            line = candidate;
+            continue;
+        }
+        if (line) {
+            lines.push(line);
+        }
+        line = word;
+        while (textWidth(line, size, font) > width && line.length > 1) {
+            let fit = line.length - 1;
+            while (fit > 1 && textWidth(line.slice(0, fit), size, font) > width) {
+                fit--;
+            }
+            lines.push(line.slice(0, fit));
+            line = line.slice(fit);
+        }
+    }
+    if (line) {
+        lines.push(line);
+    }
+    return lines;
+}
@@ -0,0 +1,66 @@
+import { type AwsCredentials, presignUrl, uriEncode } from "./aws_signing.js";
+
+/** Error raised when the object store rejects a request. */
+export class ObjectStorageError extends Error {}
+
+/** A bucket in S3-compatible object storage, addressed path-style.
+
+Clients upload and download objects directly through presigned URLs, so that
+file contents never pass through the backend.
+ */
+export class ObjectStorage {
+    constructor(
+        readonly endpoint: string,
+        readonly bucket: string,
+        readonly region: string,
+        readonly credentials: AwsCredentials,
+    ) {}
+
+    /** Configure object storage from the `S3_*` environment variables, if set.
+
+    `S3_ENDPOINT` and `S3_BUCKET` are required; `S3_REGION` defaults to
+    `us-east-1`. Credentials are read from `S3_ACCESS_KEY_ID` and
+    `S3_SECRET_ACCESS_KEY`.
+     */
+    static fromEnv(): ObjectStorage | null {
+        const { S3_ENDPOINT, S3_BUCKET, S3_REGION, S3_ACCESS_KEY_ID, S3_SECRET_ACCESS_KEY } =
+            process.env;
+        if (!S3_ENDPOINT || !S3_BUCKET) {
+            return null;
+        }
+        return new ObjectStorage(S3_ENDPOINT, S3_BUCKET, S3_REGION || "us-east-1", {
+            accessKeyId: S3_ACCESS_KEY_ID ?? "",
+            secretAccessKey: S3_SECRET_ACCESS_KEY ?? "",
+        });
+    }
+
+    /** URL through which the object with the given key can be accessed. */
+    signedUrl(
+        method: "GET" | "PUT" | "DELETE",
+        key: string,
+        expiresSeconds: number,
+        headers?: Record<string, string>,
+    ): string {
+        const path = key.split("/").map(uriEncode).join("/");
+        const url = new URL(`${this.endpoint.replace(/\/+$/, "")}/${this.bucket}/${path}`);
+        return presignUrl({
+            method,
+            url,
+            region: this.region,
+            credentials: this.credentials,
+            date: new Date(),
+            expiresSeconds,
+            headers,
+        }).toString();
+    }
+
+    /** Delete an object. Deleting an object that does not exist succeeds. */
+    async delete(key: string): Promise<void> {
+        const response = await fetch(this.signedUrl("DELETE", key, 60), { method: "DELETE" });
+        if (!response.ok && response.status !== 404) {
+            throw new ObjectStorageError(
+                `Failed to delete object ${key}: ${response.status} ${response.statusText}`,
+            );
+        }
+    }
+}
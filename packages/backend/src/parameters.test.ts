@@ -0,0 +1,76 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import type { AnalysisDocument, ModelDocument } from "./document.js";
+import { ParameterError, applyParameterRows, parameterRows } from "./parameters.js";
+
+test("Parameters", async (_t) => {
+    const model: ModelDocument = {
+        type: "model",
+        name: "Predators and prey",
+        notebook: {
+            cells: [
+                {
+                    tag: "formal",
+                    id: "c1",
+                    content: { tag: "object", id: "x", name: "Wolves", obType: null },
+                },
+            ],
+        },
+    };
+    const content: AnalysisDocument = {
+        type: "analysis",
+        name: "Simulation",
+        modelRef: { __extern__: { refId: "r", taxon: "analysis", via: null } },
+        notebook: {
+            cells: [
+                {
+                    tag: "formal",
+                    id: "c2",
+                    content: {
+                        id: "a",
+                        content: { tag: "lotka-volterra", initialValues: { x: 2 }, duration: 10 },
+                    },
+                },
+            ],
+        },
+    };
+    const cellContent = (doc: AnalysisDocument) => {
+        const cell = doc.notebook.cells[0];
+        assert(cell.tag === "formal");
+        return cell.content.content;
+    };
+
+    await it("exports parameters with names from the model", () => {
+        assert.deepStrictEqual(parameterRows(content, model).slice(1), [
+            ["a", "lotka-volterra", "initialValues", "x", "Wolves", "2"],
+            ["a", "lotka-volterra", "duration", "", "", "10"],
+        ]);
+    });
+
+    await it("round trips exported parameters", () => {
+        const rows = parameterRows(content, null);
+        assert.deepStrictEqual(applyParameterRows(content, rows), content);
+    });
+
+    await it("updates values without modifying the original", () => {
+        const rows = parameterRows(content, model);
+        rows[1][5] = "3.5";
+        rows[2][5] = "20";
+        const updated = applyParameterRows(content, rows);
+        assert.deepStrictEqual(cellContent(updated).initialValues, { x: 3.5 });
+        assert.strictEqual(cellContent(updated).duration, 20);
+        assert.strictEqual(cellContent(content).duration, 10);
+    });
+
+    await it("rejects unknown analyses, parameters, and non-numbers", () => {
+        const [header, row] = parameterRows(content, model);
+        const withRow = (changes: Record<number, string>) => {
+            const changed = row.map((field, i) => changes[i] ?? field);
+            return () => applyParameterRows(content, [header, changed]);
+        };
+        assert.throws(withRow({ 0: "b" }), ParameterError);
+        assert.throws(withRow({ 2: "growthRates" }), ParameterError);
+        assert.throws(withRow({ 5: "many" }), ParameterError);
+        assert.throws(() => applyParameterRows(content, [row]), ParameterError);
+    });
+});
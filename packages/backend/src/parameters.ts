@@ -0,0 +1,110 @@
+import type { AnalysisDocument, ModelDocument } from "./document.js";
+
+/** Header of a CSV file of analysis parameters.
+
+Each row gives one parameter of one analysis in an analysis document. The
+analysis is identified by the ID of its cell and the parameter by the name of its
+field in the analysis content, such as "duration" or "initialValues". Parameters
+that assign a value to each object or morphism of the model, such as initial
+values, have one row per object or morphism, identified by its ID. The `kind`
+and `name` columns are for the benefit of people editing the file and are
+ignored on import.
+ */
+export const parameterHeader = ["analysis", "kind", "parameter", "id", "name", "value"];
+
+/** Error raised when a CSV file of parameters does not match the document. */
+export class ParameterError extends Error {}
+
+/** Tabulate the numerical parameters of the analyses in a document.
+
+Only parameters that have been given values are exported. Names of objects and
+morphisms are taken from the model, if it is given.
+ */
+export function parameterRows(content: AnalysisDocument, model: ModelDocument | null): string[][] {
+    const names = new Map<string, string>();
+    for (const cell of model?.notebook.cells ?? []) {
+        if (cell.tag === "formal") {
+            names.set(cell.content.id, cell.content.name);
+        }
+    }
+    const rows = [parameterHeader];
+    for (const cell of content.notebook.cells) {
+        if (cell.tag !== "formal") {
+            continue;
+        }
+        const { id: analysis, content: params } = cell.content;
+        const kind = params.tag;
+        for (const [parameter, value] of Object.entries(params)) {
+            if (typeof value === "number") {
+                rows.push([analysis, kind, parameter, "", "", String(value)]);
+            } else if (isNumberRecord(value)) {
+                for (const [id, x] of Object.entries(value)) {
+                    rows.push([analysis, kind, parameter, id, names.get(id) ?? "", String(x)]);
+                }
+            }
+        }
+    }
+    return rows;
+}
+
+/** Update the parameters of the analyses in a document from rows of a CSV file.
+
+The rows must start with `parameterHeader`. Every row must refer to an existing
+analysis and to a numerical parameter of it. An empty value removes the value of
+a parameter for an object or morphism. Returns a copy of the document with the
+new values.
+ */
+export function applyParameterRows(content: AnalysisDocument, rows: string[][]): AnalysisDocument {
+    const [header, ...body] = rows;
+    if (header?.join(",") !== parameterHeader.join(",")) {
+        throw new ParameterError(`Expected header: ${parameterHeader.join(",")}`);
+    }
+    const updated: AnalysisDocument = JSON.parse(JSON.stringify(content));
+    const analyses = new Map<string, Record<string, unknown>>();
+    for (const cell of updated.notebook.cells) {
+        if (cell.tag === "formal") {
+            analyses.set(cell.content.id, cell.content.content);
+        }
+    }
+    for (const [i, row] of body.entries()) {
+        const line = i + 2;
+        if (row.length !== parameterHeader.length) {
+            throw new ParameterError(`Line ${line}: expected ${parameterHeader.length} fields`);
+        }
+        const [analysis, _kind, parameter, id, _name, text] = row;
+        const params = analyses.get(analysis);
+        if (!params) {
+            throw new ParameterError(`Line ${line}: no analysis with ID ${analysis}`);
+        }
+        const current = params[parameter];
+        const value = text.trim() === "" ? null : Number(text);
+        if (value !== null && !Number.isFinite(value)) {
+            throw new ParameterError(`Line ${line}: value is not a number: ${text}`);
+        }
+        if (id === "" && typeof current === "number") {
+            if (value === null) {
+                throw new ParameterError(`Line ${line}: missing value of ${parameter}`);
+            }
+            params[parameter] = value;
+        } else if (id !== "" && isNumberRecord(current)) {
+            if (value === null) {
+                // biome-ignore lint/performance/noDelete: removing the entry is intended
+                delete current[id];
+            } else {
+                current[id] = value;
+            }
+        } else {
+            throw new ParameterError(`Line ${line}: no parameter ${parameter} of this kind`);
+        }
+    }
+    return updated;
+}
+
+function isNumberRecord(x: unknown): x is Record<string, number> {
+    return (
+        typeof x === "object" &&
+        x !== null &&
+        !Array.isArray(x) &&
+        Object.values(x).every((v) => typeof v === "number")
+    );
+}
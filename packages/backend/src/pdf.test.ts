@@ -0,0 +1,42 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { PdfDocument, textWidth } from "./pdf.js";
+
+/** Check that each entry of the cross-reference table points at its object. */
+function assertValidXref(pdf: string) {
+    const start = Number(pdf.match(/startxref\n(\d+)\n%%EOF\n$/)?.[1]);
+    assert.ok(pdf.slice(start).startsWith("xref\n"));
+    const entries = pdf.slice(start).split("\n").slice(3);
+    const size = Number(pdf.match(/\/Size (\d+)/)?.[1]);
+    for (let id = 1; id < size; id++) {
+        const offset = Number(entries[id - 1]?.slice(0, 10));
+        assert.ok(pdf.slice(offset).startsWith(`${id} 0 obj\n`), `object ${id}`);
+    }
+}
+
+test("PDF writer", async (_t) => {
+    await it("writes a document that readers can index", () => {
+        const doc = new PdfDocument("Example");
+        doc.addPage().text(56, 700, "Hello", 12);
+        doc.addPage().line({ x: 0, y: 0 }, { x: 10, y: 10 });
+        const pdf = doc.toBuffer().toString("ascii");
+        assert.ok(pdf.startsWith("%PDF-1.4\n"));
+        assert.ok(pdf.includes("/Count 2"));
+        assert.ok(pdf.includes("(Hello) Tj"));
+        assertValidXref(pdf);
+    });
+
+    await it("escapes text and encodes it in WinAnsi", () => {
+        const doc = new PdfDocument("Título (draft)");
+        doc.addPage().text(0, 0, "a\\b – ∘", 12);
+        const pdf = doc.toBuffer().toString("ascii");
+        assert.ok(pdf.includes("/Title (T\\355tulo \\(draft\\))"));
+        assert.ok(pdf.includes("(a\\\\b \\226 ?) Tj"));
+        assertValidXref(pdf);
+    });
+
+    await it("measures text in Helvetica", () => {
+        assert.strictEqual(textWidth("Hi", 10), (722 + 222) / 100);
+        assert.ok(textWidth("Hi", 10, "bold") > textWidth("Hi", 10));
+    });
+});
@@ -0,0 +1,201 @@
+/** Minimal writer of PDF documents, for server-side exports.
+
+Pages can hold text in the standard Helvetica fonts, lines, curves, and filled
+shapes, which is all that exports need. The standard fonts are never embedded,
+which keeps documents small and the writer free of dependencies. Text is
+encoded in WinAnsiEncoding, so characters outside it are replaced by `?`.
+ */
+
+export type Font = "regular" | "bold" | "italic";
+
+const baseFonts: Record<Font, string> = {
+    regular: "Helvetica",
+    bold: "Helvetica-Bold",
+    italic: "Helvetica-Oblique",
+};
+
+const fontResources: Record<Font, string> = { regular: "F1", bold: "F2", italic: "F3" };
+
+/** Size of an A4 page, in points. */
+export const pageSize = { width: 595, height: 842 };
+
+/** Widths of the printable ASCII characters in Helvetica, in thousandths of an em. */
+const helveticaWidths = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/** Approximate width of a line of text, in points.
+
+Bold text is wider than regular text by up to a tenth, so it is measured as such.
+Characters outside ASCII are measured as digits.
+ */
+export function textWidth(text: string, size: number, font: Font = "regular"): number {
+    let width = 0;
+    for (const char of text) {
+        const code = char.charCodeAt(0);
+        width += helveticaWidths[code - 32] ?? 556;
+    }
+    return ((font === "bold" ? 1.1 : 1) * width * size) / 1000;
+}
+
+/** Characters of WinAnsiEncoding outside Latin-1, by their code in the encoding. */
+const winAnsiExtras: Record<string, number> = {
+    "€": 0x80,
+    "‚": 0x82,
+    "„": 0x84,
+    "…": 0x85,
+    "•": 0x95,
+    "‘": 0x91,
+    "’": 0x92,
+    "“": 0x93,
+    "”": 0x94,
+    "–": 0x96,
+    "—": 0x97,
+    "™": 0x99,
+};
+
+/** Encode text as a PDF literal string in WinAnsiEncoding, using only ASCII. */
+function pdfString(text: string): string {
+    let encoded = "";
+    for (const char of text) {
+        const code = char.codePointAt(0) ?? 0;
+        const byte =
+            winAnsiExtras[char] ?? (code < 0x80 || (code >= 0xa0 && code <= 0xff) ? code : 0x3f);
+        if (byte === 0x28 || byte === 0x29 || byte === 0x5c) {
+            encoded += `\\${char}`;
+        } else if (byte < 0x20 || byte >= 0x7f) {
+            encoded += `\\${byte.toString(8).padStart(3, "0")}`;
+        } else {
+            encoded += String.fromCharCode(byte);
+        }
+    }
+    return `(${encoded})`;
+}
+
+/** Format a coordinate compactly. */
+function num(x: number): string {
+    return String(Math.round(x * 100) / 100);
+}
+
+export type Point = { x: number; y: number };
+
+/** Page of a PDF document, drawn on in points from the bottom left corner. */
+export class PdfPage {
+    private ops: string[] = [];
+
+    text(x: number, y: number, text: string, size: number, font: Font = "regular") {
+        const tf = `/${fontResources[font]} ${num(size)} Tf`;
+        this.ops.push(`BT ${tf} ${num(x)} ${num(y)} Td ${pdfString(text)} Tj ET`);
+    }
+
+    /** Set the gray level of strokes and fills, from 0 for black to 1 for white. */
+    gray(level: number) {
+        this.ops.push(`${num(level)} G ${num(level)} g`);
+    }
+
+    lineWidth(width: number) {
+        this.ops.push(`${num(width)} w`);
+    }
+
+    line(from: Point, to: Point) {
+        this.ops.push(`${num(from.x)} ${num(from.y)} m ${num(to.x)} ${num(to.y)} l S`);
+    }
+
+    /** Stroke a quadratic Bézier curve. */
+    curve(from: Point, control: Point, to: Point) {
+        // PDF only has cubic curves, which can express every quadratic one.
+        const toward = (p: Point) => ({
+            x: p.x + (2 / 3) * (control.x - p.x),
+            y: p.y + (2 / 3) * (control.y - p.y),
+        });
+        this.cubic(from, toward(from), toward(to), to);
+    }
+
+    /** Stroke a cubic Bézier curve. */
+    cubic(from: Point, c1: Point, c2: Point, to: Point) {
+        const points = [c1, c2, to].map((p) => `${num(p.x)} ${num(p.y)}`).join(" ");
+        this.ops.push(`${num(from.x)} ${num(from.y)} m ${points} c S`);
+    }
+
+    rect(x: number, y: number, width: number, height: number) {
+        this.ops.push(`${num(x)} ${num(y)} ${num(width)} ${num(height)} re S`);
+    }
+
+    /** Fill a polygon. */
+    polygon(points: Point[]) {
+        const [first, ...rest] = points;
+        if (!first) {
+            return;
+        }
+        const path = [`${num(first.x)} ${num(first.y)} m`];
+        for (const p of rest) {
+            path.push(`${num(p.x)} ${num(p.y)} l`);
+        }
+        this.ops.push(`${path.join(" ")} h f`);
+    }
+
+    content(): string {
+        return this.ops.join("\n");
+    }
+}
+
+/** PDF document made of A4 pages. */
+export class PdfDocument {
+    readonly pages: PdfPage[] = [];
+
+    constructor(readonly title: string) {}
+
+    addPage(): PdfPage {
+        const page = new PdfPage();
+        this.pages.push(page);
+        return page;
+    }
+
+    /** Serialize the document, with a cross-reference table as required by readers. */
+    toBuffer(): Buffer {
+        const fonts = Object.keys(baseFonts) as Font[];
+        const fontIds = fonts.map((_, i) => 4 + i);
+        const firstPageId = 4 + fonts.length;
+        const pageIds = this.pages.map((_, i) => firstPageId + 2 * i);
+        const fontDict = fonts.map((font, i) => `/${fontResources[font]} ${fontIds[i]} 0 R`);
+
+        const kids = pageIds.map((id) => `${id} 0 R`).join(" ");
+        const objects: string[] = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            `<< /Type /Pages /Kids [${kids}] /Count ${this.pages.length} >>`,
+            `<< /Title ${pdfString(this.title)} /Producer (CatColab) >>`,
+            ...fonts.map((font) => {
+                const baseFont = `/BaseFont /${baseFonts[font]}`;
+                return `<< /Type /Font /Subtype /Type1 ${baseFont} /Encoding /WinAnsiEncoding >>`;
+            }),
+        ];
+        const mediaBox = `/MediaBox [0 0 ${pageSize.width} ${pageSize.height}]`;
+        const resources = `/Resources << /Font << ${fontDict.join(" ")} >> >>`;
+        for (const [i, page] of this.pages.entries()) {
+            const contents = `/Contents ${pageIds[i] + 1} 0 R`;
+            objects.push(`<< /Type /Page /Parent 2 0 R ${mediaBox} ${resources} ${contents} >>`);
+            const stream = page.content();
+            objects.push(`<< /Length ${stream.length} >>\nstream\n${stream}\nendstream`);
+        }
+
+        // Every string above is ASCII, so lengths in characters are also in bytes.
+        let pdf = "%PDF-1.4\n";
+        const offsets: number[] = [];
+        for (const [i, object] of objects.entries()) {
+            offsets.push(pdf.length);
+            pdf += `${i + 1} 0 obj\n${object}\nendobj\n`;
+        }
+        const xref = pdf.length;
+        pdf += `xref\n0 ${objects.length + 1}\n0000000000 65535 f \n`;
+        for (const offset of offsets) {
+            pdf += `${String(offset).padStart(10, "0")} 00000 n \n`;
+        }
+        pdf += `trailer\n<< /Size ${objects.length + 1} /Root 1 0 R /Info 3 0 R >>\n`;
+        pdf += `startxref\n${xref}\n%%EOF\n`;
+        return Buffer.from(pdf, "ascii");
+    }
+}
@@ -1,6 +1,15 @@
 import assert from "node:assert";
+import * as crypto from "node:crypto";
 import { it, test } from "node:test";
+import { AutosaveFolder } from "./autosave_folding.js";
+import { ConsistencySweep, sameContent } from "./consistency.js";
+import type { DocumentContent } from "./document.js";
+import { checkRemoteExterns } from "./federation.js";
+import { ArchiveError } from "./instance_archive.js";
 import { Persistence } from "./persistence.js";
+import { RemoteFetchError } from "./remote_fetch.js";
+import type { CollabSession } from "./sessions.js";
+import { seedSystemDocuments } from "./system_documents.js";
 
 test("Persistence API", async (_t) => {
     const url = process.env.TEST_DATABASE_URL;
@@ -85,8 +94,499 @@ test("Persistence API", async (_t) => {
 
     await p.autosaveWithExterns(r1, docWithExtern);
 
-    await it("correctly saved externs", async () => {
+    await it("autosaves are logged and folded with their externs", async () => {
+        assert.deepStrictEqual(JSON.parse(await p.getAutosave(r1)), docWithExtern);
+        assert.deepStrictEqual(await p.getBacklinks(r2, "analysis"), []);
+        assert.ok((await p.foldAutosaves(100)) >= 1);
         assert.deepStrictEqual(await p.getBacklinks(r2, "analysis"), [r1]);
+        assert.deepStrictEqual(JSON.parse(await p.getAutosave(r1)), docWithExtern);
+        assert.strictEqual(await p.foldAutosaves(100), 0);
+    });
+
+    await it("flushing the autosave log folds autosaves logged during a pass", async () => {
+        const folder = new AutosaveFolder(p);
+        const r = await p.newRef("Flushed Document");
+        const running = folder.fold();
+        await p.autosaveWithExterns(r, docWithExtern);
+        await folder.flush();
+        await running;
+        const backlinks = await p.getBacklinks(r2, "analysis");
+        assert.deepStrictEqual(backlinks.sort(), [r1, r].sort());
+    });
+
+    await it("saving a ref folds its pending autosaves first", async () => {
+        const r = await p.newRef("Logged Document");
+        await p.autosave(r, JSON.stringify({ version: 1 }));
+        await p.autosaveWithExterns(r, { version: 2 });
+        const witness = await p.getWitness(r, await p.saveRef(r, "logged"));
+        assert.ok(witness);
+        const content = await p.getSnapshot(witness.snapshot);
+        assert.deepStrictEqual(JSON.parse(content ?? "null"), { version: 2 });
+    });
+
+    await it("saving a ref witnesses the content that it prepares", async () => {
+        const r = await p.newRef("Prepared Document");
+        await p.autosaveWithExterns(r, { version: 1 });
+        const prepare = async (content: unknown) => ({ ...(content as object), checked: true });
+        const witness = await p.getWitness(r, await p.saveRef(r, "prepared", prepare));
+        const content = await p.getSnapshot(witness?.snapshot ?? -1);
+        assert.deepStrictEqual(JSON.parse(content ?? "null"), { version: 1, checked: true });
+        assert.deepStrictEqual(JSON.parse(await p.getAutosave(r)), { version: 1, checked: true });
+        await assert.rejects(
+            p.saveRef(r, "rejected", async () => {
+                throw new Error("rejected");
+            }),
+        );
+        assert.strictEqual((await p.refMeta(r)).witnesses.length, 1);
+    });
+
+    const mirrorRef = await p.newRef("Mirrored Document");
+    await p.newMirror(mirrorRef, "https://example.org/refs/1/content");
+    await p.setMirrorStatus(mirrorRef, "connection refused");
+
+    await it("mirrors stored with provenance", async () => {
+        const mirror = await p.getMirror(mirrorRef);
+        assert.strictEqual(mirror?.sourceurl, "https://example.org/refs/1/content");
+        assert.strictEqual(mirror?.lasterror, "connection refused");
+        assert.ok(mirror?.lastfetched);
+        assert.strictEqual(await p.getMirror(r1), null);
+    });
+
+    await it("remote documents may only link to refs on this instance", async () => {
+        const extern = (refId: string) => ({ __extern__: { refId, taxon: "model", via: null } });
+        const local = { type: "model", name: "Local", link: extern(r2) } as DocumentContent;
+        await checkRemoteExterns(p, local);
+        const missing = crypto.randomUUID();
+        const remote = { type: "model", name: "Remote", link: extern(missing) } as DocumentContent;
+        await assert.rejects(checkRemoteExterns(p, remote), RemoteFetchError);
+    });
+
+    const systemDoc = { slug: "example", version: 1, content: { type: "model", name: "Example" } };
+    await seedSystemDocuments(p, [systemDoc]);
+    await seedSystemDocuments(p, [systemDoc]);
+    const upgraded = await seedSystemDocuments(p, [
+        { ...systemDoc, version: 2, content: { type: "model", name: "Better example" } },
+    ]);
+
+    await it("system documents are seeded once and upgraded in place", async () => {
+        const docs = await p.allSystemDocuments();
+        assert.strictEqual(docs.length, 1);
+        assert.strictEqual(docs[0].version, 2);
+        assert.deepStrictEqual([...upgraded.keys()], [docs[0].ref]);
+        assert.strictEqual(JSON.parse(await p.getAutosave(docs[0].ref)).name, "Better example");
+        assert.strictEqual((await p.refMeta(docs[0].ref)).witnesses.length, 2);
+    });
+
+    await it("mirrors and system documents are read-only", async () => {
+        const [doc] = await p.allSystemDocuments();
+        assert.ok(await p.isReadOnly(doc.ref));
+        assert.ok(await p.isReadOnly(mirrorRef));
+        assert.ok(!(await p.isReadOnly(r1)));
+    });
+
+    await p.recordView(r2, "viewer1");
+    await p.recordView(r2, "viewer1");
+    await p.recordView(r2, "viewer2");
+
+    await it("views are counted per day with distinct viewers", async () => {
+        const views = await p.refViews(r2, 7);
+        assert.strictEqual(views.total, 3);
+        assert.strictEqual(views.daily.length, 1);
+        assert.strictEqual(views.daily[0].views, 3);
+        assert.strictEqual(views.daily[0].viewers, 2);
+        assert.strictEqual((await p.refViews(r1, 7)).total, 0);
+    });
+
+    await it("ref stats count witnesses, snapshots, and bytes", async () => {
+        const stats = await p.refStats(r2);
+        assert.strictEqual(stats.witnesses, 2);
+        assert.strictEqual(stats.snapshots, 2);
+        assert.strictEqual(stats.bytes, "snapshot1".length + "snapshot2".length);
+        assert.strictEqual(stats.views, 3);
+    });
+
+    await it("consistency sweep repairs drifted live documents", async () => {
+        await p.autosave(mirrorRef, JSON.stringify({ title: "Mirrored Document" }));
+        const drifted = { title: "Edited while the database was down" };
+        const liveDocs = new Map([
+            [r1, { docSync: () => drifted }],
+            [mirrorRef, { docSync: () => ({ title: "Edited mirror" }) }],
+        ]);
+        const reset: string[] = [];
+        const sweep = new ConsistencySweep(
+            p,
+            liveDocs as unknown as ConstructorParameters<typeof ConsistencySweep>[1],
+            (refId) => reset.push(refId),
+            0,
+        );
+        const report = await sweep.sweep();
+        assert.strictEqual(report.checked, 2);
+        assert.deepStrictEqual(
+            report.discrepancies.map((d) => [d.refId, d.kind, d.resolved]),
+            [
+                [r1, "unsaved", true],
+                [mirrorRef, "read-only-diverged", true],
+            ],
+        );
+        assert.deepStrictEqual(JSON.parse(await p.getAutosave(r1)), drifted);
+        assert.deepStrictEqual(reset, [mirrorRef]);
+        assert.strictEqual((await sweep.sweep()).discrepancies.length, 1);
+    });
+
+    await it("concurrent writes to a live document are detected as conflicts", async () => {
+        const base = JSON.stringify({ title: "Base" });
+        await p.autosave(r1, base);
+        let live = { title: "Base" };
+        const warnings: unknown[] = [];
+        const liveDocs = new Map([
+            [r1, { docSync: () => live, broadcast: (m: unknown) => warnings.push(m) }],
+        ]);
+        const sweep = new ConsistencySweep(
+            p,
+            liveDocs as unknown as ConstructorParameters<typeof ConsistencySweep>[1],
+            () => {},
+            0,
+        );
+        sweep.markSynced(r1, base);
+        assert.ok(!(await p.autosaveWithExterns(r1, { title: "Stale" }, "{}")));
+        assert.ok(await p.autosaveWithExterns(r1, { title: "Elsewhere" }, base));
+        live = { title: "Edited live" };
+        const report = await sweep.sweep();
+        assert.deepStrictEqual(
+            report.discrepancies.map((d) => [d.kind, d.resolved]),
+            [["conflict", false]],
+        );
+        assert.ok(sweep.conflicts.has(r1));
+        assert.deepStrictEqual(warnings, [{ type: "conflict", refId: r1 }]);
+        assert.strictEqual(JSON.parse(await p.getAutosave(r1)).title, "Elsewhere");
+        assert.deepStrictEqual((await p.conflicts()).map((c) => c.refId), [r1]);
+        assert.deepStrictEqual(JSON.parse((await p.getConflict(r1))?.content ?? "null"), live);
+
+        await sweep.markSynced(r1, await p.getAutosave(r1));
+        assert.strictEqual(await p.getConflict(r1), null);
+    });
+
+    await it("content is compared regardless of the order of keys", () => {
+        assert.ok(sameContent({ a: 1, b: [{ c: 2, d: 3 }] }, '{"b":[{"d":3,"c":2}],"a":1}'));
+        assert.ok(!sameContent({ a: 1 }, '{"a":1,"b":null}'));
+        assert.ok(!sameContent([1, 2], "[2,1]"));
+    });
+
+    await it("evicted live documents are kept until their ref is redacted", async () => {
+        const r = await p.newRef("Evicted Document");
+        await p.autosaveWithExterns(r, { name: "Model" });
+        await p.saveLiveDocument(r, "doc1", new Uint8Array([1, 2]), '{"name":"Model"}');
+        await p.saveLiveDocument(r, "doc2", new Uint8Array([3]), '{"name":"Model"}');
+        const saved = await p.getLiveDocument(r);
+        assert.strictEqual(saved?.documentId, "doc2");
+        assert.deepStrictEqual([...(saved?.binary ?? [])], [3]);
+        assert.strictEqual((await p.liveDocumentIds()).get(r), "doc2");
+
+        const redaction = { paths: [], ids: [], strings: ["Model"], reason: "test" };
+        await p.redactRef(r, redaction);
+        assert.strictEqual(await p.getLiveDocument(r), null);
+    });
+
+    const r3 = await p.newRef("Changing Document");
+    await p.autosave(r3, JSON.stringify({ a: 1 }));
+    const w3 = await p.saveRef(r3, "first");
+    await p.autosave(r3, JSON.stringify({ a: 2 }));
+    const w4 = await p.saveRef(r3, "second");
+
+    await it("changes since a witness are paged with diffs", async () => {
+        const first = await p.changesSince(r3, 0, 1, true);
+        assert.deepStrictEqual(
+            first.changes.map((c) => [c.id, c.diff]),
+            [[w3, null]],
+        );
+        assert.ok(first.hasMore);
+        const rest = await p.changesSince(r3, first.cursor, 10, true);
+        assert.deepStrictEqual(
+            rest.changes.map((c) => [c.id, c.diff]),
+            [[w4, [{ op: "replace", path: "/a", value: 2 }]]],
+        );
+        assert.ok(!rest.hasMore);
+        assert.strictEqual(rest.cursor, w4);
+        assert.strictEqual((await p.changesSince(r3, w4, 10)).changes.length, 0);
+    });
+
+    await it("snapshots are only read through refs that have them", async () => {
+        assert.strictEqual(await p.getRefSnapshot(r2, s3), "snapshot2");
+        assert.strictEqual(await p.getRefSnapshot(r1, s3), null);
+    });
+
+    await it("snapshots of a ref can be pinned and unpinned", async () => {
+        assert.ok(await p.pinSnapshot(r2, s1, "version in the paper"));
+        assert.ok(await p.pinSnapshot(r2, s1, "version in the final paper"));
+        assert.ok(!(await p.pinSnapshot(r1, s3, "not a snapshot of r1")));
+        const { pins } = await p.refMeta(r2);
+        assert.deepStrictEqual(
+            pins.map((pin) => [pin.snapshot, pin.note]),
+            [[s1, "version in the final paper"]],
+        );
+        assert.ok(await p.unpinSnapshot(r2, s1));
+        assert.ok(!(await p.unpinSnapshot(r2, s1)));
+    });
+
+    await it("permalinks resolve to the snapshot they were made for", async () => {
+        const token = await p.createPermalink(r2, s1);
+        const hash = crypto.createHash("sha256").update("snapshot1").digest();
+        const expected = crypto.createHash("sha256").update(r2).update(hash).digest("hex");
+        assert.strictEqual(token, expected);
+        assert.strictEqual(await p.createPermalink(r2, s1), token);
+        assert.strictEqual(await p.createPermalink(r1, s3), null);
+        const permalink = await p.getPermalink(token ?? "");
+        assert.strictEqual(permalink?.ref, r2);
+        assert.strictEqual(permalink?.snapshot, s1);
+        assert.strictEqual(permalink?.content, "snapshot1");
+        assert.ok(permalink?.intact);
+        assert.strictEqual(await p.getPermalink("not a hash"), null);
+    });
+
+    await it("refs with the same content have their own permalinks", async () => {
+        const token = await p.createPermalink(r1, s1);
+        assert.ok(token);
+        assert.notStrictEqual(token, await p.createPermalink(r2, s1));
+        assert.strictEqual((await p.getPermalink(token))?.ref, r1);
+    });
+
+    await it("redaction removes content from every snapshot of a ref", async () => {
+        const r = await p.newRef("Leaky Document");
+        const secret = "sk-redacted-in-test";
+        await p.autosaveWithExterns(r, { name: "Model", token: secret });
+        const w = await p.saveRef(r, "with secret");
+        const original = (await p.getWitness(r, w))?.snapshot ?? 0;
+        const token = (await p.createPermalink(r, original)) ?? "";
+        await p.autosaveWithExterns(r, { name: "Model", notes: `Key ${secret}` });
+        await p.saveRef(r, "still with secret");
+        const analyzed = await p.saveSnapshot(JSON.stringify({ name: "Analyzed", token: secret }));
+        const job = await p.enqueueJob("analysis", { refId: r, snapshotId: analyzed }, 1);
+
+        const redaction = { paths: ["/token"], ids: [], strings: [secret], reason: "API key" };
+        assert.strictEqual(await p.redactRef(r, redaction), 3);
+        const { witnesses } = await p.refMeta(r);
+        for (const witness of witnesses) {
+            assert.ok(!(await p.getSnapshot(witness.snapshot))?.includes(secret));
+        }
+        assert.ok(!(await p.getAutosave(r)).includes(secret));
+        assert.strictEqual(await p.getSnapshot(original), null);
+        assert.strictEqual(await p.getSnapshot(analyzed), null);
+        const claimed = await p.claimJob();
+        assert.strictEqual(claimed?.id, job);
+        const { snapshotId } = claimed?.payload as { snapshotId: number };
+        assert.ok(!(await p.getSnapshot(snapshotId))?.includes(secret));
+        await p.completeJob(job, 1, null);
+        assert.ok(!(await p.getPermalink(token))?.intact);
+        const [entry] = await p.auditLog(r);
+        assert.strictEqual(entry?.action, "redaction");
+        assert.ok(!JSON.stringify(entry?.details).includes(secret));
+    });
+
+    await it("refs are shared through permissions with one owner at least", async () => {
+        assert.strictEqual(await p.accessLevel(r1, null), "editor");
+        await p.upsertUser("alice", "alice@example.org");
+        const r = await p.newRef("Private Document", "alice");
+        assert.strictEqual(await p.accessLevel(r, "alice"), "owner");
+        assert.strictEqual(await p.accessLevel(r, "bob"), null);
+        assert.strictEqual(await p.accessLevel(r, null), null);
+
+        assert.ok(await p.setPermission(r, "bob", "viewer"));
+        assert.strictEqual(await p.accessLevel(r, "bob"), "viewer");
+        assert.ok(!(await p.setPermission(r, "alice", "editor")));
+        assert.ok(await p.setPermission(r, "bob", "owner"));
+        assert.ok(await p.setPermission(r, "alice", null));
+        assert.deepStrictEqual(
+            (await p.permissions(r)).map((perm) => [perm.userid, perm.level]),
+            [["bob", "owner"]],
+        );
+    });
+
+    await it("unowned refs can be claimed by their first owner", async () => {
+        await p.upsertUser("carol", null);
+        const r = await p.newRef("Legacy Document");
+        assert.strictEqual(await p.accessLevel(r, "carol"), "editor");
+        assert.ok(await p.claimRef(r, "carol"));
+        assert.strictEqual(await p.accessLevel(r, "carol"), "owner");
+        assert.strictEqual(await p.accessLevel(r, null), null);
+        assert.ok(!(await p.claimRef(r, "alice")));
+        assert.strictEqual(await p.accessLevel(r, "alice"), null);
+    });
+
+    await it("several refs are saved together or not at all", async () => {
+        const model = { type: "model", name: "Model" };
+        const analysis = { type: "analysis", name: "Analysis" };
+        const witnesses = await p.saveRefs(
+            [
+                { refId: r2, content: model },
+                { refId: r3, content: analysis },
+            ],
+            "coordinated",
+        );
+        assert.strictEqual(witnesses.length, 2);
+        assert.deepStrictEqual(JSON.parse(await p.getAutosave(r2)), model);
+        assert.deepStrictEqual(JSON.parse(await p.getAutosave(r3)), analysis);
+
+        const missing = "00000000-0000-4000-8000-000000000000";
+        await assert.rejects(
+            p.saveRefs(
+                [
+                    { refId: r2, content: { ...model, name: "Renamed" } },
+                    { refId: missing, content: analysis },
+                ],
+                "partial",
+            ),
+        );
+        assert.deepStrictEqual(JSON.parse(await p.getAutosave(r2)), model);
+    });
+
+    await it("attachments are limited by the quota of their ref", async () => {
+        const a1 = await p.newAttachment(r3, "data.csv", "text/csv", 600, 1000);
+        assert.ok(a1 !== null);
+        assert.strictEqual(await p.newAttachment(r3, "big.png", "image/png", 600, 1000), null);
+        assert.strictEqual(await p.attachmentUsage(r3), 600);
+        assert.deepStrictEqual((await p.getAttachments(r3)).map((a) => a.filename), ["data.csv"]);
+        assert.ok(await p.deleteAttachment(a1));
+        assert.strictEqual(await p.getAttachment(a1), null);
+        assert.ok(await p.newAttachment(r3, "big.png", "image/png", 600, 1000));
+    });
+
+    const job = await p.enqueueJob("test", { n: 1 }, 2);
+
+    await it("jobs are retried until they run out of attempts", async () => {
+        const first = await p.claimJob();
+        assert.strictEqual(first?.id, job);
+        assert.deepStrictEqual(first?.payload, { n: 1 });
+        assert.strictEqual(await p.claimJob(), null);
+        assert.ok(await p.failJob(job, 1, "oops", 0));
+        const second = await p.claimJob();
+        assert.strictEqual(second?.attempts, 2);
+        assert.ok(await p.failJob(job, 2, "oops again", 0));
+        assert.strictEqual(await p.claimJob(), null);
+        const dead = await p.deadJobs();
+        assert.strictEqual(dead[0].id, job);
+        assert.strictEqual(dead[0].lasterror, "oops again");
+    });
+
+    await it("dead jobs can be retried", async () => {
+        assert.ok(await p.retryJob(job));
+        const claimed = await p.claimJob();
+        assert.strictEqual(claimed?.attempts, 1);
+        assert.ok(await p.completeJob(job, 1, { ok: true }));
+        const done = await p.getJob(job);
+        assert.strictEqual(done?.status, "succeeded");
+        assert.deepStrictEqual(done?.result, { ok: true });
+    });
+
+    await it("stale jobs are requeued until they run out of attempts", async () => {
+        const stale = await p.enqueueJob("test", { n: 2 }, 2);
+        assert.strictEqual((await p.claimJob())?.id, stale);
+        assert.deepStrictEqual(await p.requeueStaleJobs(0), [{ id: stale, status: "queued" }]);
+        assert.strictEqual((await p.claimJob())?.attempts, 2);
+        // The first attempt lost the job when it was requeued.
+        assert.ok(!(await p.completeJob(stale, 1, { ok: true })));
+        assert.ok(!(await p.failJob(stale, 1, "late", 0)));
+        assert.deepStrictEqual(await p.requeueStaleJobs(0), [{ id: stale, status: "dead" }]);
+        assert.ok(!(await p.completeJob(stale, 2, { ok: true })));
+        const dead = await p.getJob(stale);
+        assert.strictEqual(dead?.status, "dead");
+        assert.strictEqual(dead?.result, null);
+    });
+
+    await it("search tolerates typos in titles", async () => {
+        const sir = await p.newRef("SIR epidemic model");
+        const lotka = await p.newRef("Lotka-Volterra predator prey");
+        const exact = await p.searchRefs("epidemic", 10);
+        assert.strictEqual(exact[0]?.id, sir);
+        const fuzzy = await p.searchRefs("epidemc", 10);
+        assert.strictEqual(fuzzy[0]?.id, sir);
+        assert.ok(exact[0].score > fuzzy[0].score);
+        const other = await p.searchRefs("Lotka Voltera", 10);
+        assert.strictEqual(other[0]?.id, lotka);
+        assert.deepStrictEqual(await p.searchRefs("thermodynamics", 10), []);
+    });
+
+    await it("search finds documents by the text of their cells", async () => {
+        const r = await p.newRef("Untitled");
+        const cells = [{ tag: "rich-text", id: "c1", content: "Chemostat with nutrient inflow" }];
+        const content = { type: "model", name: "Untitled", notebook: { cells } };
+        await p.autosave(r, JSON.stringify(content));
+        const [result] = await p.searchRefs("nutrient inflow", 10);
+        assert.strictEqual(result?.id, r);
+        assert.strictEqual(result?.type, "model");
+    });
+
+    await it("refs are listed by owner and type, a page at a time", async () => {
+        const owned: string[] = [];
+        for (const name of ["First", "Second", "Third"]) {
+            const r = await p.newRef(name, "carol");
+            await p.autosave(r, JSON.stringify({ type: "analysis", name }));
+            owned.push(r);
+        }
+        const first = await p.listRefs("carol", { owner: "carol", docType: "analysis" }, 0, 2);
+        assert.deepStrictEqual(first.refs.map((ref) => ref.id), [owned[2], owned[1]]);
+        assert.ok(first.hasMore);
+        const rest = await p.listRefs("carol", { owner: "carol" }, 2, 2);
+        assert.deepStrictEqual(rest.refs.map((ref) => ref.type), ["analysis"]);
+        assert.ok(!rest.hasMore);
+        assert.deepStrictEqual((await p.listRefs(null, { owner: "carol" }, 0, 10)).refs, []);
+    });
+
+    await it("collaboration sessions are recorded", async () => {
+        const session = {
+            documentId: "doc" as CollabSession["documentId"],
+            startedAt: new Date(Date.now() - 60_000),
+            endedAt: new Date(),
+            peakPeers: 2,
+            meanPeers: 1.5,
+            participants: [
+                { peerId: "alice", edits: 3 },
+                { peerId: "bob", edits: 4 },
+            ] as CollabSession["participants"],
+        };
+        await p.recordCollabSession(r1, session);
+        const [recorded] = await p.collabSessions(r1, 1);
+        assert.strictEqual(recorded?.edits, 7);
+        assert.strictEqual(recorded?.peakpeers, 2);
+        assert.deepStrictEqual(recorded?.participants, [
+            { peerId: "alice", edits: 3 },
+            { peerId: "bob", edits: 4 },
+        ]);
+    });
+
+    await it("ref lock serializes writers", async () => {
+        const order: string[] = [];
+        let signalLocked = () => {};
+        const locked = new Promise<void>((resolve) => {
+            signalLocked = resolve;
+        });
+        let release = () => {};
+        const held = new Promise<void>((resolve) => {
+            release = resolve;
+        });
+        const firstWriter = p.withRefLock(r1, async () => {
+            signalLocked();
+            await held;
+            order.push("first");
+        });
+        await locked;
+        const secondWriter = p.withRefLock(r1, async () => {
+            order.push("second");
+        });
+        setTimeout(release, 50);
+        await Promise.all([firstWriter, secondWriter]);
+        assert.deepStrictEqual(order, ["first", "second"]);
+    });
+
+    await it("instance archive round-trips through a fresh database", async () => {
+        const archive = await p.exportInstance();
+        await assert.rejects(p.importInstance(archive), ArchiveError);
+        await p.teardown("./migrations");
+        await p.migrate("./migrations");
+        const imported = await p.importInstance(archive);
+        assert.strictEqual(imported.refs, archive.tables.refs?.length);
+        assert.deepStrictEqual((await p.exportInstance()).tables, archive.tables);
+        const ids = (archive.tables.snapshots ?? []).map((row) => row.id as number);
+        assert.ok((await p.saveSnapshot("after import")) > Math.max(...ids));
     });
 
     p.close();
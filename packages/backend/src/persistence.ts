@@ -3,14 +3,46 @@ import * as migration from "./migration.js";
 
 import assert from "node:assert/strict";
 import * as uuid from "uuid";
+import type { AccessLevel } from "./auth.js";
+import type { Conflict } from "./consistency.js";
+import { type InstanceArchive, exportInstance, importInstance } from "./instance_archive.js";
+import { type JsonPatchOp, diffJson } from "./json_diff.js";
 import { type Extern, traverseExterns } from "./links.js";
 import * as queries from "./queries.js";
+import { statementTimeouts } from "./query_limits.js";
+import { type Redaction, redactJson } from "./redaction.js";
+import { ResilientPool, backoff, isConnectionError } from "./resilience.js";
+import type { CollabSession } from "./sessions.js";
 
 export type Witness = queries.IGetWitnessesResult;
 
+/** A witness, optionally with the changes since the previous witness. */
+export type Change = Witness & { diff?: JsonPatchOp[] | null };
+
+/** A page of changes to a ref, as returned by `changesSince`. */
+export type Changes = {
+    changes: Change[];
+
+    /** ID of the newest witness returned, from which to continue polling. */
+    cursor: number;
+
+    /** Whether there are more changes after this page. */
+    hasMore: boolean;
+};
+
+export type Pin = queries.IGetPinsResult;
+
+/** Snapshot of a ref resolved from a permalink.
+
+The snapshot is intact if its content still has the hash that it had when the
+permalink was created, which is no longer the case once it has been redacted.
+ */
+export type Permalink = queries.IGetPermalinkResult;
+
 export type RefMeta = {
     title: string | null;
     witnesses: Witness[];
+    pins: Pin[];
 };
 
 export type Ref = {
@@ -18,13 +50,152 @@ export type Ref = {
     title: string | null;
 };
 
+/** Ref matching a search, with a score that is higher for better matches. */
+export type SearchResult = RefSummary & {
+    /** Slug of the system document, if the ref is one. */
+    slug: string | null;
+
+    score: number;
+};
+
+/** Summary of a ref for listings, without its content. */
+export type RefSummary = {
+    id: string;
+    title: string | null;
+
+    /** Type of the document, such as "model" or "analysis". */
+    type: string | null;
+
+    lastUpdated: Date;
+};
+
+/** Page of refs, most recently updated first. */
+export type RefPage = {
+    refs: RefSummary[];
+
+    /** Whether there are more refs after this page. */
+    hasMore: boolean;
+};
+
+export type Mirror = queries.IGetMirrorResult;
+
+/** Automerge document of a ref saved when it was evicted from memory. */
+export type LiveDocument = {
+    documentId: string;
+
+    /** The document with its history, as exported by the Automerge repo. */
+    binary: Uint8Array;
+
+    /** Content of the document, which was then also its autosave. */
+    content: string;
+};
+
+export type SystemDocumentRef = queries.IGetSystemDocumentsResult;
+
+/** Number of views of a ref and of distinct viewers each day. */
+export type RefViews = {
+    total: number;
+    daily: { day: Date; views: number; viewers: number }[];
+};
+
+/** Summary of the history and storage of a ref.
+
+Snapshots are deduplicated across refs, so `bytes` counts snapshots shared with
+other refs in full.
+ */
+export type RefStats = {
+    /** Number of witnesses, i.e. saved versions. */
+    witnesses: number;
+
+    /** Number of distinct snapshots that are witnessed or autosaved. */
+    snapshots: number;
+
+    /** Total size of those snapshots, in bytes. */
+    bytes: number;
+
+    /** Number of times the ref has been viewed. */
+    views: number;
+};
+
+export type CollabSessionRecord = queries.IGetCollabSessionsResult;
+
+export type AuditEntry = queries.IGetAuditLogResult;
+
+export type Permission = queries.IGetPermissionsResult;
+
+export type Attachment = queries.IGetAttachmentResult;
+
+export type AttachmentSummary = queries.IGetAttachmentsResult;
+
+export type Job = queries.IGetJobResult;
+
+export type ClaimedJob = queries.IClaimJobResult;
+
+export type Json = queries.Json;
+
+/** A database connection: either the pool or a client checked out from it. */
+export type Queryable = ResilientPool | pg.PoolClient;
+
+/** Status of the database connection, as reported by the readiness check. */
+export type DatabaseHealth = {
+    ok: boolean;
+    circuit: "closed" | "open" | "half-open";
+    error?: string;
+};
+
 export class Persistence {
     pool: pg.Pool;
 
+    /** The pool wrapped with retries and a circuit breaker, used for all queries. */
+    conn: ResilientPool;
+
     constructor(url: string) {
-        this.pool = new pg.Pool({
-            connectionString: url,
-        });
+        this.pool = createPool(url);
+        this.conn = new ResilientPool(this.pool);
+    }
+
+    /** Switch to a new database URL, as when its credentials are rotated.
+
+    New queries use a fresh pool, while the old pool is closed once the clients
+    checked out from it, such as those running transactions, are released.
+     */
+    async setUrl(url: string) {
+        const old = this.pool;
+        this.pool = createPool(url);
+        this.conn.pool = this.pool;
+        await old.end();
+    }
+
+    /** Check whether the database is currently reachable, without retrying. */
+    async health(): Promise<DatabaseHealth> {
+        const breaker = this.conn.breaker;
+        if (breaker.state === "open") {
+            return { ok: false, circuit: "open", error: breaker.lastError ?? undefined };
+        }
+        try {
+            await queries.ping.run(void 1, this.pool);
+            breaker.success();
+            return { ok: true, circuit: "closed" };
+        } catch (e) {
+            if (isConnectionError(e)) {
+                breaker.failure(e);
+            }
+            return { ok: false, circuit: breaker.state, error: String(e) };
+        }
+    }
+
+    /** Wait until the database is reachable, retrying with backoff indefinitely. */
+    async waitForDatabase(): Promise<void> {
+        const policy = this.conn.policy;
+        for (let attempt = 1; ; attempt++) {
+            const health = await this.health();
+            if (health.ok) {
+                return;
+            }
+            const delay = Math.round(backoff(policy, attempt));
+            console.error(`Database unavailable (${health.error}), retrying in ${delay}ms`);
+            await new Promise((resolve) => setTimeout(resolve, delay));
+        }
     }
 
     async teardown(migration_dir_path: string) {
@@ -35,43 +206,413 @@ export class Persistence {
         return migration.migrate(this.pool, migration_dir_path);
     }
 
-    async saveSnapshot(content: string): Promise<number> {
-        return first(await queries.newSnapshot.run({ content }, this.pool)).id;
+    /** Run a function in a transaction holding the advisory lock for a ref.
+
+    Every operation that advances the head of a ref should go through this
+    method, so that concurrent writers, possibly on different backend instances,
+    are serialized instead of overwriting each other's updates.
+     */
+    async withRefLock<T>(refId: string, f: (client: pg.PoolClient) => Promise<T>): Promise<T> {
+        return await this.withRefLocks([refId], f);
+    }
+
+    /** Run a function in a transaction holding the advisory locks for several refs.
+
+    The locks are taken in a fixed order, so that concurrent callers locking
+    overlapping sets of refs cannot deadlock.
+     */
+    async withRefLocks<T>(refIds: string[], f: (client: pg.PoolClient) => Promise<T>): Promise<T> {
+        assert(refIds.every((refId) => uuid.validate(refId)));
+        const client = await this.conn.connect();
+        let broken: Error | undefined;
+        try {
+            await client.query("BEGIN");
+            for (const refId of [...new Set(refIds)].sort()) {
+                await queries.lockRef.run({ refId }, client);
+            }
+            const result = await f(client);
+            await client.query("COMMIT");
+            return result;
+        } catch (e) {
+            // A failed rollback must not hide the error that caused it, but it leaves
+            // the connection unusable, so it is discarded.
+            await client.query("ROLLBACK").catch((rollbackError) => {
+                broken = rollbackError;
+            });
+            throw e;
+        } finally {
+            client.release(broken);
+        }
+    }
+
+    async saveSnapshot(content: string, conn: Queryable = this.conn): Promise<number> {
+        return first(await queries.newSnapshot.run({ content }, conn)).id;
     }
 
-    async newRef(title: string | null): Promise<string> {
-        return first(await queries.newRef.run({ title }, this.pool)).id;
+    /** Create a new ref, owned by the given user if any.
+
+    A ref without an owner can be viewed and edited by anyone.
+     */
+    async newRef(title: string | null, owner: string | null = null): Promise<string> {
+        if (owner === null) {
+            return first(await queries.newRef.run({ title }, this.conn)).id;
+        }
+        return first(await queries.newOwnedRef.run({ title, owner }, this.conn)).id;
     }
 
-    async saveRef(refId: string, note: string): Promise<number> {
+    /** Witness the current autosave of a ref, returning the ID of the witness.
+
+    If given, `prepare` computes the content to save from the current autosave,
+    such as by validating or normalizing it. It runs while the ref is locked, so
+    the content that it returns is exactly the content that is witnessed.
+     */
+    async saveRef(
+        refId: string,
+        note: string,
+        prepare?: (content: unknown) => Promise<unknown>,
+    ): Promise<number> {
         assert(uuid.validate(refId));
         assert(typeof note === "string");
-        return first(await queries.saveRef.run({ refId, note }, this.pool)).id;
+        return await this.withRefLock(refId, async (client) => {
+            await this.foldAutosave(refId, client);
+            if (prepare) {
+                const { content } = first(await queries.getAutosave.run({ refId }, client));
+                assert(content !== null);
+                const prepared = JSON.stringify(await prepare(JSON.parse(content)));
+                if (prepared !== content) {
+                    const externs: Extern[] = [];
+                    traverseExterns(JSON.parse(prepared), (e) => externs.push(e));
+                    await this.advanceAutosave(refId, prepared, client);
+                    await this.setExterns(refId, externs, client);
+                }
+            }
+            return first(await queries.saveRef.run({ refId, note }, client)).id;
+        });
+    }
+
+    /** Autosave and witness new content for several refs in one transaction.
+
+    Either every ref advances to its new content or none does. Returns the IDs of
+    the new witnesses, in the order of the updates.
+     */
+    async saveRefs(
+        updates: { refId: string; content: unknown }[],
+        note: string,
+    ): Promise<number[]> {
+        const refIds = updates.map((u) => u.refId);
+        assert.strictEqual(new Set(refIds).size, refIds.length);
+        return await this.withRefLocks(refIds, async (client) => {
+            const witnesses: number[] = [];
+            for (const { refId, content } of updates) {
+                const externs: Extern[] = [];
+                traverseExterns(content, (e) => externs.push(e));
+                await this.advanceAutosave(refId, JSON.stringify(content), client);
+                await this.setExterns(refId, externs, client);
+                witnesses.push(first(await queries.saveRef.run({ refId, note }, client)).id);
+            }
+            return witnesses;
+        });
+    }
+
+    async hasRef(refId: string): Promise<boolean> {
+        if (!uuid.validate(refId)) {
+            return false;
+        }
+        return (await queries.getRefMeta.run({ refId }, this.conn)).length > 0;
+    }
+
+    /** All refs visible to a user, or to anyone signed out if null. */
+    async allRefs(userId: string | null = null): Promise<Ref[]> {
+        return await queries.getRefs.run({ userId }, this.conn);
     }
 
-    async allRefs(): Promise<Ref[]> {
-        return await queries.getRefs.run(void 1, this.pool);
+    /** List the refs visible to a user, most recently updated first.
+
+    Refs can be restricted to those owned by a given user and to documents of a
+    given type. Pages are taken by offset, so refs updated while paging may be
+    skipped or listed twice.
+     */
+    async listRefs(
+        userId: string | null,
+        filter: { owner?: string; docType?: string },
+        offset: number,
+        limit: number,
+    ): Promise<RefPage> {
+        const rows = await queries.listRefs.run(
+            {
+                userId,
+                owner: filter.owner ?? null,
+                docType: filter.docType ?? null,
+                offset,
+                limit: limit + 1,
+            },
+            this.conn,
+        );
+        const refs = rows
+            .slice(0, limit)
+            .map(({ lastupdated, ...row }) => ({ ...row, lastUpdated: lastupdated }));
+        return { refs, hasMore: rows.length > limit };
+    }
+
+    /** Search the refs visible to a user by title and content, and system
+    documents also by slug.
+
+    Titles containing every word of the query rank first, followed by documents
+    whose name, type, or rich-text cells contain every word. Other refs match when
+    the query is similar enough to some part of their title or slug, so that
+    typos and half-remembered titles still find them.
+     */
+    async searchRefs(
+        query: string,
+        limit: number,
+        userId: string | null = null,
+    ): Promise<SearchResult[]> {
+        const results = await queries.searchRefs.run({ query, limit, userId }, this.conn);
+        return results.map(({ lastupdated, score, ...result }) => ({
+            ...result,
+            lastUpdated: lastupdated,
+            score: score ?? 0,
+        }));
     }
 
+    /** Current autosaved content of a ref, including changes not yet folded. */
     async getAutosave(refId: string): Promise<string> {
-        return first(await queries.getAutosave.run({ refId }, this.pool)).content;
+        const { content } = first(await queries.getAutosave.run({ refId }, this.conn));
+        assert(content !== null);
+        return content;
     }
 
+    /** Autosave a ref directly, without the links that it contains.
+
+    Unlike `autosaveWithExterns`, this writes a snapshot immediately and supersedes
+    any autosaves not yet folded.
+     */
     async autosave(refId: string, content: string): Promise<void> {
-        const snapshotId = await this.saveSnapshot(content);
+        await this.withRefLock(refId, (client) => this.advanceAutosave(refId, content, client));
+    }
+
+    /** Autosave a document along with the refs that it points to.
+
+    The document is only appended to the autosave log, which is cheap even for
+    frequent saves of large documents. It becomes the autosave right away for
+    readers, while its snapshot and links are written when the log is folded.
+
+    If `expected` is given, the autosave is only advanced if its current content is
+    exactly `expected`, so that a writer cannot overwrite a change that it has not
+    seen. Returns whether the autosave was advanced.
+     */
+    async autosaveWithExterns(refId: string, doc: unknown, expected?: string): Promise<boolean> {
+        const content = JSON.stringify(doc);
+        return await this.withRefLock(refId, async (client) => {
+            if (expected !== undefined) {
+                const current = (await queries.getAutosave.run({ refId }, client))[0]?.content;
+                if (current !== expected) {
+                    return false;
+                }
+            }
+            await queries.appendAutosave.run({ refId, content }, client);
+            return true;
+        });
+    }
+
+    /** Point the autosave of a ref at new content, discarding any autosaves not yet
+    folded. Caller must hold the ref lock.
+     */
+    private async advanceAutosave(refId: string, content: string, client: pg.PoolClient) {
+        const snapshotId = await this.saveSnapshot(content, client);
         assert.strictEqual(typeof snapshotId, "number");
-        await queries.autosave.run({ refId, snapshotId }, this.pool);
+        await queries.autosave.run({ refId, snapshotId }, client);
+        await queries.clearAutosaveLog.run({ refId }, client);
     }
 
-    async autosaveWithExterns(refId: string, doc: unknown): Promise<void> {
+    /** Fold the autosave log of a ref into a snapshot, updating the links from the
+    ref to match. Caller must hold the ref lock.
+     */
+    private async foldAutosave(refId: string, client: pg.PoolClient) {
+        const pending = (await queries.getPendingAutosave.run({ refId }, client))[0];
+        if (!pending) {
+            return;
+        }
         const externs: Extern[] = [];
-        traverseExterns(doc, (e) => externs.push(e));
-        await this.autosave(refId, JSON.stringify(doc));
-        await this.setExterns(refId, externs);
+        traverseExterns(JSON.parse(pending.content), (e) => externs.push(e));
+        await this.advanceAutosave(refId, pending.content, client);
+        await this.setExterns(refId, externs, client);
+    }
+
+    /** Fold the autosave logs of up to `limit` refs, returning the number folded.
+
+    A ref that fails to fold is logged and skipped, so that it cannot hold up
+    the others.
+     */
+    async foldAutosaves(limit: number): Promise<number> {
+        const refs = await queries.getRefsWithPendingAutosaves.run({ limit }, this.conn);
+        let folded = 0;
+        for (const { ref: refId } of refs) {
+            try {
+                await this.withRefLock(refId, (client) => this.foldAutosave(refId, client));
+                folded++;
+            } catch (e) {
+                console.error(`Failed to fold autosaves of ref ${refId}:`, e);
+            }
+        }
+        return folded;
+    }
+
+    /** Remove content from every snapshot of a ref, recording the redaction in the
+    audit log.
+
+    The snapshots of a ref include those taken for its analysis jobs. Each snapshot
+    is replaced by a redacted copy, and the original is deleted
+    unless another ref still uses it. The strings redacted are not recorded, since
+    they may be secrets. Returns the number of snapshots that changed.
+     */
+    async redactRef(refId: string, redaction: Redaction): Promise<number> {
+        return await this.withRefLock(refId, async (client) => {
+            await this.foldAutosave(refId, client);
+            const snapshots = await queries.getRefSnapshots.run({ refId }, client);
+            let redacted = 0;
+            for (const { id: oldId, content } of snapshots) {
+                const before = JSON.parse(content);
+                const after = redactJson(before, redaction);
+                if (JSON.stringify(after) === JSON.stringify(before)) {
+                    continue;
+                }
+                const newId = await this.saveSnapshot(JSON.stringify(after), client);
+                await queries.replaceRefSnapshot.run({ refId, oldId, newId }, client);
+                await queries.deleteSnapshotIfOrphaned.run({ id: oldId }, client);
+                redacted++;
+            }
+            const autosave = (await queries.getAutosave.run({ refId }, client))[0];
+            if (autosave?.content) {
+                const externs: Extern[] = [];
+                traverseExterns(JSON.parse(autosave.content), (e) => externs.push(e));
+                await this.setExterns(refId, externs, client);
+            }
+
+            // The live content of a conflict and the history of the evicted live
+            // document are dropped along with the live document.
+            await queries.deleteConflict.run({ refId }, client);
+            await queries.deleteLiveDocument.run({ refId }, client);
+
+            const { reason, paths, ids, strings } = redaction;
+            const details = { reason, paths, ids, strings: strings.length, snapshots: redacted };
+            await queries.newAuditEntry.run({ refId, action: "redaction", details }, client);
+            return redacted;
+        });
+    }
+
+    /** Record that a live document conflicts with its autosave, or update the live
+    content of a conflict already recorded.
+     */
+    async recordConflict(refId: string, content: string): Promise<void> {
+        await queries.recordConflict.run({ refId, content }, this.conn);
+    }
+
+    async deleteConflict(refId: string): Promise<void> {
+        await queries.deleteConflict.run({ refId }, this.conn);
+    }
+
+    /** Get the conflict of a ref, with its live content, if it has one. */
+    async getConflict(refId: string): Promise<{ content: string; detectedAt: Date } | null> {
+        const row = (await queries.getConflict.run({ refId }, this.conn))[0];
+        return row ? { content: row.content, detectedAt: row.detectedat } : null;
+    }
+
+    /** All unresolved conflicts, oldest first. */
+    async conflicts(): Promise<Conflict[]> {
+        const rows = await queries.getConflicts.run(void 1, this.conn);
+        return rows.map((row) => ({ refId: row.ref, detectedAt: row.detectedat }));
+    }
+
+    /** Save the Automerge document of a ref, with its history, when it leaves memory.
+
+    Only the last document of each ref is kept, along with its ID and content.
+     */
+    async saveLiveDocument(
+        refId: string,
+        documentId: string,
+        binary: Uint8Array,
+        content: string,
+    ): Promise<void> {
+        const params = { refId, documentId, data: Buffer.from(binary), content };
+        await queries.saveLiveDocument.run(params, this.conn);
     }
 
-    async setExterns(refId: string, externs: Extern[]): Promise<void> {
-        await queries.dropExternsFrom.run({ refId }, this.pool);
+    /** Get the last saved Automerge document of a ref, if any. */
+    async getLiveDocument(refId: string): Promise<LiveDocument | null> {
+        const row = (await queries.getLiveDocument.run({ refId }, this.conn))[0];
+        if (!row) {
+            return null;
+        }
+        return { documentId: row.documentid, binary: row.data, content: row.content };
+    }
+
+    /** IDs of the saved Automerge documents, by ref. */
+    async liveDocumentIds(): Promise<Map<string, string>> {
+        const rows = await queries.getLiveDocumentIds.run(void 1, this.conn);
+        return new Map(rows.map((row) => [row.ref, row.documentid]));
+    }
+
+    /** Record that a user signed in, keeping their email up to date. */
+    async upsertUser(id: string, email: string | null): Promise<void> {
+        await queries.upsertUser.run({ id, email }, this.conn);
+    }
+
+    /** Level of access of a user, or of anyone signed out if null, to a ref. */
+    async accessLevel(refId: string, userId: string | null): Promise<AccessLevel | null> {
+        const rows = await queries.getAccessLevel.run({ refId, userId }, this.conn);
+        return (rows[0]?.level as AccessLevel | undefined) ?? null;
+    }
+
+    async permissions(refId: string): Promise<Permission[]> {
+        return await queries.getPermissions.run({ refId }, this.conn);
+    }
+
+    /** Grant a user a level of access to a ref, or revoke their access if null.
+
+    A ref with any permissions must have an owner. Returns whether the permission
+    was changed, which it is not if it would leave the ref without an owner.
+     */
+    async setPermission(
+        refId: string,
+        userId: string,
+        level: AccessLevel | null,
+    ): Promise<boolean> {
+        return await this.withRefLock(refId, async (client) => {
+            const permissions = await queries.getPermissions.run({ refId }, client);
+            const owners = permissions.filter((p) => p.level === "owner" && p.userid !== userId);
+            if (owners.length === 0 && level !== "owner") {
+                return false;
+            }
+            if (level === null) {
+                await queries.removePermission.run({ refId, userId }, client);
+            } else {
+                await queries.setPermission.run({ refId, userId, level }, client);
+            }
+            return true;
+        });
+    }
+
+    /** Make a user the owner of a ref that has no permissions, and so no owner.
+
+    Returns whether the ref was claimed, which it is not if it already had an owner.
+     */
+    async claimRef(refId: string, userId: string): Promise<boolean> {
+        return await this.withRefLock(refId, async (client) => {
+            if ((await queries.getPermissions.run({ refId }, client)).length > 0) {
+                return false;
+            }
+            await queries.setPermission.run({ refId, userId, level: "owner" }, client);
+            return true;
+        });
+    }
+
+    async auditLog(refId: string): Promise<AuditEntry[]> {
+        return await queries.getAuditLog.run({ refId }, this.conn);
+    }
+
+    async setExterns(refId: string, externs: Extern[], conn: Queryable = this.conn): Promise<void> {
+        await queries.dropExternsFrom.run({ refId }, conn);
         if (externs.length > 0) {
             await queries.insertNewExterns.run(
                 {
@@ -84,20 +625,314 @@ export class Persistence {
                         };
                     }),
                 },
-                this.pool,
+                conn,
             );
         }
     }
 
     async getBacklinks(refId: string, taxon: string): Promise<string[]> {
-        const result = await queries.getBacklinks.run({ toRef: refId, taxon }, this.pool);
+        const result = await queries.getBacklinks.run({ toRef: refId, taxon }, this.conn);
         return result.map((r) => r.fromref);
     }
 
     async refMeta(refId: string): Promise<RefMeta> {
-        const meta = first(await queries.getRefMeta.run({ refId }, this.pool));
-        const witnesses = await queries.getWitnesses.run({ refId }, this.pool);
-        return { ...meta, witnesses };
+        const meta = first(await queries.getRefMeta.run({ refId }, this.conn));
+        const witnesses = await queries.getWitnesses.run({ refId }, this.conn);
+        const pins = await queries.getPins.run({ refId }, this.conn);
+        return { ...meta, witnesses, pins };
+    }
+
+    /** Pin a snapshot of a ref, so that it is exempt from any retention policy.
+
+    Only snapshots that are witnessed or autosaved for the ref can be pinned.
+    Returns whether the snapshot was pinned.
+     */
+    async pinSnapshot(refId: string, snapshotId: number, note: string): Promise<boolean> {
+        return (await queries.pinSnapshot.run({ refId, snapshotId, note }, this.conn)).length > 0;
+    }
+
+    async unpinSnapshot(refId: string, snapshotId: number): Promise<boolean> {
+        return (await queries.unpinSnapshot.run({ refId, snapshotId }, this.conn)).length > 0;
+    }
+
+    /** Create a permalink to a snapshot of a ref, or get the existing one.
+
+    Only snapshots that are witnessed or autosaved for the ref can be linked. The
+    snapshot is kept for as long as the permalink exists, whatever happens to the
+    ref later. Returns the token of the permalink, the hex-encoded SHA-256 hash of
+    the ID of the ref followed by the hash of the content, or null if the snapshot
+    is not part of the ref. Refs with the same content have different permalinks.
+     */
+    async createPermalink(refId: string, snapshotId: number): Promise<string | null> {
+        const rows = await queries.newPermalink.run({ refId, snapshotId }, this.conn);
+        return rows[0]?.token ?? null;
+    }
+
+    async getPermalink(token: string): Promise<Permalink | null> {
+        if (!/^[0-9a-f]{64}$/.test(token)) {
+            return null;
+        }
+        return (await queries.getPermalink.run({ token }, this.conn))[0] ?? null;
+    }
+
+    async getSnapshot(id: number): Promise<string | null> {
+        return (await queries.getSnapshot.run({ id }, this.conn))[0]?.content ?? null;
+    }
+
+    /** Get the content of a snapshot, if it is witnessed or autosaved for a ref. */
+    async getRefSnapshot(refId: string, snapshotId: number): Promise<string | null> {
+        const rows = await queries.getRefSnapshot.run({ refId, snapshotId }, this.conn);
+        return rows[0]?.content ?? null;
+    }
+
+    async getWitness(refId: string, id: number): Promise<Witness | null> {
+        return (await queries.getWitness.run({ refId, id }, this.conn))[0] ?? null;
+    }
+
+    /** List the witnesses of a ref newer than a given witness, oldest first.
+
+    Witness IDs increase over time, so the ID of the last witness returned can be
+    passed as `since` to continue from there; use 0 to start from the beginning.
+    If `withDiffs` is set, each change includes a JSON Patch from the content of
+    the previous witness, which is null for the first witness of the ref.
+     */
+    async changesSince(
+        refId: string,
+        since: number,
+        limit: number,
+        withDiffs = false,
+    ): Promise<Changes> {
+        const witnesses = await queries.getWitnessesSince.run(
+            { refId, since, limit: limit + 1 },
+            this.conn,
+        );
+        const page: Change[] = witnesses.slice(0, limit);
+        if (withDiffs) {
+            let previous: unknown = null;
+            const previousWitness = since > 0 ? await this.getWitness(refId, since) : null;
+            if (previousWitness) {
+                previous = await this.snapshotContent(previousWitness.snapshot);
+            }
+            for (const change of page) {
+                const content = await this.snapshotContent(change.snapshot);
+                change.diff = previous === null ? null : diffJson(previous, content);
+                previous = content;
+            }
+        }
+        return {
+            changes: page,
+            cursor: page.at(-1)?.id ?? since,
+            hasMore: witnesses.length > limit,
+        };
+    }
+
+    private async snapshotContent(id: number): Promise<unknown> {
+        return JSON.parse((await this.getSnapshot(id)) ?? "null");
+    }
+
+    async newMirror(refId: string, sourceUrl: string): Promise<void> {
+        await queries.newMirror.run({ refId, sourceUrl }, this.conn);
+    }
+
+    async getMirror(refId: string): Promise<Mirror | null> {
+        if (!uuid.validate(refId)) {
+            return null;
+        }
+        return (await queries.getMirror.run({ refId }, this.conn))[0] ?? null;
+    }
+
+    async allMirrors(): Promise<Mirror[]> {
+        return await queries.getMirrors.run(void 1, this.conn);
+    }
+
+    /** Record the outcome of refreshing a mirror; `error` is null on success. */
+    async setMirrorStatus(refId: string, error: string | null): Promise<void> {
+        const lastFetched = error === null ? new Date() : null;
+        await queries.updateMirrorStatus.run({ refId, lastFetched, lastError: error }, this.conn);
+    }
+
+    /** Whether a ref is read-only, because it is a mirror or a system document. */
+    async isReadOnly(refId: string): Promise<boolean> {
+        if (!uuid.validate(refId)) {
+            return false;
+        }
+        return first(await queries.isReadOnly.run({ refId }, this.conn)).readonly === true;
+    }
+
+    async newSystemDocument(slug: string, refId: string, version: number): Promise<void> {
+        await queries.newSystemDocument.run({ slug, refId, version }, this.conn);
+    }
+
+    async allSystemDocuments(): Promise<SystemDocumentRef[]> {
+        return await queries.getSystemDocuments.run(void 1, this.conn);
+    }
+
+    async setSystemDocumentVersion(slug: string, version: number): Promise<void> {
+        await queries.updateSystemDocumentVersion.run({ slug, version }, this.conn);
+    }
+
+    async recordView(refId: string, viewer: string): Promise<void> {
+        await queries.recordView.run({ refId, viewer }, this.conn);
+    }
+
+    /** Count the views of a ref, in total and for each of the last `days` days. */
+    async refViews(refId: string, days: number): Promise<RefViews> {
+        const total = first(await queries.getViewCount.run({ refId }, this.conn)).views ?? 0;
+        const daily = await queries.getDailyViews.run({ refId, days }, this.conn);
+        return {
+            total,
+            daily: daily.map((row) => ({
+                day: row.day as Date,
+                views: row.views ?? 0,
+                viewers: row.viewers ?? 0,
+            })),
+        };
+    }
+
+    /** Record a collaboration session on a ref. */
+    async recordCollabSession(refId: string, session: CollabSession): Promise<void> {
+        const { startedAt, endedAt, peakPeers, meanPeers, participants } = session;
+        const edits = participants.reduce((total, p) => total + p.edits, 0);
+        await queries.newCollabSession.run(
+            {
+                refId,
+                startedAt,
+                endedAt,
+                peakPeers,
+                meanPeers,
+                edits,
+                // Serialized here, since `pg` would encode an array as a Postgres array.
+                participants: JSON.stringify(participants),
+            },
+            this.conn,
+        );
+    }
+
+    /** Collaboration sessions on a ref that started in the last `days` days. */
+    async collabSessions(refId: string, days: number): Promise<CollabSessionRecord[]> {
+        return await queries.getCollabSessions.run({ refId, days }, this.conn);
+    }
+
+    /** Summarize the history and storage of a ref. */
+    async refStats(refId: string): Promise<RefStats> {
+        const stats = first(await queries.getRefStats.run({ refId }, this.conn));
+        const views = first(await queries.getViewCount.run({ refId }, this.conn)).views;
+        return {
+            witnesses: stats.witnesses ?? 0,
+            snapshots: stats.snapshots ?? 0,
+            bytes: Number(stats.bytes ?? 0),
+            views: views ?? 0,
+        };
+    }
+
+    /** Record an attachment of a ref, unless it would exceed the quota of the ref.
+
+    Returns the ID of the new attachment, or null if the quota, in bytes, would be
+    exceeded.
+     */
+    async newAttachment(
+        refId: string,
+        filename: string,
+        contentType: string,
+        size: number,
+        quota: number,
+    ): Promise<number | null> {
+        return await this.withRefLock(refId, async (client) => {
+            const params = { refId, filename, contentType, size, quota };
+            return (await queries.newAttachment.run(params, client))[0]?.id ?? null;
+        });
+    }
+
+    async getAttachment(id: number): Promise<Attachment | null> {
+        return (await queries.getAttachment.run({ id }, this.conn))[0] ?? null;
+    }
+
+    async getAttachments(refId: string): Promise<AttachmentSummary[]> {
+        return await queries.getAttachments.run({ refId }, this.conn);
+    }
+
+    /** Total size in bytes of the attachments of a ref. */
+    async attachmentUsage(refId: string): Promise<number> {
+        return Number(first(await queries.getAttachmentUsage.run({ refId }, this.conn)).bytes ?? 0);
+    }
+
+    async deleteAttachment(id: number): Promise<boolean> {
+        return (await queries.deleteAttachment.run({ id }, this.conn)).length > 0;
+    }
+
+    async enqueueJob(kind: string, payload: Json, maxAttempts: number): Promise<number> {
+        return first(await queries.enqueueJob.run({ kind, payload, maxAttempts }, this.conn)).id;
+    }
+
+    /** Claim the next runnable job, if any, marking it as running. */
+    async claimJob(): Promise<ClaimedJob | null> {
+        return (await queries.claimJob.run(void 1, this.conn))[0] ?? null;
+    }
+
+    /** Record the result of a job, returning whether the attempt still held the job.
+
+    An attempt loses the job if it is requeued as stale, after which another attempt
+    may claim it. Results reported by an attempt that lost the job are discarded.
+     */
+    async completeJob(id: number, attempt: number, result: Json): Promise<boolean> {
+        return (await queries.completeJob.run({ id, attempt, result }, this.conn)).length > 0;
+    }
+
+    /** Record a failed attempt, scheduling a retry unless no attempts are left.
+
+    Returns whether the attempt still held the job, as for `completeJob`.
+     */
+    async failJob(
+        id: number,
+        attempt: number,
+        error: string,
+        backoffSeconds: number,
+        permanent = false,
+    ): Promise<boolean> {
+        const params = { id, attempt, error, backoffSeconds, permanent };
+        return (await queries.failJob.run(params, this.conn)).length > 0;
+    }
+
+    /** Requeue jobs whose worker has not reported back in time.
+
+    A lost attempt counts against the attempts of a job, so jobs that have run out
+    of attempts are sent to the dead jobs instead.
+     */
+    async requeueStaleJobs(staleSeconds: number): Promise<{ id: number; status: string }[]> {
+        return await queries.requeueStaleJobs.run({ staleSeconds }, this.conn);
+    }
+
+    async getJob(id: number): Promise<Job | null> {
+        return (await queries.getJob.run({ id }, this.conn))[0] ?? null;
+    }
+
+    async deadJobs(): Promise<Job[]> {
+        return await queries.getDeadJobs.run(void 1, this.conn);
+    }
+
+    /** Send a dead job back to the queue, returning whether there was such a job. */
+    async retryJob(id: number): Promise<boolean> {
+        return (await queries.retryJob.run({ id }, this.conn)).length > 0;
+    }
+
+    /** Export the whole database, for moving the instance to another server. */
+    async exportInstance(): Promise<InstanceArchive> {
+        const client = await this.conn.connect();
+        try {
+            return await exportInstance(client);
+        } finally {
+            client.release();
+        }
+    }
+
+    /** Import an exported database into this one, which must be fresh. */
+    async importInstance(archive: InstanceArchive): Promise<Record<string, number>> {
+        const client = await this.conn.connect();
+        try {
+            return await importInstance(client, archive);
+        } finally {
+            client.release();
+        }
     }
 
     async close() {
@@ -105,6 +940,16 @@ export class Persistence {
     }
 }
 
+function createPool(url: string): pg.Pool {
+    const pool = new pg.Pool({
+        connectionString: url,
+        statement_timeout: statementTimeouts.interactive,
+    });
+    // Without a listener, an idle client losing its connection crashes the process.
+    pool.on("error", (e) => console.error("Idle database connection failed:", e));
+    return pool;
+}
+
 function first<T>(array: Array<T>): T {
     assert(array[0]);
     return array[0];
@@ -0,0 +1,107 @@
+import assert from "node:assert";
+import * as fs from "node:fs/promises";
+import * as os from "node:os";
+import * as path from "node:path";
+import { it, test } from "node:test";
+import { PluginError, PluginRegistry, PluginRejection, runSandboxed } from "./plugins.js";
+
+// A module whose `run` returns its input unchanged.
+const echoWasm = new Uint8Array([
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0c, 0x02, 0x60,
+    0x01, 0x7f, 0x01, 0x7f, 0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7e, 0x02, 0x0f,
+    0x01, 0x03, 0x65, 0x6e, 0x76, 0x06, 0x6d, 0x65, 0x6d, 0x6f, 0x72, 0x79,
+    0x02, 0x00, 0x01, 0x03, 0x03, 0x02, 0x00, 0x01, 0x07, 0x0f, 0x02, 0x05,
+    0x61, 0x6c, 0x6c, 0x6f, 0x63, 0x00, 0x00, 0x03, 0x72, 0x75, 0x6e, 0x00,
+    0x01, 0x0a, 0x14, 0x02, 0x05, 0x00, 0x41, 0x80, 0x08, 0x0b, 0x0c, 0x00,
+    0x20, 0x00, 0xad, 0x42, 0x20, 0x86, 0x20, 0x01, 0xad, 0x84, 0x0b,
+]);
+
+// A module whose `run` never returns.
+const spinWasm = new Uint8Array([
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0c, 0x02, 0x60,
+    0x01, 0x7f, 0x01, 0x7f, 0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7e, 0x02, 0x0f,
+    0x01, 0x03, 0x65, 0x6e, 0x76, 0x06, 0x6d, 0x65, 0x6d, 0x6f, 0x72, 0x79,
+    0x02, 0x00, 0x01, 0x03, 0x03, 0x02, 0x00, 0x01, 0x07, 0x0f, 0x02, 0x05,
+    0x61, 0x6c, 0x6c, 0x6f, 0x63, 0x00, 0x00, 0x03, 0x72, 0x75, 0x6e, 0x00,
+    0x01, 0x0a, 0x11, 0x02, 0x05, 0x00, 0x41, 0x80, 0x08, 0x0b, 0x09, 0x00,
+    0x03, 0x40, 0x0c, 0x00, 0x0b, 0x42, 0x00, 0x0b,
+]);
+
+const limits = { timeoutMs: 1000, maxMemoryMb: 1 };
+
+test("WebAssembly plugins", async (_t) => {
+    const echo = await WebAssembly.compile(echoWasm);
+    const spin = await WebAssembly.compile(spinWasm);
+    const doc = { type: "model", name: "Example" };
+
+    await it("sandbox passes JSON through the module", async () => {
+        assert.deepStrictEqual(await runSandboxed(echo, { a: [1, 2] }, limits), { a: [1, 2] });
+    });
+
+    await it("sandbox enforces the time limit", async () => {
+        await assert.rejects(runSandboxed(spin, {}, { ...limits, timeoutMs: 100 }), PluginError);
+    });
+
+    await it("plugins only run on their document type", async () => {
+        const manifest = {
+            ...limits,
+            name: "spin",
+            kind: "transformer" as const,
+            docType: "diagram",
+            path: "",
+        };
+        const registry = new PluginRegistry([{ manifest, module: spin }]);
+        assert.deepStrictEqual(await registry.onSave(doc), doc);
+    });
+
+    await it("validator output must list errors", async () => {
+        const manifest = {
+            ...limits,
+            name: "echo",
+            kind: "validator" as const,
+            docType: "model",
+            path: "",
+        };
+        const registry = new PluginRegistry([{ manifest, module: echo }]);
+        await assert.rejects(registry.onSave(doc), PluginError);
+    });
+
+    await it("validator errors reject the document", async () => {
+        const manifest = {
+            ...limits,
+            name: "echo",
+            kind: "validator" as const,
+            docType: "model",
+            path: "",
+        };
+        const registry = new PluginRegistry([{ manifest, module: echo }]);
+        await assert.rejects(registry.onSave({ ...doc, errors: ["no units"] }), PluginRejection);
+    });
+
+    await it("analyses are listed and looked up by name", async () => {
+        const manifest = {
+            ...limits,
+            name: "echo",
+            kind: "analysis" as const,
+            docType: "model",
+            path: "",
+        };
+        const registry = new PluginRegistry([{ manifest, module: echo }]);
+        assert.deepStrictEqual(registry.analysisKinds(), [{ name: "echo", docType: "model" }]);
+        assert.deepStrictEqual(await registry.onSave(doc), doc);
+        await assert.rejects(registry.runAnalysis("missing", doc, null), PluginError);
+        const diagram = { type: "diagram", name: "Example" };
+        await assert.rejects(registry.runAnalysis("echo", diagram, null), PluginError);
+    });
+
+    await it("registry loads modules listed in a manifest", async () => {
+        const dir = await fs.mkdtemp(path.join(os.tmpdir(), "catcolab-plugins-"));
+        await fs.writeFile(path.join(dir, "echo.wasm"), echoWasm);
+        const manifest = [{ name: "echo", kind: "validator", docType: "model", path: "echo.wasm" }];
+        await fs.writeFile(path.join(dir, "plugins.json"), JSON.stringify(manifest));
+        const registry = await PluginRegistry.load(path.join(dir, "plugins.json"));
+        assert.strictEqual(registry.plugins.length, 1);
+        assert.strictEqual(registry.plugins[0].manifest.timeoutMs, 1000);
+        await fs.rm(dir, { recursive: true });
+    });
+});
@@ -0,0 +1,217 @@
+import * as fs from "node:fs/promises";
+import * as path from "node:path";
+import { Worker } from "node:worker_threads";
+import * as z from "zod";
+import { DocumentContent } from "./document.js";
+
+/** Manifest entry describing a plugin installed by the operator.
+
+Plugins are WebAssembly modules that import their memory as `env.memory` and
+export two functions:
+
+- `alloc(len: i32) -> i32`, returning a pointer to `len` writable bytes;
+- `run(ptr: i32, len: i32) -> i64`, taking the UTF-8 JSON input at `ptr` and
+  returning the location of its UTF-8 JSON output packed as `(ptr << 32) | len`.
+
+Validators and transformers run when a document is saved. Their input is the
+document content. A validator outputs `{ "errors": [...] }`, where an empty list
+accepts the document, and a transformer outputs `{ "content": ... }` with the
+replacement content.
+
+Analyses run on request and are exposed to clients as additional analysis
+kinds. Their input is `{ "content": ..., "params": ... }` and their output is
+`{ "result": ... }`, with the result passed back to the client as is.
+ */
+const PluginManifest = z.object({
+    name: z.string(),
+    kind: z.enum(["validator", "transformer", "analysis"]),
+    /// The type of document that the plugin applies to, e.g. "model"
+    docType: z.string(),
+    /// Path to the WebAssembly module, relative to the manifest file
+    path: z.string(),
+    timeoutMs: z.number().int().positive().default(1000),
+    maxMemoryMb: z.number().int().positive().default(16),
+});
+
+export type PluginManifest = z.infer<typeof PluginManifest>;
+
+const ValidatorOutput = z.object({ errors: z.array(z.string()) });
+
+const TransformerOutput = z.object({ content: DocumentContent });
+
+const AnalysisOutput = z.object({ result: z.unknown() });
+
+/** Description of an analysis provided by a plugin, as reported to clients. */
+export type AnalysisKind = {
+    name: string;
+    docType: string;
+};
+
+/** Limits on a single sandboxed execution. */
+export type SandboxLimits = {
+    timeoutMs: number;
+    maxMemoryMb: number;
+};
+
+/** Error raised when a plugin fails to run or produces malformed output. */
+export class PluginError extends Error {}
+
+/** Error raised when validators reject a document. */
+export class PluginRejection extends Error {
+    constructor(readonly errors: string[]) {
+        super(errors.join("; "));
+    }
+}
+
+// Runs inside the worker. It is plain JavaScript so that it does not depend on
+// how the backend itself was compiled or loaded.
+const workerSource = `
+const { parentPort, workerData } = require("node:worker_threads");
+(async () => {
+    const { module, input, maxMemoryPages } = workerData;
+    const memory = new WebAssembly.Memory({ initial: 1, maximum: maxMemoryPages });
+    const instance = await WebAssembly.instantiate(module, { env: { memory } });
+    const { alloc, run } = instance.exports;
+    const bytes = new TextEncoder().encode(input);
+    const ptr = alloc(bytes.length);
+    new Uint8Array(memory.buffer, ptr, bytes.length).set(bytes);
+    const packed = BigInt.asUintN(64, run(ptr, bytes.length));
+    const outPtr = Number(packed >> 32n);
+    const outLen = Number(packed & 0xffffffffn);
+    parentPort.postMessage(new TextDecoder().decode(new Uint8Array(memory.buffer, outPtr, outLen)));
+})();
+`;
+
+/** Run a WebAssembly module on a JSON input, in a worker thread with resource limits.
+
+The module can only touch the memory we give it, which is capped in size. The
+worker isolates it from the event loop, is given an empty environment, and is
+terminated if it does not finish in time.
+ */
+export function runSandboxed(
+    module: WebAssembly.Module,
+    input: unknown,
+    limits: SandboxLimits,
+): Promise<unknown> {
+    return new Promise((resolve, reject) => {
+        const worker = new Worker(workerSource, {
+            eval: true,
+            env: {},
+            workerData: {
+                module,
+                input: JSON.stringify(input),
+                maxMemoryPages: Math.ceil((limits.maxMemoryMb * 1024 * 1024) / 65536),
+            },
+            resourceLimits: { maxOldGenerationSizeMb: 32, maxYoungGenerationSizeMb: 8 },
+        });
+        const timer = setTimeout(() => {
+            worker.terminate();
+            reject(new PluginError(`timed out after ${limits.timeoutMs}ms`));
+        }, limits.timeoutMs);
+        worker.once("message", (output: string) => {
+            clearTimeout(timer);
+            worker.terminate();
+            try {
+                resolve(JSON.parse(output));
+            } catch {
+                reject(new PluginError("output is not valid JSON"));
+            }
+        });
+        worker.once("error", (e) => {
+            clearTimeout(timer);
+            reject(new PluginError(e.message));
+        });
+    });
+}
+
+type Plugin = {
+    manifest: PluginManifest;
+    module: WebAssembly.Module;
+};
+
+/** The plugins installed by the operator. */
+export class PluginRegistry {
+    constructor(readonly plugins: Plugin[] = []) {}
+
+    /** Load the plugins listed in a JSON manifest file. */
+    static async load(manifestPath: string): Promise<PluginRegistry> {
+        const json = JSON.parse(await fs.readFile(manifestPath, { encoding: "utf-8" }));
+        const manifests = z.array(PluginManifest).parse(json);
+        const plugins: Plugin[] = [];
+        for (const manifest of manifests) {
+            const wasm = await fs.readFile(path.resolve(path.dirname(manifestPath), manifest.path));
+            plugins.push({ manifest, module: await WebAssembly.compile(wasm) });
+            console.info(`loaded ${manifest.kind} plugin: ${manifest.name}`);
+        }
+        return new PluginRegistry(plugins);
+    }
+
+    /** Load the plugins configured by the `PLUGINS_PATH` environment variable, if any. */
+    static async fromEnv(): Promise<PluginRegistry> {
+        const manifestPath = process.env.PLUGINS_PATH;
+        return manifestPath ? PluginRegistry.load(manifestPath) : new PluginRegistry();
+    }
+
+    /** Run the plugins for a document being saved, in the order they are listed.
+
+    Returns the content after all transformers have been applied, or throws
+    `PluginRejection` if any validator reports errors.
+     */
+    async onSave(content: DocumentContent): Promise<DocumentContent> {
+        const errors: string[] = [];
+        for (const { manifest, module } of this.plugins) {
+            if (manifest.kind === "analysis" || manifest.docType !== content.type) {
+                continue;
+            }
+            const output = await runSandboxed(module, content, manifest).catch((e) => {
+                throw new PluginError(`plugin ${manifest.name} failed: ${e.message}`);
+            });
+            if (manifest.kind === "validator") {
+                const result = ValidatorOutput.safeParse(output);
+                if (!result.success) {
+                    throw new PluginError(`plugin ${manifest.name} produced malformed output`);
+                }
+                errors.push(...result.data.errors.map((e) => `${manifest.name}: ${e}`));
+            } else {
+                const result = TransformerOutput.safeParse(output);
+                if (!result.success) {
+                    throw new PluginError(`plugin ${manifest.name} produced malformed output`);
+                }
+                content = result.data.content;
+            }
+        }
+        if (errors.length > 0) {
+            throw new PluginRejection(errors);
+        }
+        return content;
+    }
+
+    /** List the analyses provided by plugins. */
+    analysisKinds(): AnalysisKind[] {
+        return this.plugins
+            .filter(({ manifest }) => manifest.kind === "analysis")
+            .map(({ manifest: { name, docType } }) => ({ name, docType }));
+    }
+
+    /** Run the analysis with the given name on a document. */
+    async runAnalysis(name: string, content: DocumentContent, params: unknown): Promise<unknown> {
+        const plugin = this.plugins.find(
+            ({ manifest }) => manifest.kind === "analysis" && manifest.name === name,
+        );
+        if (!plugin) {
+            throw new PluginError(`no such analysis: ${name}`);
+        }
+        const { manifest, module } = plugin;
+        if (manifest.docType !== content.type) {
+            throw new PluginError(`analysis ${name} does not apply to ${content.type} documents`);
+        }
+        const output = await runSandboxed(module, { content, params }, manifest).catch((e) => {
+            throw new PluginError(`analysis ${name} failed: ${e.message}`);
+        });
+        const result = AnalysisOutput.safeParse(output);
+        if (!result.success) {
+            throw new PluginError(`analysis ${name} produced malformed output`);
+        }
+        return result.data.result;
+    }
+}
@@ -4,10 +4,13 @@ SET autosave = :snapshotId, lastUpdated = NOW()
 WHERE id = :refId;
 
 /* @name GetAutosave */
-SELECT snapshots.content as content
+SELECT COALESCE(pending.content, snapshots.content) AS content
 FROM refs
-INNER JOIN snapshots ON refs.autosave = snapshots.id
-WHERE refs.id = :refId;
+LEFT JOIN snapshots ON refs.autosave = snapshots.id
+LEFT JOIN LATERAL (
+    SELECT content FROM autosave_log WHERE ref = refs.id ORDER BY id DESC LIMIT 1
+) pending ON TRUE
+WHERE refs.id = :refId AND (pending.content IS NOT NULL OR snapshots.content IS NOT NULL);
 
 /* @name GetRefMeta */
 SELECT title FROM refs WHERE id = :refId;
@@ -15,6 +18,8 @@ SELECT title FROM refs WHERE id = :refId;
 /* @name GetRefs */
 SELECT id, title
 FROM refs
+WHERE NOT EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id)
+    OR EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id AND userId = :userId)
 ORDER BY lastUpdated DESC;
 
 /* @name GetWitnesses */
@@ -52,3 +57,421 @@ VALUES :rows;
 SELECT fromRef
 FROM externs
 WHERE toRef = :toRef AND taxon = :taxon;
+
+/* @name LockRef */
+SELECT pg_advisory_xact_lock(hashtextextended(:refId::text, 0));
+
+/* @name NewMirror */
+INSERT INTO mirrors(ref, sourceUrl, subscribedAt, lastFetched)
+VALUES (:refId, :sourceUrl, NOW(), NOW());
+
+/* @name GetMirror */
+SELECT ref, sourceUrl, subscribedAt, lastFetched, lastError
+FROM mirrors
+WHERE ref = :refId;
+
+/* @name GetMirrors */
+SELECT ref, sourceUrl, subscribedAt, lastFetched, lastError
+FROM mirrors
+ORDER BY lastFetched NULLS FIRST;
+
+/* @name UpdateMirrorStatus */
+UPDATE mirrors
+SET lastFetched = COALESCE(:lastFetched, lastFetched), lastError = :lastError
+WHERE ref = :refId;
+
+/* @name EnqueueJob */
+INSERT INTO jobs(kind, payload, status, attempts, maxAttempts, runAt, createdAt)
+VALUES (:kind, :payload, 'queued', 0, :maxAttempts, NOW(), NOW())
+RETURNING id;
+
+/* @name ClaimJob */
+UPDATE jobs
+SET status = 'running', attempts = attempts + 1, lockedAt = NOW()
+WHERE id = (
+    SELECT id FROM jobs
+    WHERE status = 'queued' AND runAt <= NOW()
+    ORDER BY runAt
+    FOR UPDATE SKIP LOCKED
+    LIMIT 1
+)
+RETURNING id, kind, payload, attempts, maxAttempts;
+
+/* @name CompleteJob */
+UPDATE jobs
+SET status = 'succeeded', result = :result, lockedAt = NULL, finishedAt = NOW()
+WHERE id = :id AND status = 'running' AND attempts = :attempt
+RETURNING id;
+
+/* @name FailJob */
+UPDATE jobs
+SET status = CASE WHEN attempts >= maxAttempts OR :permanent THEN 'dead' ELSE 'queued' END,
+    runAt = NOW() + make_interval(secs => :backoffSeconds),
+    finishedAt = CASE WHEN attempts >= maxAttempts OR :permanent THEN NOW() END,
+    lastError = :error,
+    lockedAt = NULL
+WHERE id = :id AND status = 'running' AND attempts = :attempt
+RETURNING id;
+
+/* @name RequeueStaleJobs */
+UPDATE jobs
+SET status = CASE WHEN attempts >= maxAttempts THEN 'dead' ELSE 'queued' END,
+    finishedAt = CASE WHEN attempts >= maxAttempts THEN NOW() END,
+    lastError = 'Worker did not report back',
+    lockedAt = NULL
+WHERE status = 'running' AND lockedAt < NOW() - make_interval(secs => :staleSeconds)
+RETURNING id, status;
+
+/* @name GetJob */
+SELECT id, kind, status, attempts, maxAttempts, lastError, result, createdAt, finishedAt,
+    payload ->> 'refId' AS ref
+FROM jobs
+WHERE id = :id;
+
+/* @name GetDeadJobs */
+SELECT id, kind, status, attempts, maxAttempts, lastError, result, createdAt, finishedAt,
+    payload ->> 'refId' AS ref
+FROM jobs
+WHERE status = 'dead'
+ORDER BY finishedAt DESC;
+
+/* @name RetryJob */
+UPDATE jobs
+SET status = 'queued', attempts = 0, runAt = NOW(), finishedAt = NULL
+WHERE id = :id AND status = 'dead'
+RETURNING id;
+
+/* @name Ping */
+SELECT 1 AS ok;
+
+/* @name NewSystemDocument */
+INSERT INTO system_documents(slug, ref, version)
+VALUES (:slug, :refId, :version);
+
+/* @name GetSystemDocuments */
+SELECT slug, ref, version
+FROM system_documents
+ORDER BY slug;
+
+/* @name UpdateSystemDocumentVersion */
+UPDATE system_documents
+SET version = :version
+WHERE slug = :slug;
+
+/* @name IsReadOnly */
+SELECT EXISTS (SELECT 1 FROM mirrors WHERE ref = :refId)
+    OR EXISTS (SELECT 1 FROM system_documents WHERE ref = :refId) AS readOnly;
+
+/* @name RecordView */
+INSERT INTO ref_views(ref, viewer, viewedAt)
+VALUES (:refId, :viewer, NOW());
+
+/* @name GetViewCount */
+SELECT COUNT(*)::INT AS views
+FROM ref_views
+WHERE ref = :refId;
+
+/* @name GetDailyViews */
+SELECT date_trunc('day', viewedAt) AS day, COUNT(*)::INT AS views,
+    COUNT(DISTINCT viewer)::INT AS viewers
+FROM ref_views
+WHERE ref = :refId AND viewedAt >= date_trunc('day', NOW()) - make_interval(days => :days)
+GROUP BY day
+ORDER BY day;
+
+/* @name GetRefStats */
+WITH refSnapshots AS (
+    SELECT snapshot AS id FROM witnesses WHERE forRef = :refId
+    UNION
+    SELECT autosave FROM refs WHERE id = :refId AND autosave IS NOT NULL
+)
+SELECT (SELECT COUNT(*)::INT FROM witnesses WHERE forRef = :refId) AS witnesses,
+    COUNT(*)::INT AS snapshots,
+    COALESCE(SUM(octet_length(snapshots.content)), 0)::BIGINT AS bytes
+FROM refSnapshots
+INNER JOIN snapshots ON snapshots.id = refSnapshots.id;
+
+/* @name GetWitnessesSince */
+SELECT id, snapshot, note, atTime
+FROM witnesses
+WHERE forRef = :refId AND id > :since
+ORDER BY id
+LIMIT :limit::INT;
+
+/* @name GetWitness */
+SELECT id, snapshot, note, atTime
+FROM witnesses
+WHERE forRef = :refId AND id = :id;
+
+/* @name GetSnapshot */
+SELECT content
+FROM snapshots
+WHERE id = :id;
+
+/* @name PinSnapshot */
+INSERT INTO pins(ref, snapshot, note, pinnedAt)
+SELECT :refId, :snapshotId, :note, NOW()
+WHERE EXISTS (SELECT 1 FROM witnesses WHERE forRef = :refId AND snapshot = :snapshotId)
+    OR EXISTS (SELECT 1 FROM refs WHERE id = :refId AND autosave = :snapshotId)
+ON CONFLICT (ref, snapshot) DO UPDATE SET note = EXCLUDED.note
+RETURNING snapshot;
+
+/* @name UnpinSnapshot */
+DELETE FROM pins
+WHERE ref = :refId AND snapshot = :snapshotId
+RETURNING snapshot;
+
+/* @name GetPins */
+SELECT snapshot, note, pinnedAt
+FROM pins
+WHERE ref = :refId
+ORDER BY pinnedAt;
+
+/* @name NewAttachment */
+INSERT INTO attachments(ref, filename, contentType, size, createdAt)
+SELECT :refId, :filename, :contentType, :size, NOW()
+WHERE (SELECT COALESCE(SUM(size), 0) FROM attachments WHERE ref = :refId) + :size <= :quota
+RETURNING id;
+
+/* @name GetAttachments */
+SELECT id, filename, contentType, size, createdAt
+FROM attachments
+WHERE ref = :refId
+ORDER BY id;
+
+/* @name GetAttachment */
+SELECT id, ref, filename, contentType, size, createdAt
+FROM attachments
+WHERE id = :id;
+
+/* @name DeleteAttachment */
+DELETE FROM attachments
+WHERE id = :id
+RETURNING id;
+
+/* @name GetAttachmentUsage */
+SELECT COALESCE(SUM(size), 0)::BIGINT AS bytes
+FROM attachments
+WHERE ref = :refId;
+
+/* @name SearchRefs */
+WITH hits AS (
+    SELECT id FROM refs
+    WHERE to_tsvector('simple', COALESCE(title, '')) @@ plainto_tsquery('simple', :query)
+    UNION
+    SELECT id FROM refs WHERE :query <% title
+    UNION
+    SELECT ref FROM system_documents WHERE :query <% slug
+    UNION
+    SELECT id FROM refs WHERE searchVector @@ plainto_tsquery('simple', :query)
+)
+SELECT refs.id, refs.title, system_documents.slug, refs.docType AS type, refs.lastUpdated,
+    (to_tsvector('simple', COALESCE(refs.title, '')) @@ plainto_tsquery('simple', :query))::INT
+    + 0.5::REAL * COALESCE(refs.searchVector @@ plainto_tsquery('simple', :query), FALSE)::INT
+    + GREATEST(
+        word_similarity(:query, COALESCE(refs.title, '')),
+        word_similarity(:query, COALESCE(system_documents.slug, ''))
+    ) AS score
+FROM hits
+JOIN refs ON refs.id = hits.id
+LEFT JOIN system_documents ON system_documents.ref = refs.id
+WHERE NOT EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id)
+    OR EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id AND userId = :userId)
+ORDER BY score DESC, refs.lastUpdated DESC
+LIMIT :limit::INT;
+
+/* @name AppendAutosave */
+INSERT INTO autosave_log(ref, content, loggedAt)
+VALUES (:refId, :content, NOW());
+
+/* @name GetPendingAutosave */
+SELECT content
+FROM autosave_log
+WHERE ref = :refId
+ORDER BY id DESC
+LIMIT 1;
+
+/* @name ClearAutosaveLog */
+DELETE FROM autosave_log WHERE ref = :refId;
+
+/* @name GetRefsWithPendingAutosaves */
+SELECT DISTINCT ref
+FROM autosave_log
+LIMIT :limit::INT;
+
+/* @name NewCollabSession */
+INSERT INTO collab_sessions(ref, startedAt, endedAt, peakPeers, meanPeers, edits, participants)
+VALUES (:refId, :startedAt, :endedAt, :peakPeers, :meanPeers, :edits, :participants);
+
+/* @name GetCollabSessions */
+SELECT id, startedAt, endedAt, peakPeers, meanPeers, edits, participants
+FROM collab_sessions
+WHERE ref = :refId AND startedAt >= NOW() - make_interval(days => :days)
+ORDER BY startedAt DESC;
+
+/* @name NewPermalink */
+INSERT INTO permalinks(token, hash, ref, snapshot, createdAt)
+SELECT digest(convert_to(:refId::UUID::TEXT, 'UTF8') || snapshots.hash, 'sha256'),
+    snapshots.hash, :refId, snapshots.id, NOW()
+FROM snapshots
+WHERE snapshots.id = :snapshotId
+    AND (EXISTS (SELECT 1 FROM witnesses WHERE forRef = :refId AND snapshot = :snapshotId)
+        OR EXISTS (SELECT 1 FROM refs WHERE id = :refId AND autosave = :snapshotId))
+ON CONFLICT (ref, hash) DO UPDATE SET hash = EXCLUDED.hash
+RETURNING encode(token, 'hex') AS token;
+
+/* @name GetPermalink */
+SELECT permalinks.ref, permalinks.snapshot, permalinks.createdAt, snapshots.content,
+    permalinks.hash = digest(snapshots.content, 'sha256') AS intact
+FROM permalinks
+INNER JOIN snapshots ON snapshots.id = permalinks.snapshot
+WHERE permalinks.token = decode(:token, 'hex');
+
+/* @name GetRefSnapshots */
+SELECT id, content
+FROM snapshots
+WHERE id IN (
+    SELECT snapshot FROM witnesses WHERE forRef = :refId
+    UNION SELECT autosave FROM refs WHERE id = :refId
+    UNION SELECT snapshot FROM pins WHERE ref = :refId
+    UNION SELECT snapshot FROM permalinks WHERE ref = :refId
+    UNION SELECT (payload ->> 'snapshotId')::INT FROM jobs
+    WHERE kind = 'analysis' AND payload ->> 'refId' = :refId::UUID::TEXT
+)
+ORDER BY id;
+
+/* @name ReplaceRefSnapshot */
+WITH witnessed AS (
+    UPDATE witnesses SET snapshot = :newId WHERE forRef = :refId AND snapshot = :oldId
+), autosaved AS (
+    UPDATE refs SET autosave = :newId WHERE id = :refId AND autosave = :oldId
+), linked AS (
+    UPDATE permalinks SET snapshot = :newId WHERE ref = :refId AND snapshot = :oldId
+), analyzed AS (
+    UPDATE jobs
+    SET payload = jsonb_set(payload, '{snapshotId}', to_jsonb(:newId::INT)),
+        result = CASE WHEN result ? 'snapshotId'
+            THEN jsonb_set(result, '{snapshotId}', to_jsonb(:newId::INT))
+            ELSE result END
+    WHERE kind = 'analysis' AND payload ->> 'refId' = :refId::UUID::TEXT
+        AND payload -> 'snapshotId' = to_jsonb(:oldId::INT)
+), unpinned AS (
+    DELETE FROM pins WHERE ref = :refId AND snapshot = :oldId RETURNING note, pinnedAt
+)
+INSERT INTO pins(ref, snapshot, note, pinnedAt)
+SELECT :refId, :newId, note, pinnedAt FROM unpinned
+ON CONFLICT (ref, snapshot) DO NOTHING;
+
+/* @name DeleteSnapshotIfOrphaned */
+DELETE FROM snapshots
+WHERE id = :id
+    AND NOT EXISTS (SELECT 1 FROM witnesses WHERE snapshot = :id)
+    AND NOT EXISTS (SELECT 1 FROM refs WHERE autosave = :id)
+    AND NOT EXISTS (SELECT 1 FROM pins WHERE snapshot = :id)
+    AND NOT EXISTS (SELECT 1 FROM permalinks WHERE snapshot = :id)
+    AND NOT EXISTS (
+        SELECT 1 FROM jobs
+        WHERE kind = 'analysis' AND payload -> 'snapshotId' = to_jsonb(:id::INT)
+    )
+RETURNING id;
+
+/* @name NewAuditEntry */
+INSERT INTO audit_log(ref, action, details, at)
+VALUES (:refId, :action, :details, NOW())
+RETURNING id;
+
+/* @name GetAuditLog */
+SELECT id, action, details, at
+FROM audit_log
+WHERE ref = :refId
+ORDER BY at;
+
+/* @name GetRefSnapshot */
+SELECT content
+FROM snapshots
+WHERE id = :snapshotId
+    AND (EXISTS (SELECT 1 FROM witnesses WHERE forRef = :refId AND snapshot = :snapshotId)
+        OR EXISTS (SELECT 1 FROM refs WHERE id = :refId AND autosave = :snapshotId));
+
+/* @name UpsertUser */
+INSERT INTO users(id, email, createdAt, lastSeen)
+VALUES (:id, :email, NOW(), NOW())
+ON CONFLICT (id) DO UPDATE SET email = EXCLUDED.email, lastSeen = EXCLUDED.lastSeen;
+
+/* @name GetAccessLevel */
+SELECT CASE
+    WHEN NOT EXISTS (SELECT 1 FROM permissions WHERE ref = :refId) THEN 'editor'
+    ELSE (SELECT level FROM permissions WHERE ref = :refId AND userId = :userId)
+END AS level;
+
+/* @name SetPermission */
+INSERT INTO permissions(ref, userId, level)
+VALUES (:refId, :userId, :level)
+ON CONFLICT (ref, userId) DO UPDATE SET level = EXCLUDED.level;
+
+/* @name RemovePermission */
+DELETE FROM permissions
+WHERE ref = :refId AND userId = :userId;
+
+/* @name GetPermissions */
+SELECT permissions.userId, users.email, permissions.level
+FROM permissions
+LEFT JOIN users ON users.id = permissions.userId
+WHERE permissions.ref = :refId
+ORDER BY permissions.userId;
+
+/* @name NewOwnedRef */
+WITH ref AS (
+    INSERT INTO refs(id, title, lastUpdated)
+    VALUES (gen_random_uuid(), :title, NOW())
+    RETURNING id
+), owner AS (
+    INSERT INTO permissions(ref, userId, level)
+    SELECT id, :owner, 'owner' FROM ref
+)
+SELECT id FROM ref;
+
+/* @name ListRefs */
+SELECT refs.id, refs.title, refs.docType AS type, refs.lastUpdated
+FROM refs
+WHERE (NOT EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id)
+        OR EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id AND userId = :userId))
+    AND (:owner::TEXT IS NULL OR EXISTS (
+        SELECT 1 FROM permissions
+        WHERE ref = refs.id AND userId = :owner AND level = 'owner'
+    ))
+    AND (:docType::TEXT IS NULL OR refs.docType = :docType)
+ORDER BY refs.lastUpdated DESC, refs.id
+LIMIT :limit::INT OFFSET :offset::INT;
+
+/* @name RecordConflict */
+INSERT INTO conflicts(ref, content, detectedAt)
+VALUES (:refId, :content, NOW())
+ON CONFLICT (ref) DO UPDATE SET content = EXCLUDED.content;
+
+/* @name DeleteConflict */
+DELETE FROM conflicts WHERE ref = :refId;
+
+/* @name GetConflict */
+SELECT content, detectedAt FROM conflicts WHERE ref = :refId;
+
+/* @name GetConflicts */
+SELECT ref, detectedAt FROM conflicts ORDER BY detectedAt;
+
+/* @name SaveLiveDocument */
+INSERT INTO live_documents(ref, documentId, data, content, savedAt)
+VALUES (:refId, :documentId, :data, :content, NOW())
+ON CONFLICT (ref) DO UPDATE SET
+    documentId = EXCLUDED.documentId,
+    data = EXCLUDED.data,
+    content = EXCLUDED.content,
+    savedAt = EXCLUDED.savedAt;
+
+/* @name GetLiveDocument */
+SELECT documentId, data, content FROM live_documents WHERE ref = :refId;
+
+/* @name GetLiveDocumentIds */
+SELECT ref, documentId FROM live_documents;
+
+/* @name DeleteLiveDocument */
+DELETE FROM live_documents WHERE ref = :refId;
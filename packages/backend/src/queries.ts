@@ -1,6 +1,12 @@
 /** Types generated for queries found in "src/queries.sql" */
 import { PreparedQuery } from '@pgtyped/runtime';
 
+export type DateOrString = Date | string;
+
+export type Json = null | boolean | number | string | Json[] | { [key: string]: Json };
+
+export type NumberOrString = number | string;
+
 /** 'Autosave' parameters type */
 export interface IAutosaveParams {
   refId?: string | null | void;
@@ -36,7 +42,7 @@ export interface IGetAutosaveParams {
 
 /** 'GetAutosave' return type */
 export interface IGetAutosaveResult {
-  content: string;
+  content: string | null;
 }
 
 /** 'GetAutosave' query type */
@@ -45,15 +51,18 @@ export interface IGetAutosaveQuery {
   result: IGetAutosaveResult;
 }
 
-const getAutosaveIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":115,"b":120}]}],"statement":"SELECT snapshots.content as content\nFROM refs\nINNER JOIN snapshots ON refs.autosave = snapshots.id\nWHERE refs.id = :refId"};
+const getAutosaveIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":261,"b":266}]}],"statement":"SELECT COALESCE(pending.content, snapshots.content) AS content\nFROM refs\nLEFT JOIN snapshots ON refs.autosave = snapshots.id\nLEFT JOIN LATERAL (\n    SELECT content FROM autosave_log WHERE ref = refs.id ORDER BY id DESC LIMIT 1\n) pending ON TRUE\nWHERE refs.id = :refId AND (pending.content IS NOT NULL OR snapshots.content IS NOT NULL)"};
 
 /**
  * Query generated from SQL:
  * ```
- * SELECT snapshots.content as content
+ * SELECT COALESCE(pending.content, snapshots.content) AS content
  * FROM refs
- * INNER JOIN snapshots ON refs.autosave = snapshots.id
- * WHERE refs.id = :refId
+ * LEFT JOIN snapshots ON refs.autosave = snapshots.id
+ * LEFT JOIN LATERAL (
+ *     SELECT content FROM autosave_log WHERE ref = refs.id ORDER BY id DESC LIMIT 1
+ * ) pending ON TRUE
+ * WHERE refs.id = :refId AND (pending.content IS NOT NULL OR snapshots.content IS NOT NULL)
  * ```
  */
 export const getAutosave = new PreparedQuery<IGetAutosaveParams,IGetAutosaveResult>(getAutosaveIR);
@@ -87,7 +96,9 @@ export const getRefMeta = new PreparedQuery<IGetRefMetaParams,IGetRefMetaResult>
 
 
 /** 'GetRefs' parameters type */
-export type IGetRefsParams = void;
+export interface IGetRefsParams {
+  userId?: string | null | void;
+}
 
 /** 'GetRefs' return type */
 export interface IGetRefsResult {
@@ -101,13 +112,15 @@ export interface IGetRefsQuery {
   result: IGetRefsResult;
 }
 
-const getRefsIR: any = {"usedParamSet":{},"params":[],"statement":"SELECT id, title\nFROM refs\nORDER BY lastUpdated DESC"};
+const getRefsIR: any = {"usedParamSet":{"userId":true},"params":[{"name":"userId","required":false,"transform":{"type":"scalar"},"locs":[{"a":166,"b":172}]}],"statement":"SELECT id, title\nFROM refs\nWHERE NOT EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id)\n    OR EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id AND userId = :userId)\nORDER BY lastUpdated DESC"};
 
 /**
  * Query generated from SQL:
  * ```
  * SELECT id, title
  * FROM refs
+ * WHERE NOT EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id)
+ *     OR EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id AND userId = :userId)
  * ORDER BY lastUpdated DESC
  * ```
  */
@@ -321,3 +334,2026 @@ const getBacklinksIR: any = {"usedParamSet":{"toRef":true,"taxon":true},"params"
 export const getBacklinks = new PreparedQuery<IGetBacklinksParams,IGetBacklinksResult>(getBacklinksIR);
 
 
+/** 'LockRef' parameters type */
+export interface ILockRefParams {
+  refId?: string | null | void;
+}
+
+/** 'LockRef' return type */
+export interface ILockRefResult {
+  pg_advisory_xact_lock: undefined | null;
+}
+
+/** 'LockRef' query type */
+export interface ILockRefQuery {
+  params: ILockRefParams;
+  result: ILockRefResult;
+}
+
+const lockRefIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":46,"b":51}]}],"statement":"SELECT pg_advisory_xact_lock(hashtextextended(:refId::text, 0))"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT pg_advisory_xact_lock(hashtextextended(:refId::text, 0))
+ * ```
+ */
+export const lockRef = new PreparedQuery<ILockRefParams,ILockRefResult>(lockRefIR);
+
+
+/** 'NewMirror' parameters type */
+export interface INewMirrorParams {
+  refId?: string | null | void;
+  sourceUrl?: string | null | void;
+}
+
+/** 'NewMirror' return type */
+export type INewMirrorResult = void;
+
+/** 'NewMirror' query type */
+export interface INewMirrorQuery {
+  params: INewMirrorParams;
+  result: INewMirrorResult;
+}
+
+const newMirrorIR: any = {"usedParamSet":{"refId":true,"sourceUrl":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":71,"b":76}]},{"name":"sourceUrl","required":false,"transform":{"type":"scalar"},"locs":[{"a":79,"b":88}]}],"statement":"INSERT INTO mirrors(ref, sourceUrl, subscribedAt, lastFetched)\nVALUES (:refId, :sourceUrl, NOW(), NOW())"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO mirrors(ref, sourceUrl, subscribedAt, lastFetched)
+ * VALUES (:refId, :sourceUrl, NOW(), NOW())
+ * ```
+ */
+export const newMirror = new PreparedQuery<INewMirrorParams,INewMirrorResult>(newMirrorIR);
+
+
+/** 'GetMirror' parameters type */
+export interface IGetMirrorParams {
+  refId?: string | null | void;
+}
+
+/** 'GetMirror' return type */
+export interface IGetMirrorResult {
+  lasterror: string | null;
+  lastfetched: Date | null;
+  ref: string;
+  sourceurl: string;
+  subscribedat: Date;
+}
+
+/** 'GetMirror' query type */
+export interface IGetMirrorQuery {
+  params: IGetMirrorParams;
+  result: IGetMirrorResult;
+}
+
+const getMirrorIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":85,"b":90}]}],"statement":"SELECT ref, sourceUrl, subscribedAt, lastFetched, lastError\nFROM mirrors\nWHERE ref = :refId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT ref, sourceUrl, subscribedAt, lastFetched, lastError
+ * FROM mirrors
+ * WHERE ref = :refId
+ * ```
+ */
+export const getMirror = new PreparedQuery<IGetMirrorParams,IGetMirrorResult>(getMirrorIR);
+
+
+/** 'GetMirrors' parameters type */
+export type IGetMirrorsParams = void;
+
+/** 'GetMirrors' return type */
+export interface IGetMirrorsResult {
+  lasterror: string | null;
+  lastfetched: Date | null;
+  ref: string;
+  sourceurl: string;
+  subscribedat: Date;
+}
+
+/** 'GetMirrors' query type */
+export interface IGetMirrorsQuery {
+  params: IGetMirrorsParams;
+  result: IGetMirrorsResult;
+}
+
+const getMirrorsIR: any = {"usedParamSet":{},"params":[],"statement":"SELECT ref, sourceUrl, subscribedAt, lastFetched, lastError\nFROM mirrors\nORDER BY lastFetched NULLS FIRST"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT ref, sourceUrl, subscribedAt, lastFetched, lastError
+ * FROM mirrors
+ * ORDER BY lastFetched NULLS FIRST
+ * ```
+ */
+export const getMirrors = new PreparedQuery<IGetMirrorsParams,IGetMirrorsResult>(getMirrorsIR);
+
+
+/** 'UpdateMirrorStatus' parameters type */
+export interface IUpdateMirrorStatusParams {
+  lastError?: string | null | void;
+  lastFetched?: DateOrString | null | void;
+  refId?: string | null | void;
+}
+
+/** 'UpdateMirrorStatus' return type */
+export type IUpdateMirrorStatusResult = void;
+
+/** 'UpdateMirrorStatus' query type */
+export interface IUpdateMirrorStatusQuery {
+  params: IUpdateMirrorStatusParams;
+  result: IUpdateMirrorStatusResult;
+}
+
+const updateMirrorStatusIR: any = {"usedParamSet":{"lastFetched":true,"lastError":true,"refId":true},"params":[{"name":"lastFetched","required":false,"transform":{"type":"scalar"},"locs":[{"a":42,"b":53}]},{"name":"lastError","required":false,"transform":{"type":"scalar"},"locs":[{"a":82,"b":91}]},{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":105,"b":110}]}],"statement":"UPDATE mirrors\nSET lastFetched = COALESCE(:lastFetched, lastFetched), lastError = :lastError\nWHERE ref = :refId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * UPDATE mirrors
+ * SET lastFetched = COALESCE(:lastFetched, lastFetched), lastError = :lastError
+ * WHERE ref = :refId
+ * ```
+ */
+export const updateMirrorStatus = new PreparedQuery<IUpdateMirrorStatusParams,IUpdateMirrorStatusResult>(updateMirrorStatusIR);
+
+
+/** 'EnqueueJob' parameters type */
+export interface IEnqueueJobParams {
+  kind?: string | null | void;
+  maxAttempts?: number | null | void;
+  payload?: Json | null | void;
+}
+
+/** 'EnqueueJob' return type */
+export interface IEnqueueJobResult {
+  id: number;
+}
+
+/** 'EnqueueJob' query type */
+export interface IEnqueueJobQuery {
+  params: IEnqueueJobParams;
+  result: IEnqueueJobResult;
+}
+
+const enqueueJobIR: any = {"usedParamSet":{"kind":true,"payload":true,"maxAttempts":true},"params":[{"name":"kind","required":false,"transform":{"type":"scalar"},"locs":[{"a":89,"b":93}]},{"name":"payload","required":false,"transform":{"type":"scalar"},"locs":[{"a":96,"b":103}]},{"name":"maxAttempts","required":false,"transform":{"type":"scalar"},"locs":[{"a":119,"b":130}]}],"statement":"INSERT INTO jobs(kind, payload, status, attempts, maxAttempts, runAt, createdAt)\nVALUES (:kind, :payload, 'queued', 0, :maxAttempts, NOW(), NOW())\nRETURNING id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO jobs(kind, payload, status, attempts, maxAttempts, runAt, createdAt)
+ * VALUES (:kind, :payload, 'queued', 0, :maxAttempts, NOW(), NOW())
+ * RETURNING id
+ * ```
+ */
+export const enqueueJob = new PreparedQuery<IEnqueueJobParams,IEnqueueJobResult>(enqueueJobIR);
+
+
+/** 'ClaimJob' parameters type */
+export type IClaimJobParams = void;
+
+/** 'ClaimJob' return type */
+export interface IClaimJobResult {
+  attempts: number;
+  id: number;
+  kind: string;
+  maxattempts: number;
+  payload: Json;
+}
+
+/** 'ClaimJob' query type */
+export interface IClaimJobQuery {
+  params: IClaimJobParams;
+  result: IClaimJobResult;
+}
+
+const claimJobIR: any = {"usedParamSet":{},"params":[],"statement":"UPDATE jobs\nSET status = 'running', attempts = attempts + 1, lockedAt = NOW()\nWHERE id = (\n    SELECT id FROM jobs\n    WHERE status = 'queued' AND runAt <= NOW()\n    ORDER BY runAt\n    FOR UPDATE SKIP LOCKED\n    LIMIT 1\n)\nRETURNING id, kind, payload, attempts, maxAttempts"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * UPDATE jobs
+ * SET status = 'running', attempts = attempts + 1, lockedAt = NOW()
+ * WHERE id = (
+ *     SELECT id FROM jobs
+ *     WHERE status = 'queued' AND runAt <= NOW()
+ *     ORDER BY runAt
+ *     FOR UPDATE SKIP LOCKED
+ *     LIMIT 1
+ * )
+ * RETURNING id, kind, payload, attempts, maxAttempts
+ * ```
+ */
+export const claimJob = new PreparedQuery<IClaimJobParams,IClaimJobResult>(claimJobIR);
+
+
+/** 'CompleteJob' parameters type */
+export interface ICompleteJobParams {
+  attempt?: number | null | void;
+  id?: number | null | void;
+  result?: Json | null | void;
+}
+
+/** 'CompleteJob' return type */
+export interface ICompleteJobResult {
+  id: number;
+}
+
+/** 'CompleteJob' query type */
+export interface ICompleteJobQuery {
+  params: ICompleteJobParams;
+  result: ICompleteJobResult;
+}
+
+const completeJobIR: any = {"usedParamSet":{"result":true,"id":true,"attempt":true},"params":[{"name":"result","required":false,"transform":{"type":"scalar"},"locs":[{"a":47,"b":53}]},{"name":"id","required":false,"transform":{"type":"scalar"},"locs":[{"a":103,"b":105}]},{"name":"attempt","required":false,"transform":{"type":"scalar"},"locs":[{"a":145,"b":152}]}],"statement":"UPDATE jobs\nSET status = 'succeeded', result = :result, lockedAt = NULL, finishedAt = NOW()\nWHERE id = :id AND status = 'running' AND attempts = :attempt\nRETURNING id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * UPDATE jobs
+ * SET status = 'succeeded', result = :result, lockedAt = NULL, finishedAt = NOW()
+ * WHERE id = :id AND status = 'running' AND attempts = :attempt
+ * RETURNING id
+ * ```
+ */
+export const completeJob = new PreparedQuery<ICompleteJobParams,ICompleteJobResult>(completeJobIR);
+
+
+/** 'FailJob' parameters type */
+export interface IFailJobParams {
+  attempt?: number | null | void;
+  backoffSeconds?: number | null | void;
+  error?: string | null | void;
+  id?: number | null | void;
+  permanent?: boolean | null | void;
+}
+
+/** 'FailJob' return type */
+export interface IFailJobResult {
+  id: number;
+}
+
+/** 'FailJob' query type */
+export interface IFailJobQuery {
+  params: IFailJobParams;
+  result: IFailJobResult;
+}
+
+const failJobIR: any = {"usedParamSet":{"permanent":true,"backoffSeconds":true,"error":true,"id":true,"attempt":true},"params":[{"name":"permanent","required":false,"transform":{"type":"scalar"},"locs":[{"a":62,"b":71},{"a":218,"b":227}]},{"name":"backoffSeconds","required":false,"transform":{"type":"scalar"},"locs":[{"a":146,"b":160}]},{"name":"error","required":false,"transform":{"type":"scalar"},"locs":[{"a":261,"b":266}]},{"name":"id","required":false,"transform":{"type":"scalar"},"locs":[{"a":300,"b":302}]},{"name":"attempt","required":false,"transform":{"type":"scalar"},"locs":[{"a":342,"b":349}]}],"statement":"UPDATE jobs\nSET status = CASE WHEN attempts >= maxAttempts OR :permanent THEN 'dead' ELSE 'queued' END,\n    runAt = NOW() + make_interval(secs => :backoffSeconds),\n    finishedAt = CASE WHEN attempts >= maxAttempts OR :permanent THEN NOW() END,\n    lastError = :error,\n    lockedAt = NULL\nWHERE id = :id AND status = 'running' AND attempts = :attempt\nRETURNING id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * UPDATE jobs
+ * SET status = CASE WHEN attempts >= maxAttempts OR :permanent THEN 'dead' ELSE 'queued' END,
+ *     runAt = NOW() + make_interval(secs => :backoffSeconds),
+ *     finishedAt = CASE WHEN attempts >= maxAttempts OR :permanent THEN NOW() END,
+ *     lastError = :error,
+ *     lockedAt = NULL
+ * WHERE id = :id AND status = 'running' AND attempts = :attempt
+ * RETURNING id
+ * ```
+ */
+export const failJob = new PreparedQuery<IFailJobParams,IFailJobResult>(failJobIR);
+
+
+/** 'RequeueStaleJobs' parameters type */
+export interface IRequeueStaleJobsParams {
+  staleSeconds?: number | null | void;
+}
+
+/** 'RequeueStaleJobs' return type */
+export interface IRequeueStaleJobsResult {
+  id: number;
+  status: string;
+}
+
+/** 'RequeueStaleJobs' query type */
+export interface IRequeueStaleJobsQuery {
+  params: IRequeueStaleJobsParams;
+  result: IRequeueStaleJobsResult;
+}
+
+const requeueStaleJobsIR: any = {"usedParamSet":{"staleSeconds":true},"params":[{"name":"staleSeconds","required":false,"transform":{"type":"scalar"},"locs":[{"a":293,"b":305}]}],"statement":"UPDATE jobs\nSET status = CASE WHEN attempts >= maxAttempts THEN 'dead' ELSE 'queued' END,\n    finishedAt = CASE WHEN attempts >= maxAttempts THEN NOW() END,\n    lastError = 'Worker did not report back',\n    lockedAt = NULL\nWHERE status = 'running' AND lockedAt < NOW() - make_interval(secs => :staleSeconds)\nRETURNING id, status"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * UPDATE jobs
+ * SET status = CASE WHEN attempts >= maxAttempts THEN 'dead' ELSE 'queued' END,
+ *     finishedAt = CASE WHEN attempts >= maxAttempts THEN NOW() END,
+ *     lastError = 'Worker did not report back',
+ *     lockedAt = NULL
+ * WHERE status = 'running' AND lockedAt < NOW() - make_interval(secs => :staleSeconds)
+ * RETURNING id, status
+ * ```
+ */
+export const requeueStaleJobs = new PreparedQuery<IRequeueStaleJobsParams,IRequeueStaleJobsResult>(requeueStaleJobsIR);
+
+
+/** 'GetJob' parameters type */
+export interface IGetJobParams {
+  id?: number | null | void;
+}
+
+/** 'GetJob' return type */
+export interface IGetJobResult {
+  attempts: number;
+  createdat: Date;
+  finishedat: Date | null;
+  id: number;
+  kind: string;
+  lasterror: string | null;
+  maxattempts: number;
+  ref: string | null;
+  result: Json | null;
+  status: string;
+}
+
+/** 'GetJob' query type */
+export interface IGetJobQuery {
+  params: IGetJobParams;
+  result: IGetJobResult;
+}
+
+const getJobIR: any = {"usedParamSet":{"id":true},"params":[{"name":"id","required":false,"transform":{"type":"scalar"},"locs":[{"a":142,"b":144}]}],"statement":"SELECT id, kind, status, attempts, maxAttempts, lastError, result, createdAt, finishedAt,\n    payload ->> 'refId' AS ref\nFROM jobs\nWHERE id = :id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT id, kind, status, attempts, maxAttempts, lastError, result, createdAt, finishedAt,
+ *     payload ->> 'refId' AS ref
+ * FROM jobs
+ * WHERE id = :id
+ * ```
+ */
+export const getJob = new PreparedQuery<IGetJobParams,IGetJobResult>(getJobIR);
+
+
+/** 'GetDeadJobs' parameters type */
+export type IGetDeadJobsParams = void;
+
+/** 'GetDeadJobs' return type */
+export interface IGetDeadJobsResult {
+  attempts: number;
+  createdat: Date;
+  finishedat: Date | null;
+  id: number;
+  kind: string;
+  lasterror: string | null;
+  maxattempts: number;
+  ref: string | null;
+  result: Json | null;
+  status: string;
+}
+
+/** 'GetDeadJobs' query type */
+export interface IGetDeadJobsQuery {
+  params: IGetDeadJobsParams;
+  result: IGetDeadJobsResult;
+}
+
+const getDeadJobsIR: any = {"usedParamSet":{},"params":[],"statement":"SELECT id, kind, status, attempts, maxAttempts, lastError, result, createdAt, finishedAt,\n    payload ->> 'refId' AS ref\nFROM jobs\nWHERE status = 'dead'\nORDER BY finishedAt DESC"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT id, kind, status, attempts, maxAttempts, lastError, result, createdAt, finishedAt,
+ *     payload ->> 'refId' AS ref
+ * FROM jobs
+ * WHERE status = 'dead'
+ * ORDER BY finishedAt DESC
+ * ```
+ */
+export const getDeadJobs = new PreparedQuery<IGetDeadJobsParams,IGetDeadJobsResult>(getDeadJobsIR);
+
+
+/** 'RetryJob' parameters type */
+export interface IRetryJobParams {
+  id?: number | null | void;
+}
+
+/** 'RetryJob' return type */
+export interface IRetryJobResult {
+  id: number;
+}
+
+/** 'RetryJob' query type */
+export interface IRetryJobQuery {
+  params: IRetryJobParams;
+  result: IRetryJobResult;
+}
+
+const retryJobIR: any = {"usedParamSet":{"id":true},"params":[{"name":"id","required":false,"transform":{"type":"scalar"},"locs":[{"a":93,"b":95}]}],"statement":"UPDATE jobs\nSET status = 'queued', attempts = 0, runAt = NOW(), finishedAt = NULL\nWHERE id = :id AND status = 'dead'\nRETURNING id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * UPDATE jobs
+ * SET status = 'queued', attempts = 0, runAt = NOW(), finishedAt = NULL
+ * WHERE id = :id AND status = 'dead'
+ * RETURNING id
+ * ```
+ */
+export const retryJob = new PreparedQuery<IRetryJobParams,IRetryJobResult>(retryJobIR);
+
+
+/** 'Ping' parameters type */
+export type IPingParams = void;
+
+/** 'Ping' return type */
+export interface IPingResult {
+  ok: number | null;
+}
+
+/** 'Ping' query type */
+export interface IPingQuery {
+  params: IPingParams;
+  result: IPingResult;
+}
+
+const pingIR: any = {"usedParamSet":{},"params":[],"statement":"SELECT 1 AS ok"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT 1 AS ok
+ * ```
+ */
+export const ping = new PreparedQuery<IPingParams,IPingResult>(pingIR);
+
+
+/** 'NewSystemDocument' parameters type */
+export interface INewSystemDocumentParams {
+  refId?: string | null | void;
+  slug?: string | null | void;
+  version?: number | null | void;
+}
+
+/** 'NewSystemDocument' return type */
+export type INewSystemDocumentResult = void;
+
+/** 'NewSystemDocument' query type */
+export interface INewSystemDocumentQuery {
+  params: INewSystemDocumentParams;
+  result: INewSystemDocumentResult;
+}
+
+const newSystemDocumentIR: any = {"usedParamSet":{"slug":true,"refId":true,"version":true},"params":[{"name":"slug","required":false,"transform":{"type":"scalar"},"locs":[{"a":57,"b":61}]},{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":64,"b":69}]},{"name":"version","required":false,"transform":{"type":"scalar"},"locs":[{"a":72,"b":79}]}],"statement":"INSERT INTO system_documents(slug, ref, version)\nVALUES (:slug, :refId, :version)"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO system_documents(slug, ref, version)
+ * VALUES (:slug, :refId, :version)
+ * ```
+ */
+export const newSystemDocument = new PreparedQuery<INewSystemDocumentParams,INewSystemDocumentResult>(newSystemDocumentIR);
+
+
+/** 'GetSystemDocuments' parameters type */
+export type IGetSystemDocumentsParams = void;
+
+/** 'GetSystemDocuments' return type */
+export interface IGetSystemDocumentsResult {
+  ref: string;
+  slug: string;
+  version: number;
+}
+
+/** 'GetSystemDocuments' query type */
+export interface IGetSystemDocumentsQuery {
+  params: IGetSystemDocumentsParams;
+  result: IGetSystemDocumentsResult;
+}
+
+const getSystemDocumentsIR: any = {"usedParamSet":{},"params":[],"statement":"SELECT slug, ref, version\nFROM system_documents\nORDER BY slug"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT slug, ref, version
+ * FROM system_documents
+ * ORDER BY slug
+ * ```
+ */
+export const getSystemDocuments = new PreparedQuery<IGetSystemDocumentsParams,IGetSystemDocumentsResult>(getSystemDocumentsIR);
+
+
+/** 'UpdateSystemDocumentVersion' parameters type */
+export interface IUpdateSystemDocumentVersionParams {
+  slug?: string | null | void;
+  version?: number | null | void;
+}
+
+/** 'UpdateSystemDocumentVersion' return type */
+export type IUpdateSystemDocumentVersionResult = void;
+
+/** 'UpdateSystemDocumentVersion' query type */
+export interface IUpdateSystemDocumentVersionQuery {
+  params: IUpdateSystemDocumentVersionParams;
+  result: IUpdateSystemDocumentVersionResult;
+}
+
+const updateSystemDocumentVersionIR: any = {"usedParamSet":{"version":true,"slug":true},"params":[{"name":"version","required":false,"transform":{"type":"scalar"},"locs":[{"a":38,"b":45}]},{"name":"slug","required":false,"transform":{"type":"scalar"},"locs":[{"a":60,"b":64}]}],"statement":"UPDATE system_documents\nSET version = :version\nWHERE slug = :slug"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * UPDATE system_documents
+ * SET version = :version
+ * WHERE slug = :slug
+ * ```
+ */
+export const updateSystemDocumentVersion = new PreparedQuery<IUpdateSystemDocumentVersionParams,IUpdateSystemDocumentVersionResult>(updateSystemDocumentVersionIR);
+
+
+/** 'IsReadOnly' parameters type */
+export interface IIsReadOnlyParams {
+  refId?: string | null | void;
+}
+
+/** 'IsReadOnly' return type */
+export interface IIsReadOnlyResult {
+  readonly: boolean | null;
+}
+
+/** 'IsReadOnly' query type */
+export interface IIsReadOnlyQuery {
+  params: IIsReadOnlyParams;
+  result: IIsReadOnlyResult;
+}
+
+const isReadOnlyIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":49,"b":54},{"a":115,"b":120}]}],"statement":"SELECT EXISTS (SELECT 1 FROM mirrors WHERE ref = :refId)\n    OR EXISTS (SELECT 1 FROM system_documents WHERE ref = :refId) AS readOnly"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT EXISTS (SELECT 1 FROM mirrors WHERE ref = :refId)
+ *     OR EXISTS (SELECT 1 FROM system_documents WHERE ref = :refId) AS readOnly
+ * ```
+ */
+export const isReadOnly = new PreparedQuery<IIsReadOnlyParams,IIsReadOnlyResult>(isReadOnlyIR);
+
+
+/** 'RecordView' parameters type */
+export interface IRecordViewParams {
+  refId?: string | null | void;
+  viewer?: string | null | void;
+}
+
+/** 'RecordView' return type */
+export type IRecordViewResult = void;
+
+/** 'RecordView' query type */
+export interface IRecordViewQuery {
+  params: IRecordViewParams;
+  result: IRecordViewResult;
+}
+
+const recordViewIR: any = {"usedParamSet":{"refId":true,"viewer":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":53,"b":58}]},{"name":"viewer","required":false,"transform":{"type":"scalar"},"locs":[{"a":61,"b":67}]}],"statement":"INSERT INTO ref_views(ref, viewer, viewedAt)\nVALUES (:refId, :viewer, NOW())"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO ref_views(ref, viewer, viewedAt)
+ * VALUES (:refId, :viewer, NOW())
+ * ```
+ */
+export const recordView = new PreparedQuery<IRecordViewParams,IRecordViewResult>(recordViewIR);
+
+
+/** 'GetViewCount' parameters type */
+export interface IGetViewCountParams {
+  refId?: string | null | void;
+}
+
+/** 'GetViewCount' return type */
+export interface IGetViewCountResult {
+  views: number | null;
+}
+
+/** 'GetViewCount' query type */
+export interface IGetViewCountQuery {
+  params: IGetViewCountParams;
+  result: IGetViewCountResult;
+}
+
+const getViewCountIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":57,"b":62}]}],"statement":"SELECT COUNT(*)::INT AS views\nFROM ref_views\nWHERE ref = :refId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT COUNT(*)::INT AS views
+ * FROM ref_views
+ * WHERE ref = :refId
+ * ```
+ */
+export const getViewCount = new PreparedQuery<IGetViewCountParams,IGetViewCountResult>(getViewCountIR);
+
+
+/** 'GetDailyViews' parameters type */
+export interface IGetDailyViewsParams {
+  days?: number | null | void;
+  refId?: string | null | void;
+}
+
+/** 'GetDailyViews' return type */
+export interface IGetDailyViewsResult {
+  day: Date | null;
+  viewers: number | null;
+  views: number | null;
+}
+
+/** 'GetDailyViews' query type */
+export interface IGetDailyViewsQuery {
+  params: IGetDailyViewsParams;
+  result: IGetDailyViewsResult;
+}
+
+const getDailyViewsIR: any = {"usedParamSet":{"refId":true,"days":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":137,"b":142}]},{"name":"days","required":false,"transform":{"type":"scalar"},"locs":[{"a":209,"b":213}]}],"statement":"SELECT date_trunc('day', viewedAt) AS day, COUNT(*)::INT AS views,\n    COUNT(DISTINCT viewer)::INT AS viewers\nFROM ref_views\nWHERE ref = :refId AND viewedAt >= date_trunc('day', NOW()) - make_interval(days => :days)\nGROUP BY day\nORDER BY day"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT date_trunc('day', viewedAt) AS day, COUNT(*)::INT AS views,
+ *     COUNT(DISTINCT viewer)::INT AS viewers
+ * FROM ref_views
+ * WHERE ref = :refId AND viewedAt >= date_trunc('day', NOW()) - make_interval(days => :days)
+ * GROUP BY day
+ * ORDER BY day
+ * ```
+ */
+export const getDailyViews = new PreparedQuery<IGetDailyViewsParams,IGetDailyViewsResult>(getDailyViewsIR);
+
+
+/** 'GetRefStats' parameters type */
+export interface IGetRefStatsParams {
+  refId?: string | null | void;
+}
+
+/** 'GetRefStats' return type */
+export interface IGetRefStatsResult {
+  bytes: string | null;
+  snapshots: number | null;
+  witnesses: number | null;
+}
+
+/** 'GetRefStats' query type */
+export interface IGetRefStatsQuery {
+  params: IGetRefStatsParams;
+  result: IGetRefStatsResult;
+}
+
+const getRefStatsIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":79,"b":84},{"a":137,"b":142},{"a":230,"b":235}]}],"statement":"WITH refSnapshots AS (\n    SELECT snapshot AS id FROM witnesses WHERE forRef = :refId\n    UNION\n    SELECT autosave FROM refs WHERE id = :refId AND autosave IS NOT NULL\n)\nSELECT (SELECT COUNT(*)::INT FROM witnesses WHERE forRef = :refId) AS witnesses,\n    COUNT(*)::INT AS snapshots,\n    COALESCE(SUM(octet_length(snapshots.content)), 0)::BIGINT AS bytes\nFROM refSnapshots\nINNER JOIN snapshots ON snapshots.id = refSnapshots.id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * WITH refSnapshots AS (
+ *     SELECT snapshot AS id FROM witnesses WHERE forRef = :refId
+ *     UNION
+ *     SELECT autosave FROM refs WHERE id = :refId AND autosave IS NOT NULL
+ * )
+ * SELECT (SELECT COUNT(*)::INT FROM witnesses WHERE forRef = :refId) AS witnesses,
+ *     COUNT(*)::INT AS snapshots,
+ *     COALESCE(SUM(octet_length(snapshots.content)), 0)::BIGINT AS bytes
+ * FROM refSnapshots
+ * INNER JOIN snapshots ON snapshots.id = refSnapshots.id
+ * ```
+ */
+export const getRefStats = new PreparedQuery<IGetRefStatsParams,IGetRefStatsResult>(getRefStatsIR);
+
+
+/** 'GetWitnessesSince' parameters type */
+export interface IGetWitnessesSinceParams {
+  limit?: number | null | void;
+  refId?: string | null | void;
+  since?: number | null | void;
+}
+
+/** 'GetWitnessesSince' return type */
+export interface IGetWitnessesSinceResult {
+  attime: Date;
+  id: number;
+  note: string | null;
+  snapshot: number;
+}
+
+/** 'GetWitnessesSince' query type */
+export interface IGetWitnessesSinceQuery {
+  params: IGetWitnessesSinceParams;
+  result: IGetWitnessesSinceResult;
+}
+
+const getWitnessesSinceIR: any = {"usedParamSet":{"refId":true,"since":true,"limit":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":64,"b":69}]},{"name":"since","required":false,"transform":{"type":"scalar"},"locs":[{"a":80,"b":85}]},{"name":"limit","required":false,"transform":{"type":"scalar"},"locs":[{"a":105,"b":110}]}],"statement":"SELECT id, snapshot, note, atTime\nFROM witnesses\nWHERE forRef = :refId AND id > :since\nORDER BY id\nLIMIT :limit::INT"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT id, snapshot, note, atTime
+ * FROM witnesses
+ * WHERE forRef = :refId AND id > :since
+ * ORDER BY id
+ * LIMIT :limit::INT
+ * ```
+ */
+export const getWitnessesSince = new PreparedQuery<IGetWitnessesSinceParams,IGetWitnessesSinceResult>(getWitnessesSinceIR);
+
+
+/** 'GetWitness' parameters type */
+export interface IGetWitnessParams {
+  id?: number | null | void;
+  refId?: string | null | void;
+}
+
+/** 'GetWitness' return type */
+export interface IGetWitnessResult {
+  attime: Date;
+  id: number;
+  note: string | null;
+  snapshot: number;
+}
+
+/** 'GetWitness' query type */
+export interface IGetWitnessQuery {
+  params: IGetWitnessParams;
+  result: IGetWitnessResult;
+}
+
+const getWitnessIR: any = {"usedParamSet":{"refId":true,"id":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":64,"b":69}]},{"name":"id","required":false,"transform":{"type":"scalar"},"locs":[{"a":80,"b":82}]}],"statement":"SELECT id, snapshot, note, atTime\nFROM witnesses\nWHERE forRef = :refId AND id = :id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT id, snapshot, note, atTime
+ * FROM witnesses
+ * WHERE forRef = :refId AND id = :id
+ * ```
+ */
+export const getWitness = new PreparedQuery<IGetWitnessParams,IGetWitnessResult>(getWitnessIR);
+
+
+/** 'GetSnapshot' parameters type */
+export interface IGetSnapshotParams {
+  id?: number | null | void;
+}
+
+/** 'GetSnapshot' return type */
+export interface IGetSnapshotResult {
+  content: string;
+}
+
+/** 'GetSnapshot' query type */
+export interface IGetSnapshotQuery {
+  params: IGetSnapshotParams;
+  result: IGetSnapshotResult;
+}
+
+const getSnapshotIR: any = {"usedParamSet":{"id":true},"params":[{"name":"id","required":false,"transform":{"type":"scalar"},"locs":[{"a":41,"b":43}]}],"statement":"SELECT content\nFROM snapshots\nWHERE id = :id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT content
+ * FROM snapshots
+ * WHERE id = :id
+ * ```
+ */
+export const getSnapshot = new PreparedQuery<IGetSnapshotParams,IGetSnapshotResult>(getSnapshotIR);
+
+
+/** 'PinSnapshot' parameters type */
+export interface IPinSnapshotParams {
+  note?: string | null | void;
+  refId?: string | null | void;
+  snapshotId?: number | null | void;
+}
+
+/** 'PinSnapshot' return type */
+export interface IPinSnapshotResult {
+  snapshot: number;
+}
+
+/** 'PinSnapshot' query type */
+export interface IPinSnapshotQuery {
+  params: IPinSnapshotParams;
+  result: IPinSnapshotResult;
+}
+
+const pinSnapshotIR: any = {"usedParamSet":{"refId":true,"snapshotId":true,"note":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":55,"b":60},{"a":142,"b":147},{"a":222,"b":227}]},{"name":"snapshotId","required":false,"transform":{"type":"scalar"},"locs":[{"a":63,"b":73},{"a":164,"b":174},{"a":244,"b":254}]},{"name":"note","required":false,"transform":{"type":"scalar"},"locs":[{"a":76,"b":80}]}],"statement":"INSERT INTO pins(ref, snapshot, note, pinnedAt)\nSELECT :refId, :snapshotId, :note, NOW()\nWHERE EXISTS (SELECT 1 FROM witnesses WHERE forRef = :refId AND snapshot = :snapshotId)\n    OR EXISTS (SELECT 1 FROM refs WHERE id = :refId AND autosave = :snapshotId)\nON CONFLICT (ref, snapshot) DO UPDATE SET note = EXCLUDED.note\nRETURNING snapshot"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO pins(ref, snapshot, note, pinnedAt)
+ * SELECT :refId, :snapshotId, :note, NOW()
+ * WHERE EXISTS (SELECT 1 FROM witnesses WHERE forRef = :refId AND snapshot = :snapshotId)
+ *     OR EXISTS (SELECT 1 FROM refs WHERE id = :refId AND autosave = :snapshotId)
+ * ON CONFLICT (ref, snapshot) DO UPDATE SET note = EXCLUDED.note
+ * RETURNING snapshot
+ * ```
+ */
+export const pinSnapshot = new PreparedQuery<IPinSnapshotParams,IPinSnapshotResult>(pinSnapshotIR);
+
+
+/** 'UnpinSnapshot' parameters type */
+export interface IUnpinSnapshotParams {
+  refId?: string | null | void;
+  snapshotId?: number | null | void;
+}
+
+/** 'UnpinSnapshot' return type */
+export interface IUnpinSnapshotResult {
+  snapshot: number;
+}
+
+/** 'UnpinSnapshot' query type */
+export interface IUnpinSnapshotQuery {
+  params: IUnpinSnapshotParams;
+  result: IUnpinSnapshotResult;
+}
+
+const unpinSnapshotIR: any = {"usedParamSet":{"refId":true,"snapshotId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":29,"b":34}]},{"name":"snapshotId","required":false,"transform":{"type":"scalar"},"locs":[{"a":51,"b":61}]}],"statement":"DELETE FROM pins\nWHERE ref = :refId AND snapshot = :snapshotId\nRETURNING snapshot"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * DELETE FROM pins
+ * WHERE ref = :refId AND snapshot = :snapshotId
+ * RETURNING snapshot
+ * ```
+ */
+export const unpinSnapshot = new PreparedQuery<IUnpinSnapshotParams,IUnpinSnapshotResult>(unpinSnapshotIR);
+
+
+/** 'GetPins' parameters type */
+export interface IGetPinsParams {
+  refId?: string | null | void;
+}
+
+/** 'GetPins' return type */
+export interface IGetPinsResult {
+  note: string | null;
+  pinnedat: Date;
+  snapshot: number;
+}
+
+/** 'GetPins' query type */
+export interface IGetPinsQuery {
+  params: IGetPinsParams;
+  result: IGetPinsResult;
+}
+
+const getPinsIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":54,"b":59}]}],"statement":"SELECT snapshot, note, pinnedAt\nFROM pins\nWHERE ref = :refId\nORDER BY pinnedAt"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT snapshot, note, pinnedAt
+ * FROM pins
+ * WHERE ref = :refId
+ * ORDER BY pinnedAt
+ * ```
+ */
+export const getPins = new PreparedQuery<IGetPinsParams,IGetPinsResult>(getPinsIR);
+
+
+/** 'NewAttachment' parameters type */
+export interface INewAttachmentParams {
+  contentType?: string | null | void;
+  filename?: string | null | void;
+  quota?: NumberOrString | null | void;
+  refId?: string | null | void;
+  size?: NumberOrString | null | void;
+}
+
+/** 'NewAttachment' return type */
+export interface INewAttachmentResult {
+  id: number;
+}
+
+/** 'NewAttachment' query type */
+export interface INewAttachmentQuery {
+  params: INewAttachmentParams;
+  result: INewAttachmentResult;
+}
+
+const newAttachmentIR: any = {"usedParamSet":{"refId":true,"filename":true,"contentType":true,"size":true,"quota":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":76,"b":81},{"a":188,"b":193}]},{"name":"filename","required":false,"transform":{"type":"scalar"},"locs":[{"a":84,"b":92}]},{"name":"contentType","required":false,"transform":{"type":"scalar"},"locs":[{"a":95,"b":106}]},{"name":"size","required":false,"transform":{"type":"scalar"},"locs":[{"a":109,"b":113},{"a":198,"b":202}]},{"name":"quota","required":false,"transform":{"type":"scalar"},"locs":[{"a":207,"b":212}]}],"statement":"INSERT INTO attachments(ref, filename, contentType, size, createdAt)\nSELECT :refId, :filename, :contentType, :size, NOW()\nWHERE (SELECT COALESCE(SUM(size), 0) FROM attachments WHERE ref = :refId) + :size <= :quota\nRETURNING id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO attachments(ref, filename, contentType, size, createdAt)
+ * SELECT :refId, :filename, :contentType, :size, NOW()
+ * WHERE (SELECT COALESCE(SUM(size), 0) FROM attachments WHERE ref = :refId) + :size <= :quota
+ * RETURNING id
+ * ```
+ */
+export const newAttachment = new PreparedQuery<INewAttachmentParams,INewAttachmentResult>(newAttachmentIR);
+
+
+/** 'GetAttachments' parameters type */
+export interface IGetAttachmentsParams {
+  refId?: string | null | void;
+}
+
+/** 'GetAttachments' return type */
+export interface IGetAttachmentsResult {
+  contenttype: string;
+  createdat: Date;
+  filename: string;
+  id: number;
+  size: number;
+}
+
+/** 'GetAttachments' query type */
+export interface IGetAttachmentsQuery {
+  params: IGetAttachmentsParams;
+  result: IGetAttachmentsResult;
+}
+
+const getAttachmentsIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":79,"b":84}]}],"statement":"SELECT id, filename, contentType, size, createdAt\nFROM attachments\nWHERE ref = :refId\nORDER BY id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT id, filename, contentType, size, createdAt
+ * FROM attachments
+ * WHERE ref = :refId
+ * ORDER BY id
+ * ```
+ */
+export const getAttachments = new PreparedQuery<IGetAttachmentsParams,IGetAttachmentsResult>(getAttachmentsIR);
+
+
+/** 'GetAttachment' parameters type */
+export interface IGetAttachmentParams {
+  id?: number | null | void;
+}
+
+/** 'GetAttachment' return type */
+export interface IGetAttachmentResult {
+  contenttype: string;
+  createdat: Date;
+  filename: string;
+  id: number;
+  ref: string;
+  size: number;
+}
+
+/** 'GetAttachment' query type */
+export interface IGetAttachmentQuery {
+  params: IGetAttachmentParams;
+  result: IGetAttachmentResult;
+}
+
+const getAttachmentIR: any = {"usedParamSet":{"id":true},"params":[{"name":"id","required":false,"transform":{"type":"scalar"},"locs":[{"a":83,"b":85}]}],"statement":"SELECT id, ref, filename, contentType, size, createdAt\nFROM attachments\nWHERE id = :id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT id, ref, filename, contentType, size, createdAt
+ * FROM attachments
+ * WHERE id = :id
+ * ```
+ */
+export const getAttachment = new PreparedQuery<IGetAttachmentParams,IGetAttachmentResult>(getAttachmentIR);
+
+
+/** 'DeleteAttachment' parameters type */
+export interface IDeleteAttachmentParams {
+  id?: number | null | void;
+}
+
+/** 'DeleteAttachment' return type */
+export interface IDeleteAttachmentResult {
+  id: number;
+}
+
+/** 'DeleteAttachment' query type */
+export interface IDeleteAttachmentQuery {
+  params: IDeleteAttachmentParams;
+  result: IDeleteAttachmentResult;
+}
+
+const deleteAttachmentIR: any = {"usedParamSet":{"id":true},"params":[{"name":"id","required":false,"transform":{"type":"scalar"},"locs":[{"a":35,"b":37}]}],"statement":"DELETE FROM attachments\nWHERE id = :id\nRETURNING id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * DELETE FROM attachments
+ * WHERE id = :id
+ * RETURNING id
+ * ```
+ */
+export const deleteAttachment = new PreparedQuery<IDeleteAttachmentParams,IDeleteAttachmentResult>(deleteAttachmentIR);
+
+
+/** 'GetAttachmentUsage' parameters type */
+export interface IGetAttachmentUsageParams {
+  refId?: string | null | void;
+}
+
+/** 'GetAttachmentUsage' return type */
+export interface IGetAttachmentUsageResult {
+  bytes: string | null;
+}
+
+/** 'GetAttachmentUsage' query type */
+export interface IGetAttachmentUsageQuery {
+  params: IGetAttachmentUsageParams;
+  result: IGetAttachmentUsageResult;
+}
+
+const getAttachmentUsageIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":76,"b":81}]}],"statement":"SELECT COALESCE(SUM(size), 0)::BIGINT AS bytes\nFROM attachments\nWHERE ref = :refId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT COALESCE(SUM(size), 0)::BIGINT AS bytes
+ * FROM attachments
+ * WHERE ref = :refId
+ * ```
+ */
+export const getAttachmentUsage = new PreparedQuery<IGetAttachmentUsageParams,IGetAttachmentUsageResult>(getAttachmentUsageIR);
+
+
+/** 'SearchRefs' parameters type */
+export interface ISearchRefsParams {
+  limit?: number | null | void;
+  query?: string | null | void;
+  userId?: string | null | void;
+}
+
+/** 'SearchRefs' return type */
+export interface ISearchRefsResult {
+  id: string;
+  lastupdated: Date;
+  score: number | null;
+  slug: string | null;
+  title: string | null;
+  type: string | null;
+}
+
+/** 'SearchRefs' query type */
+export interface ISearchRefsQuery {
+  params: ISearchRefsParams;
+  result: ISearchRefsResult;
+}
+
+const searchRefsIR: any = {"usedParamSet":{"query":true,"userId":true,"limit":true},"params":[{"name":"query","required":false,"transform":{"type":"scalar"},"locs":[{"a":121,"b":126},{"a":169,"b":174},{"a":238,"b":243},{"a":335,"b":340},{"a":518,"b":523},{"a":606,"b":611},{"a":667,"b":672},{"a":726,"b":731}]},{"name":"userId","required":false,"transform":{"type":"scalar"},"locs":[{"a":1027,"b":1033}]},{"name":"limit","required":false,"transform":{"type":"scalar"},"locs":[{"a":1085,"b":1090}]}],"statement":"WITH hits AS (\n    SELECT id FROM refs\n    WHERE to_tsvector('simple', COALESCE(title, '')) @@ plainto_tsquery('simple', :query)\n    UNION\n    SELECT id FROM refs WHERE :query <% title\n    UNION\n    SELECT ref FROM system_documents WHERE :query <% slug\n    UNION\n    SELECT id FROM refs WHERE searchVector @@ plainto_tsquery('simple', :query)\n)\nSELECT refs.id, refs.title, system_documents.slug, refs.docType AS type, refs.lastUpdated,\n    (to_tsvector('simple', COALESCE(refs.title, '')) @@ plainto_tsquery('simple', :query))::INT\n    + 0.5::REAL * COALESCE(refs.searchVector @@ plainto_tsquery('simple', :query), FALSE)::INT\n    + GREATEST(\n        word_similarity(:query, COALESCE(refs.title, '')),\n        word_similarity(:query, COALESCE(system_documents.slug, ''))\n    ) AS score\nFROM hits\nJOIN refs ON refs.id = hits.id\nLEFT JOIN system_documents ON system_documents.ref = refs.id\nWHERE NOT EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id)\n    OR EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id AND userId = :userId)\nORDER BY score DESC, refs.lastUpdated DESC\nLIMIT :limit::INT"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * WITH hits AS (
+ *     SELECT id FROM refs
+ *     WHERE to_tsvector('simple', COALESCE(title, '')) @@ plainto_tsquery('simple', :query)
+ *     UNION
+ *     SELECT id FROM refs WHERE :query <% title
+ *     UNION
+ *     SELECT ref FROM system_documents WHERE :query <% slug
+ *     UNION
+ *     SELECT id FROM refs WHERE searchVector @@ plainto_tsquery('simple', :query)
+ * )
+ * SELECT refs.id, refs.title, system_documents.slug, refs.docType AS type, refs.lastUpdated,
+ *     (to_tsvector('simple', COALESCE(refs.title, '')) @@ plainto_tsquery('simple', :query))::INT
+ *     + 0.5::REAL * COALESCE(refs.searchVector @@ plainto_tsquery('simple', :query), FALSE)::INT
+ *     + GREATEST(
+ *         word_similarity(:query, COALESCE(refs.title, '')),
+ *         word_similarity(:query, COALESCE(system_documents.slug, ''))
+ *     ) AS score
+ * FROM hits
+ * JOIN refs ON refs.id = hits.id
+ * LEFT JOIN system_documents ON system_documents.ref = refs.id
+ * WHERE NOT EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id)
+ *     OR EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id AND userId = :userId)
+ * ORDER BY score DESC, refs.lastUpdated DESC
+ * LIMIT :limit::INT
+ * ```
+ */
+export const searchRefs = new PreparedQuery<ISearchRefsParams,ISearchRefsResult>(searchRefsIR);
+
+
+/** 'AppendAutosave' parameters type */
+export interface IAppendAutosaveParams {
+  content?: string | null | void;
+  refId?: string | null | void;
+}
+
+/** 'AppendAutosave' return type */
+export type IAppendAutosaveResult = void;
+
+/** 'AppendAutosave' query type */
+export interface IAppendAutosaveQuery {
+  params: IAppendAutosaveParams;
+  result: IAppendAutosaveResult;
+}
+
+const appendAutosaveIR: any = {"usedParamSet":{"refId":true,"content":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":57,"b":62}]},{"name":"content","required":false,"transform":{"type":"scalar"},"locs":[{"a":65,"b":72}]}],"statement":"INSERT INTO autosave_log(ref, content, loggedAt)\nVALUES (:refId, :content, NOW())"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO autosave_log(ref, content, loggedAt)
+ * VALUES (:refId, :content, NOW())
+ * ```
+ */
+export const appendAutosave = new PreparedQuery<IAppendAutosaveParams,IAppendAutosaveResult>(appendAutosaveIR);
+
+
+/** 'GetPendingAutosave' parameters type */
+export interface IGetPendingAutosaveParams {
+  refId?: string | null | void;
+}
+
+/** 'GetPendingAutosave' return type */
+export interface IGetPendingAutosaveResult {
+  content: string;
+}
+
+/** 'GetPendingAutosave' query type */
+export interface IGetPendingAutosaveQuery {
+  params: IGetPendingAutosaveParams;
+  result: IGetPendingAutosaveResult;
+}
+
+const getPendingAutosaveIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":45,"b":50}]}],"statement":"SELECT content\nFROM autosave_log\nWHERE ref = :refId\nORDER BY id DESC\nLIMIT 1"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT content
+ * FROM autosave_log
+ * WHERE ref = :refId
+ * ORDER BY id DESC
+ * LIMIT 1
+ * ```
+ */
+export const getPendingAutosave = new PreparedQuery<IGetPendingAutosaveParams,IGetPendingAutosaveResult>(getPendingAutosaveIR);
+
+
+/** 'ClearAutosaveLog' parameters type */
+export interface IClearAutosaveLogParams {
+  refId?: string | null | void;
+}
+
+/** 'ClearAutosaveLog' return type */
+export type IClearAutosaveLogResult = void;
+
+/** 'ClearAutosaveLog' query type */
+export interface IClearAutosaveLogQuery {
+  params: IClearAutosaveLogParams;
+  result: IClearAutosaveLogResult;
+}
+
+const clearAutosaveLogIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":37,"b":42}]}],"statement":"DELETE FROM autosave_log WHERE ref = :refId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * DELETE FROM autosave_log WHERE ref = :refId
+ * ```
+ */
+export const clearAutosaveLog = new PreparedQuery<IClearAutosaveLogParams,IClearAutosaveLogResult>(clearAutosaveLogIR);
+
+
+/** 'GetRefsWithPendingAutosaves' parameters type */
+export interface IGetRefsWithPendingAutosavesParams {
+  limit?: number | null | void;
+}
+
+/** 'GetRefsWithPendingAutosaves' return type */
+export interface IGetRefsWithPendingAutosavesResult {
+  ref: string;
+}
+
+/** 'GetRefsWithPendingAutosaves' query type */
+export interface IGetRefsWithPendingAutosavesQuery {
+  params: IGetRefsWithPendingAutosavesParams;
+  result: IGetRefsWithPendingAutosavesResult;
+}
+
+const getRefsWithPendingAutosavesIR: any = {"usedParamSet":{"limit":true},"params":[{"name":"limit","required":false,"transform":{"type":"scalar"},"locs":[{"a":44,"b":49}]}],"statement":"SELECT DISTINCT ref\nFROM autosave_log\nLIMIT :limit::INT"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT DISTINCT ref
+ * FROM autosave_log
+ * LIMIT :limit::INT
+ * ```
+ */
+export const getRefsWithPendingAutosaves = new PreparedQuery<IGetRefsWithPendingAutosavesParams,IGetRefsWithPendingAutosavesResult>(getRefsWithPendingAutosavesIR);
+
+
+/** 'NewCollabSession' parameters type */
+export interface INewCollabSessionParams {
+  edits?: number | null | void;
+  endedAt?: DateOrString | null | void;
+  meanPeers?: number | null | void;
+  participants?: Json | null | void;
+  peakPeers?: number | null | void;
+  refId?: string | null | void;
+  startedAt?: DateOrString | null | void;
+}
+
+/** 'NewCollabSession' return type */
+export type INewCollabSessionResult = void;
+
+/** 'NewCollabSession' query type */
+export interface INewCollabSessionQuery {
+  params: INewCollabSessionParams;
+  result: INewCollabSessionResult;
+}
+
+const newCollabSessionIR: any = {"usedParamSet":{"refId":true,"startedAt":true,"endedAt":true,"peakPeers":true,"meanPeers":true,"edits":true,"participants":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":104,"b":109}]},{"name":"startedAt","required":false,"transform":{"type":"scalar"},"locs":[{"a":112,"b":121}]},{"name":"endedAt","required":false,"transform":{"type":"scalar"},"locs":[{"a":124,"b":131}]},{"name":"peakPeers","required":false,"transform":{"type":"scalar"},"locs":[{"a":134,"b":143}]},{"name":"meanPeers","required":false,"transform":{"type":"scalar"},"locs":[{"a":146,"b":155}]},{"name":"edits","required":false,"transform":{"type":"scalar"},"locs":[{"a":158,"b":163}]},{"name":"participants","required":false,"transform":{"type":"scalar"},"locs":[{"a":166,"b":178}]}],"statement":"INSERT INTO collab_sessions(ref, startedAt, endedAt, peakPeers, meanPeers, edits, participants)\nVALUES (:refId, :startedAt, :endedAt, :peakPeers, :meanPeers, :edits, :participants)"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO collab_sessions(ref, startedAt, endedAt, peakPeers, meanPeers, edits, participants)
+ * VALUES (:refId, :startedAt, :endedAt, :peakPeers, :meanPeers, :edits, :participants)
+ * ```
+ */
+export const newCollabSession = new PreparedQuery<INewCollabSessionParams,INewCollabSessionResult>(newCollabSessionIR);
+
+
+/** 'GetCollabSessions' parameters type */
+export interface IGetCollabSessionsParams {
+  days?: number | null | void;
+  refId?: string | null | void;
+}
+
+/** 'GetCollabSessions' return type */
+export interface IGetCollabSessionsResult {
+  edits: number;
+  endedat: Date;
+  id: number;
+  meanpeers: number;
+  participants: Json;
+  peakpeers: number;
+  startedat: Date;
+}
+
+/** 'GetCollabSessions' query type */
+export interface IGetCollabSessionsQuery {
+  params: IGetCollabSessionsParams;
+  result: IGetCollabSessionsResult;
+}
+
+const getCollabSessionsIR: any = {"usedParamSet":{"refId":true,"days":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":106,"b":111}]},{"name":"days","required":false,"transform":{"type":"scalar"},"locs":[{"a":160,"b":164}]}],"statement":"SELECT id, startedAt, endedAt, peakPeers, meanPeers, edits, participants\nFROM collab_sessions\nWHERE ref = :refId AND startedAt >= NOW() - make_interval(days => :days)\nORDER BY startedAt DESC"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT id, startedAt, endedAt, peakPeers, meanPeers, edits, participants
+ * FROM collab_sessions
+ * WHERE ref = :refId AND startedAt >= NOW() - make_interval(days => :days)
+ * ORDER BY startedAt DESC
+ * ```
+ */
+export const getCollabSessions = new PreparedQuery<IGetCollabSessionsParams,IGetCollabSessionsResult>(getCollabSessionsIR);
+
+
+/** 'NewPermalink' parameters type */
+export interface INewPermalinkParams {
+  refId?: string | null | void;
+  snapshotId?: number | null | void;
+}
+
+/** 'NewPermalink' return type */
+export interface INewPermalinkResult {
+  token: string | null;
+}
+
+/** 'NewPermalink' query type */
+export interface INewPermalinkQuery {
+  params: INewPermalinkParams;
+  result: INewPermalinkResult;
+}
+
+const newPermalinkIR: any = {"usedParamSet":{"refId":true,"snapshotId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":87,"b":92},{"a":165,"b":170},{"a":297,"b":302},{"a":381,"b":386}]},{"name":"snapshotId","required":false,"transform":{"type":"scalar"},"locs":[{"a":229,"b":239},{"a":319,"b":329},{"a":403,"b":413}]}],"statement":"INSERT INTO permalinks(token, hash, ref, snapshot, createdAt)\nSELECT digest(convert_to(:refId::UUID::TEXT, 'UTF8') || snapshots.hash, 'sha256'),\n    snapshots.hash, :refId, snapshots.id, NOW()\nFROM snapshots\nWHERE snapshots.id = :snapshotId\n    AND (EXISTS (SELECT 1 FROM witnesses WHERE forRef = :refId AND snapshot = :snapshotId)\n        OR EXISTS (SELECT 1 FROM refs WHERE id = :refId AND autosave = :snapshotId))\nON CONFLICT (ref, hash) DO UPDATE SET hash = EXCLUDED.hash\nRETURNING encode(token, 'hex') AS token"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO permalinks(token, hash, ref, snapshot, createdAt)
+ * SELECT digest(convert_to(:refId::UUID::TEXT, 'UTF8') || snapshots.hash, 'sha256'),
+ *     snapshots.hash, :refId, snapshots.id, NOW()
+ * FROM snapshots
+ * WHERE snapshots.id = :snapshotId
+ *     AND (EXISTS (SELECT 1 FROM witnesses WHERE forRef = :refId AND snapshot = :snapshotId)
+ *         OR EXISTS (SELECT 1 FROM refs WHERE id = :refId AND autosave = :snapshotId))
+ * ON CONFLICT (ref, hash) DO UPDATE SET hash = EXCLUDED.hash
+ * RETURNING encode(token, 'hex') AS token
+ * ```
+ */
+export const newPermalink = new PreparedQuery<INewPermalinkParams,INewPermalinkResult>(newPermalinkIR);
+
+
+/** 'GetPermalink' parameters type */
+export interface IGetPermalinkParams {
+  token?: string | null | void;
+}
+
+/** 'GetPermalink' return type */
+export interface IGetPermalinkResult {
+  content: string;
+  createdat: Date;
+  intact: boolean | null;
+  ref: string;
+  snapshot: number;
+}
+
+/** 'GetPermalink' query type */
+export interface IGetPermalinkQuery {
+  params: IGetPermalinkParams;
+  result: IGetPermalinkResult;
+}
+
+const getPermalinkIR: any = {"usedParamSet":{"token":true},"params":[{"name":"token","required":false,"transform":{"type":"scalar"},"locs":[{"a":260,"b":265}]}],"statement":"SELECT permalinks.ref, permalinks.snapshot, permalinks.createdAt, snapshots.content,\n    permalinks.hash = digest(snapshots.content, 'sha256') AS intact\nFROM permalinks\nINNER JOIN snapshots ON snapshots.id = permalinks.snapshot\nWHERE permalinks.token = decode(:token, 'hex')"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT permalinks.ref, permalinks.snapshot, permalinks.createdAt, snapshots.content,
+ *     permalinks.hash = digest(snapshots.content, 'sha256') AS intact
+ * FROM permalinks
+ * INNER JOIN snapshots ON snapshots.id = permalinks.snapshot
+ * WHERE permalinks.token = decode(:token, 'hex')
+ * ```
+ */
+export const getPermalink = new PreparedQuery<IGetPermalinkParams,IGetPermalinkResult>(getPermalinkIR);
+
+
+/** 'GetRefSnapshots' parameters type */
+export interface IGetRefSnapshotsParams {
+  refId?: string | null | void;
+}
+
+/** 'GetRefSnapshots' return type */
+export interface IGetRefSnapshotsResult {
+  content: string;
+  id: number;
+}
+
+/** 'GetRefSnapshots' query type */
+export interface IGetRefSnapshotsQuery {
+  params: IGetRefSnapshotsParams;
+  result: IGetRefSnapshotsResult;
+}
+
+const getRefSnapshotsIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":98,"b":103},{"a":152,"b":157},{"a":207,"b":212},{"a":268,"b":273},{"a":388,"b":393}]}],"statement":"SELECT id, content\nFROM snapshots\nWHERE id IN (\n    SELECT snapshot FROM witnesses WHERE forRef = :refId\n    UNION SELECT autosave FROM refs WHERE id = :refId\n    UNION SELECT snapshot FROM pins WHERE ref = :refId\n    UNION SELECT snapshot FROM permalinks WHERE ref = :refId\n    UNION SELECT (payload ->> 'snapshotId')::INT FROM jobs\n    WHERE kind = 'analysis' AND payload ->> 'refId' = :refId::UUID::TEXT\n)\nORDER BY id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT id, content
+ * FROM snapshots
+ * WHERE id IN (
+ *     SELECT snapshot FROM witnesses WHERE forRef = :refId
+ *     UNION SELECT autosave FROM refs WHERE id = :refId
+ *     UNION SELECT snapshot FROM pins WHERE ref = :refId
+ *     UNION SELECT snapshot FROM permalinks WHERE ref = :refId
+ *     UNION SELECT (payload ->> 'snapshotId')::INT FROM jobs
+ *     WHERE kind = 'analysis' AND payload ->> 'refId' = :refId::UUID::TEXT
+ * )
+ * ORDER BY id
+ * ```
+ */
+export const getRefSnapshots = new PreparedQuery<IGetRefSnapshotsParams,IGetRefSnapshotsResult>(getRefSnapshotsIR);
+
+
+/** 'ReplaceRefSnapshot' parameters type */
+export interface IReplaceRefSnapshotParams {
+  newId?: number | null | void;
+  oldId?: number | null | void;
+  refId?: string | null | void;
+}
+
+/** 'ReplaceRefSnapshot' return type */
+export type IReplaceRefSnapshotResult = void;
+
+/** 'ReplaceRefSnapshot' query type */
+export interface IReplaceRefSnapshotQuery {
+  params: IReplaceRefSnapshotParams;
+  result: IReplaceRefSnapshotResult;
+}
+
+const replaceRefSnapshotIR: any = {"usedParamSet":{"newId":true,"refId":true,"oldId":true},"params":[{"name":"newId","required":false,"transform":{"type":"scalar"},"locs":[{"a":56,"b":61},{"a":156,"b":161},{"a":255,"b":260},{"a":398,"b":403},{"a":522,"b":527},{"a":866,"b":871}]},{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":78,"b":83},{"a":174,"b":179},{"a":274,"b":279},{"a":618,"b":623},{"a":747,"b":752},{"a":858,"b":863}]},{"name":"oldId","required":false,"transform":{"type":"scalar"},"locs":[{"a":100,"b":105},{"a":196,"b":201},{"a":296,"b":301},{"a":684,"b":689},{"a":769,"b":774}]}],"statement":"WITH witnessed AS (\n    UPDATE witnesses SET snapshot = :newId WHERE forRef = :refId AND snapshot = :oldId\n), autosaved AS (\n    UPDATE refs SET autosave = :newId WHERE id = :refId AND autosave = :oldId\n), linked AS (\n    UPDATE permalinks SET snapshot = :newId WHERE ref = :refId AND snapshot = :oldId\n), analyzed AS (\n    UPDATE jobs\n    SET payload = jsonb_set(payload, '{snapshotId}', to_jsonb(:newId::INT)),\n        result = CASE WHEN result ? 'snapshotId'\n            THEN jsonb_set(result, '{snapshotId}', to_jsonb(:newId::INT))\n            ELSE result END\n    WHERE kind = 'analysis' AND payload ->> 'refId' = :refId::UUID::TEXT\n        AND payload -> 'snapshotId' = to_jsonb(:oldId::INT)\n), unpinned AS (\n    DELETE FROM pins WHERE ref = :refId AND snapshot = :oldId RETURNING note, pinnedAt\n)\nINSERT INTO pins(ref, snapshot, note, pinnedAt)\nSELECT :refId, :newId, note, pinnedAt FROM unpinned\nON CONFLICT (ref, snapshot) DO NOTHING"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * WITH witnessed AS (
+ *     UPDATE witnesses SET snapshot = :newId WHERE forRef = :refId AND snapshot = :oldId
+ * ), autosaved AS (
+ *     UPDATE refs SET autosave = :newId WHERE id = :refId AND autosave = :oldId
+ * ), linked AS (
+ *     UPDATE permalinks SET snapshot = :newId WHERE ref = :refId AND snapshot = :oldId
+ * ), analyzed AS (
+ *     UPDATE jobs
+ *     SET payload = jsonb_set(payload, '{snapshotId}', to_jsonb(:newId::INT)),
+ *         result = CASE WHEN result ? 'snapshotId'
+ *             THEN jsonb_set(result, '{snapshotId}', to_jsonb(:newId::INT))
+ *             ELSE result END
+ *     WHERE kind = 'analysis' AND payload ->> 'refId' = :refId::UUID::TEXT
+ *         AND payload -> 'snapshotId' = to_jsonb(:oldId::INT)
+ * ), unpinned AS (
+ *     DELETE FROM pins WHERE ref = :refId AND snapshot = :oldId RETURNING note, pinnedAt
+ * )
+ * INSERT INTO pins(ref, snapshot, note, pinnedAt)
+ * SELECT :refId, :newId, note, pinnedAt FROM unpinned
+ * ON CONFLICT (ref, snapshot) DO NOTHING
+ * ```
+ */
+export const replaceRefSnapshot = new PreparedQuery<IReplaceRefSnapshotParams,IReplaceRefSnapshotResult>(replaceRefSnapshotIR);
+
+
+/** 'DeleteSnapshotIfOrphaned' parameters type */
+export interface IDeleteSnapshotIfOrphanedParams {
+  id?: number | null | void;
+}
+
+/** 'DeleteSnapshotIfOrphaned' return type */
+export interface IDeleteSnapshotIfOrphanedResult {
+  id: number;
+}
+
+/** 'DeleteSnapshotIfOrphaned' query type */
+export interface IDeleteSnapshotIfOrphanedQuery {
+  params: IDeleteSnapshotIfOrphanedParams;
+  result: IDeleteSnapshotIfOrphanedResult;
+}
+
+const deleteSnapshotIfOrphanedIR: any = {"usedParamSet":{"id":true},"params":[{"name":"id","required":false,"transform":{"type":"scalar"},"locs":[{"a":33,"b":35},{"a":98,"b":100},{"a":159,"b":161},{"a":220,"b":222},{"a":287,"b":289},{"a":411,"b":413}]}],"statement":"DELETE FROM snapshots\nWHERE id = :id\n    AND NOT EXISTS (SELECT 1 FROM witnesses WHERE snapshot = :id)\n    AND NOT EXISTS (SELECT 1 FROM refs WHERE autosave = :id)\n    AND NOT EXISTS (SELECT 1 FROM pins WHERE snapshot = :id)\n    AND NOT EXISTS (SELECT 1 FROM permalinks WHERE snapshot = :id)\n    AND NOT EXISTS (\n        SELECT 1 FROM jobs\n        WHERE kind = 'analysis' AND payload -> 'snapshotId' = to_jsonb(:id::INT)\n    )\nRETURNING id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * DELETE FROM snapshots
+ * WHERE id = :id
+ *     AND NOT EXISTS (SELECT 1 FROM witnesses WHERE snapshot = :id)
+ *     AND NOT EXISTS (SELECT 1 FROM refs WHERE autosave = :id)
+ *     AND NOT EXISTS (SELECT 1 FROM pins WHERE snapshot = :id)
+ *     AND NOT EXISTS (SELECT 1 FROM permalinks WHERE snapshot = :id)
+ *     AND NOT EXISTS (
+ *         SELECT 1 FROM jobs
+ *         WHERE kind = 'analysis' AND payload -> 'snapshotId' = to_jsonb(:id::INT)
+ *     )
+ * RETURNING id
+ * ```
+ */
+export const deleteSnapshotIfOrphaned = new PreparedQuery<IDeleteSnapshotIfOrphanedParams,IDeleteSnapshotIfOrphanedResult>(deleteSnapshotIfOrphanedIR);
+
+
+/** 'NewAuditEntry' parameters type */
+export interface INewAuditEntryParams {
+  action?: string | null | void;
+  details?: Json | null | void;
+  refId?: string | null | void;
+}
+
+/** 'NewAuditEntry' return type */
+export interface INewAuditEntryResult {
+  id: number;
+}
+
+/** 'NewAuditEntry' query type */
+export interface INewAuditEntryQuery {
+  params: INewAuditEntryParams;
+  result: INewAuditEntryResult;
+}
+
+const newAuditEntryIR: any = {"usedParamSet":{"refId":true,"action":true,"details":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":56,"b":61}]},{"name":"action","required":false,"transform":{"type":"scalar"},"locs":[{"a":64,"b":70}]},{"name":"details","required":false,"transform":{"type":"scalar"},"locs":[{"a":73,"b":80}]}],"statement":"INSERT INTO audit_log(ref, action, details, at)\nVALUES (:refId, :action, :details, NOW())\nRETURNING id"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO audit_log(ref, action, details, at)
+ * VALUES (:refId, :action, :details, NOW())
+ * RETURNING id
+ * ```
+ */
+export const newAuditEntry = new PreparedQuery<INewAuditEntryParams,INewAuditEntryResult>(newAuditEntryIR);
+
+
+/** 'GetAuditLog' parameters type */
+export interface IGetAuditLogParams {
+  refId?: string | null | void;
+}
+
+/** 'GetAuditLog' return type */
+export interface IGetAuditLogResult {
+  action: string;
+  at: Date;
+  details: Json;
+  id: number;
+}
+
+/** 'GetAuditLog' query type */
+export interface IGetAuditLogQuery {
+  params: IGetAuditLogParams;
+  result: IGetAuditLogResult;
+}
+
+const getAuditLogIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":58,"b":63}]}],"statement":"SELECT id, action, details, at\nFROM audit_log\nWHERE ref = :refId\nORDER BY at"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT id, action, details, at
+ * FROM audit_log
+ * WHERE ref = :refId
+ * ORDER BY at
+ * ```
+ */
+export const getAuditLog = new PreparedQuery<IGetAuditLogParams,IGetAuditLogResult>(getAuditLogIR);
+
+
+/** 'GetRefSnapshot' parameters type */
+export interface IGetRefSnapshotParams {
+  refId?: string | null | void;
+  snapshotId?: number | null | void;
+}
+
+/** 'GetRefSnapshot' return type */
+export interface IGetRefSnapshotResult {
+  content: string;
+}
+
+/** 'GetRefSnapshot' query type */
+export interface IGetRefSnapshotQuery {
+  params: IGetRefSnapshotParams;
+  result: IGetRefSnapshotResult;
+}
+
+const getRefSnapshotIR: any = {"usedParamSet":{"snapshotId":true,"refId":true},"params":[{"name":"snapshotId","required":false,"transform":{"type":"scalar"},"locs":[{"a":41,"b":51},{"a":131,"b":141},{"a":215,"b":225}]},{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":109,"b":114},{"a":193,"b":198}]}],"statement":"SELECT content\nFROM snapshots\nWHERE id = :snapshotId\n    AND (EXISTS (SELECT 1 FROM witnesses WHERE forRef = :refId AND snapshot = :snapshotId)\n        OR EXISTS (SELECT 1 FROM refs WHERE id = :refId AND autosave = :snapshotId))"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT content
+ * FROM snapshots
+ * WHERE id = :snapshotId
+ *     AND (EXISTS (SELECT 1 FROM witnesses WHERE forRef = :refId AND snapshot = :snapshotId)
+ *         OR EXISTS (SELECT 1 FROM refs WHERE id = :refId AND autosave = :snapshotId))
+ * ```
+ */
+export const getRefSnapshot = new PreparedQuery<IGetRefSnapshotParams,IGetRefSnapshotResult>(getRefSnapshotIR);
+
+
+/** 'UpsertUser' parameters type */
+export interface IUpsertUserParams {
+  email?: string | null | void;
+  id?: string | null | void;
+}
+
+/** 'UpsertUser' return type */
+export type IUpsertUserResult = void;
+
+/** 'UpsertUser' query type */
+export interface IUpsertUserQuery {
+  params: IUpsertUserParams;
+  result: IUpsertUserResult;
+}
+
+const upsertUserIR: any = {"usedParamSet":{"id":true,"email":true},"params":[{"name":"id","required":false,"transform":{"type":"scalar"},"locs":[{"a":58,"b":60}]},{"name":"email","required":false,"transform":{"type":"scalar"},"locs":[{"a":63,"b":68}]}],"statement":"INSERT INTO users(id, email, createdAt, lastSeen)\nVALUES (:id, :email, NOW(), NOW())\nON CONFLICT (id) DO UPDATE SET email = EXCLUDED.email, lastSeen = EXCLUDED.lastSeen"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO users(id, email, createdAt, lastSeen)
+ * VALUES (:id, :email, NOW(), NOW())
+ * ON CONFLICT (id) DO UPDATE SET email = EXCLUDED.email, lastSeen = EXCLUDED.lastSeen
+ * ```
+ */
+export const upsertUser = new PreparedQuery<IUpsertUserParams,IUpsertUserResult>(upsertUserIR);
+
+
+/** 'GetAccessLevel' parameters type */
+export interface IGetAccessLevelParams {
+  refId?: string | null | void;
+  userId?: string | null | void;
+}
+
+/** 'GetAccessLevel' return type */
+export interface IGetAccessLevelResult {
+  level: string | null;
+}
+
+/** 'GetAccessLevel' query type */
+export interface IGetAccessLevelQuery {
+  params: IGetAccessLevelParams;
+  result: IGetAccessLevelResult;
+}
+
+const getAccessLevelIR: any = {"usedParamSet":{"refId":true,"userId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":71,"b":76},{"a":145,"b":150}]},{"name":"userId","required":false,"transform":{"type":"scalar"},"locs":[{"a":165,"b":171}]}],"statement":"SELECT CASE\n    WHEN NOT EXISTS (SELECT 1 FROM permissions WHERE ref = :refId) THEN 'editor'\n    ELSE (SELECT level FROM permissions WHERE ref = :refId AND userId = :userId)\nEND AS level"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT CASE
+ *     WHEN NOT EXISTS (SELECT 1 FROM permissions WHERE ref = :refId) THEN 'editor'
+ *     ELSE (SELECT level FROM permissions WHERE ref = :refId AND userId = :userId)
+ * END AS level
+ * ```
+ */
+export const getAccessLevel = new PreparedQuery<IGetAccessLevelParams,IGetAccessLevelResult>(getAccessLevelIR);
+
+
+/** 'SetPermission' parameters type */
+export interface ISetPermissionParams {
+  level?: string | null | void;
+  refId?: string | null | void;
+  userId?: string | null | void;
+}
+
+/** 'SetPermission' return type */
+export type ISetPermissionResult = void;
+
+/** 'SetPermission' query type */
+export interface ISetPermissionQuery {
+  params: ISetPermissionParams;
+  result: ISetPermissionResult;
+}
+
+const setPermissionIR: any = {"usedParamSet":{"refId":true,"userId":true,"level":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":52,"b":57}]},{"name":"userId","required":false,"transform":{"type":"scalar"},"locs":[{"a":60,"b":66}]},{"name":"level","required":false,"transform":{"type":"scalar"},"locs":[{"a":69,"b":74}]}],"statement":"INSERT INTO permissions(ref, userId, level)\nVALUES (:refId, :userId, :level)\nON CONFLICT (ref, userId) DO UPDATE SET level = EXCLUDED.level"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO permissions(ref, userId, level)
+ * VALUES (:refId, :userId, :level)
+ * ON CONFLICT (ref, userId) DO UPDATE SET level = EXCLUDED.level
+ * ```
+ */
+export const setPermission = new PreparedQuery<ISetPermissionParams,ISetPermissionResult>(setPermissionIR);
+
+
+/** 'RemovePermission' parameters type */
+export interface IRemovePermissionParams {
+  refId?: string | null | void;
+  userId?: string | null | void;
+}
+
+/** 'RemovePermission' return type */
+export type IRemovePermissionResult = void;
+
+/** 'RemovePermission' query type */
+export interface IRemovePermissionQuery {
+  params: IRemovePermissionParams;
+  result: IRemovePermissionResult;
+}
+
+const removePermissionIR: any = {"usedParamSet":{"refId":true,"userId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":36,"b":41}]},{"name":"userId","required":false,"transform":{"type":"scalar"},"locs":[{"a":56,"b":62}]}],"statement":"DELETE FROM permissions\nWHERE ref = :refId AND userId = :userId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * DELETE FROM permissions
+ * WHERE ref = :refId AND userId = :userId
+ * ```
+ */
+export const removePermission = new PreparedQuery<IRemovePermissionParams,IRemovePermissionResult>(removePermissionIR);
+
+
+/** 'GetPermissions' parameters type */
+export interface IGetPermissionsParams {
+  refId?: string | null | void;
+}
+
+/** 'GetPermissions' return type */
+export interface IGetPermissionsResult {
+  email: string | null;
+  level: string;
+  userid: string;
+}
+
+/** 'GetPermissions' query type */
+export interface IGetPermissionsQuery {
+  params: IGetPermissionsParams;
+  result: IGetPermissionsResult;
+}
+
+const getPermissionsIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":148,"b":153}]}],"statement":"SELECT permissions.userId, users.email, permissions.level\nFROM permissions\nLEFT JOIN users ON users.id = permissions.userId\nWHERE permissions.ref = :refId\nORDER BY permissions.userId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT permissions.userId, users.email, permissions.level
+ * FROM permissions
+ * LEFT JOIN users ON users.id = permissions.userId
+ * WHERE permissions.ref = :refId
+ * ORDER BY permissions.userId
+ * ```
+ */
+export const getPermissions = new PreparedQuery<IGetPermissionsParams,IGetPermissionsResult>(getPermissionsIR);
+
+
+/** 'NewOwnedRef' parameters type */
+export interface INewOwnedRefParams {
+  owner?: string | null | void;
+  title?: string | null | void;
+}
+
+/** 'NewOwnedRef' return type */
+export interface INewOwnedRefResult {
+  id: string;
+}
+
+/** 'NewOwnedRef' query type */
+export interface INewOwnedRefQuery {
+  params: INewOwnedRefParams;
+  result: INewOwnedRefResult;
+}
+
+const newOwnedRefIR: any = {"usedParamSet":{"title":true,"owner":true},"params":[{"name":"title","required":false,"transform":{"type":"scalar"},"locs":[{"a":90,"b":95}]},{"name":"owner","required":false,"transform":{"type":"scalar"},"locs":[{"a":199,"b":204}]}],"statement":"WITH ref AS (\n    INSERT INTO refs(id, title, lastUpdated)\n    VALUES (gen_random_uuid(), :title, NOW())\n    RETURNING id\n), owner AS (\n    INSERT INTO permissions(ref, userId, level)\n    SELECT id, :owner, 'owner' FROM ref\n)\nSELECT id FROM ref"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * WITH ref AS (
+ *     INSERT INTO refs(id, title, lastUpdated)
+ *     VALUES (gen_random_uuid(), :title, NOW())
+ *     RETURNING id
+ * ), owner AS (
+ *     INSERT INTO permissions(ref, userId, level)
+ *     SELECT id, :owner, 'owner' FROM ref
+ * )
+ * SELECT id FROM ref
+ * ```
+ */
+export const newOwnedRef = new PreparedQuery<INewOwnedRefParams,INewOwnedRefResult>(newOwnedRefIR);
+
+
+/** 'ListRefs' parameters type */
+export interface IListRefsParams {
+  docType?: string | null | void;
+  limit?: number | null | void;
+  offset?: number | null | void;
+  owner?: string | null | void;
+  userId?: string | null | void;
+}
+
+/** 'ListRefs' return type */
+export interface IListRefsResult {
+  id: string;
+  lastupdated: Date;
+  title: string | null;
+  type: string | null;
+}
+
+/** 'ListRefs' query type */
+export interface IListRefsQuery {
+  params: IListRefsParams;
+  result: IListRefsResult;
+}
+
+const listRefsIR: any = {"usedParamSet":{"userId":true,"owner":true,"docType":true,"limit":true,"offset":true},"params":[{"name":"userId","required":false,"transform":{"type":"scalar"},"locs":[{"a":221,"b":227}]},{"name":"owner","required":false,"transform":{"type":"scalar"},"locs":[{"a":240,"b":245},{"a":348,"b":353}]},{"name":"docType","required":false,"transform":{"type":"scalar"},"locs":[{"a":391,"b":398},{"a":432,"b":439}]},{"name":"limit","required":false,"transform":{"type":"scalar"},"locs":[{"a":488,"b":493}]},{"name":"offset","required":false,"transform":{"type":"scalar"},"locs":[{"a":507,"b":513}]}],"statement":"SELECT refs.id, refs.title, refs.docType AS type, refs.lastUpdated\nFROM refs\nWHERE (NOT EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id)\n        OR EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id AND userId = :userId))\n    AND (:owner::TEXT IS NULL OR EXISTS (\n        SELECT 1 FROM permissions\n        WHERE ref = refs.id AND userId = :owner AND level = 'owner'\n    ))\n    AND (:docType::TEXT IS NULL OR refs.docType = :docType)\nORDER BY refs.lastUpdated DESC, refs.id\nLIMIT :limit::INT OFFSET :offset::INT"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT refs.id, refs.title, refs.docType AS type, refs.lastUpdated
+ * FROM refs
+ * WHERE (NOT EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id)
+ *         OR EXISTS (SELECT 1 FROM permissions WHERE ref = refs.id AND userId = :userId))
+ *     AND (:owner::TEXT IS NULL OR EXISTS (
+ *         SELECT 1 FROM permissions
+ *         WHERE ref = refs.id AND userId = :owner AND level = 'owner'
+ *     ))
+ *     AND (:docType::TEXT IS NULL OR refs.docType = :docType)
+ * ORDER BY refs.lastUpdated DESC, refs.id
+ * LIMIT :limit::INT OFFSET :offset::INT
+ * ```
+ */
+export const listRefs = new PreparedQuery<IListRefsParams,IListRefsResult>(listRefsIR);
+
+
+/** 'RecordConflict' parameters type */
+export interface IRecordConflictParams {
+  content?: string | null | void;
+  refId?: string | null | void;
+}
+
+/** 'RecordConflict' return type */
+export type IRecordConflictResult = void;
+
+/** 'RecordConflict' query type */
+export interface IRecordConflictQuery {
+  params: IRecordConflictParams;
+  result: IRecordConflictResult;
+}
+
+const recordConflictIR: any = {"usedParamSet":{"refId":true,"content":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":56,"b":61}]},{"name":"content","required":false,"transform":{"type":"scalar"},"locs":[{"a":64,"b":71}]}],"statement":"INSERT INTO conflicts(ref, content, detectedAt)\nVALUES (:refId, :content, NOW())\nON CONFLICT (ref) DO UPDATE SET content = EXCLUDED.content"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO conflicts(ref, content, detectedAt)
+ * VALUES (:refId, :content, NOW())
+ * ON CONFLICT (ref) DO UPDATE SET content = EXCLUDED.content
+ * ```
+ */
+export const recordConflict = new PreparedQuery<IRecordConflictParams,IRecordConflictResult>(recordConflictIR);
+
+
+/** 'DeleteConflict' parameters type */
+export interface IDeleteConflictParams {
+  refId?: string | null | void;
+}
+
+/** 'DeleteConflict' return type */
+export type IDeleteConflictResult = void;
+
+/** 'DeleteConflict' query type */
+export interface IDeleteConflictQuery {
+  params: IDeleteConflictParams;
+  result: IDeleteConflictResult;
+}
+
+const deleteConflictIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":34,"b":39}]}],"statement":"DELETE FROM conflicts WHERE ref = :refId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * DELETE FROM conflicts WHERE ref = :refId
+ * ```
+ */
+export const deleteConflict = new PreparedQuery<IDeleteConflictParams,IDeleteConflictResult>(deleteConflictIR);
+
+
+/** 'GetConflict' parameters type */
+export interface IGetConflictParams {
+  refId?: string | null | void;
+}
+
+/** 'GetConflict' return type */
+export interface IGetConflictResult {
+  content: string;
+  detectedat: Date;
+}
+
+/** 'GetConflict' query type */
+export interface IGetConflictQuery {
+  params: IGetConflictParams;
+  result: IGetConflictResult;
+}
+
+const getConflictIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":54,"b":59}]}],"statement":"SELECT content, detectedAt FROM conflicts WHERE ref = :refId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT content, detectedAt FROM conflicts WHERE ref = :refId
+ * ```
+ */
+export const getConflict = new PreparedQuery<IGetConflictParams,IGetConflictResult>(getConflictIR);
+
+
+/** 'GetConflicts' parameters type */
+export type IGetConflictsParams = void;
+
+/** 'GetConflicts' return type */
+export interface IGetConflictsResult {
+  detectedat: Date;
+  ref: string;
+}
+
+/** 'GetConflicts' query type */
+export interface IGetConflictsQuery {
+  params: IGetConflictsParams;
+  result: IGetConflictsResult;
+}
+
+const getConflictsIR: any = {"usedParamSet":{},"params":[],"statement":"SELECT ref, detectedAt FROM conflicts ORDER BY detectedAt"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT ref, detectedAt FROM conflicts ORDER BY detectedAt
+ * ```
+ */
+export const getConflicts = new PreparedQuery<IGetConflictsParams,IGetConflictsResult>(getConflictsIR);
+
+
+/** 'SaveLiveDocument' parameters type */
+export interface ISaveLiveDocumentParams {
+  content?: string | null | void;
+  data?: Buffer | null | void;
+  documentId?: string | null | void;
+  refId?: string | null | void;
+}
+
+/** 'SaveLiveDocument' return type */
+export type ISaveLiveDocumentResult = void;
+
+/** 'SaveLiveDocument' query type */
+export interface ISaveLiveDocumentQuery {
+  params: ISaveLiveDocumentParams;
+  result: ISaveLiveDocumentResult;
+}
+
+const saveLiveDocumentIR: any = {"usedParamSet":{"refId":true,"documentId":true,"data":true,"content":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":76,"b":81}]},{"name":"documentId","required":false,"transform":{"type":"scalar"},"locs":[{"a":84,"b":94}]},{"name":"data","required":false,"transform":{"type":"scalar"},"locs":[{"a":97,"b":101}]},{"name":"content","required":false,"transform":{"type":"scalar"},"locs":[{"a":104,"b":111}]}],"statement":"INSERT INTO live_documents(ref, documentId, data, content, savedAt)\nVALUES (:refId, :documentId, :data, :content, NOW())\nON CONFLICT (ref) DO UPDATE SET\n    documentId = EXCLUDED.documentId,\n    data = EXCLUDED.data,\n    content = EXCLUDED.content,\n    savedAt = EXCLUDED.savedAt"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * INSERT INTO live_documents(ref, documentId, data, content, savedAt)
+ * VALUES (:refId, :documentId, :data, :content, NOW())
+ * ON CONFLICT (ref) DO UPDATE SET
+ *     documentId = EXCLUDED.documentId,
+ *     data = EXCLUDED.data,
+ *     content = EXCLUDED.content,
+ *     savedAt = EXCLUDED.savedAt
+ * ```
+ */
+export const saveLiveDocument = new PreparedQuery<ISaveLiveDocumentParams,ISaveLiveDocumentResult>(saveLiveDocumentIR);
+
+
+/** 'GetLiveDocument' parameters type */
+export interface IGetLiveDocumentParams {
+  refId?: string | null | void;
+}
+
+/** 'GetLiveDocument' return type */
+export interface IGetLiveDocumentResult {
+  content: string;
+  data: Buffer;
+  documentid: string;
+}
+
+/** 'GetLiveDocument' query type */
+export interface IGetLiveDocumentQuery {
+  params: IGetLiveDocumentParams;
+  result: IGetLiveDocumentResult;
+}
+
+const getLiveDocumentIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":65,"b":70}]}],"statement":"SELECT documentId, data, content FROM live_documents WHERE ref = :refId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT documentId, data, content FROM live_documents WHERE ref = :refId
+ * ```
+ */
+export const getLiveDocument = new PreparedQuery<IGetLiveDocumentParams,IGetLiveDocumentResult>(getLiveDocumentIR);
+
+
+/** 'GetLiveDocumentIds' parameters type */
+export type IGetLiveDocumentIdsParams = void;
+
+/** 'GetLiveDocumentIds' return type */
+export interface IGetLiveDocumentIdsResult {
+  documentid: string;
+  ref: string;
+}
+
+/** 'GetLiveDocumentIds' query type */
+export interface IGetLiveDocumentIdsQuery {
+  params: IGetLiveDocumentIdsParams;
+  result: IGetLiveDocumentIdsResult;
+}
+
+const getLiveDocumentIdsIR: any = {"usedParamSet":{},"params":[],"statement":"SELECT ref, documentId FROM live_documents"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * SELECT ref, documentId FROM live_documents
+ * ```
+ */
+export const getLiveDocumentIds = new PreparedQuery<IGetLiveDocumentIdsParams,IGetLiveDocumentIdsResult>(getLiveDocumentIdsIR);
+
+
+/** 'DeleteLiveDocument' parameters type */
+export interface IDeleteLiveDocumentParams {
+  refId?: string | null | void;
+}
+
+/** 'DeleteLiveDocument' return type */
+export type IDeleteLiveDocumentResult = void;
+
+/** 'DeleteLiveDocument' query type */
+export interface IDeleteLiveDocumentQuery {
+  params: IDeleteLiveDocumentParams;
+  result: IDeleteLiveDocumentResult;
+}
+
+const deleteLiveDocumentIR: any = {"usedParamSet":{"refId":true},"params":[{"name":"refId","required":false,"transform":{"type":"scalar"},"locs":[{"a":39,"b":44}]}],"statement":"DELETE FROM live_documents WHERE ref = :refId"};
+
+/**
+ * Query generated from SQL:
+ * ```
+ * DELETE FROM live_documents WHERE ref = :refId
+ * ```
+ */
+export const deleteLiveDocument = new PreparedQuery<IDeleteLiveDocumentParams,IDeleteLiveDocumentResult>(deleteLiveDocumentIR);
+
+
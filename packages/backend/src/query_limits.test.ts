@@ -0,0 +1,79 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import type pg from "pg";
+import {
+    QueryCanceledError,
+    currentQueryLimits,
+    limitClient,
+    statementTimeouts,
+    withQueryLimits,
+    withoutQueryLimits,
+} from "./query_limits.js";
+
+/** Pool whose connections record the statements run on them. */
+function recordingPool() {
+    const statements: string[] = [];
+    const releases: unknown[] = [];
+    const client = {
+        processID: 42,
+        query: async (text: string, values?: unknown[]) => {
+            statements.push(values ? `${text} ${values.join(",")}` : text);
+            return { rows: [] };
+        },
+        release: (err?: unknown) => {
+            releases.push(err);
+        },
+    } as unknown as pg.PoolClient;
+    const pool = {
+        query: async (text: string, values?: unknown[]) => {
+            statements.push(`pool: ${text} ${values?.join(",")}`);
+            return { rows: [] };
+        },
+        connect: async () => client,
+    } as unknown as pg.Pool;
+    return { pool, client, statements, releases };
+}
+
+test("Query limits", async (_t) => {
+    await it("applies limits within their scope only", async () => {
+        assert.strictEqual(currentQueryLimits(), undefined);
+        const limits = { timeoutMs: statementTimeouts.interactive + 1 };
+        await withQueryLimits(limits, async () => {
+            await Promise.resolve();
+            assert.strictEqual(currentQueryLimits(), limits);
+            withoutQueryLimits(() => assert.strictEqual(currentQueryLimits(), undefined));
+        });
+        assert.strictEqual(currentQueryLimits(), undefined);
+    });
+
+    await it("ignores limits that match the defaults", () => {
+        const limits = { timeoutMs: statementTimeouts.interactive };
+        withQueryLimits(limits, () => assert.strictEqual(currentQueryLimits(), undefined));
+    });
+
+    await it("sets the statement timeout until the client is released", async () => {
+        const { pool, client, statements, releases } = recordingPool();
+        await limitClient(pool, client, { timeoutMs: 30_000 });
+        assert.deepStrictEqual(statements, [
+            "SELECT set_config('statement_timeout', $1, false) 30000",
+        ]);
+        client.release();
+        await new Promise((resolve) => setImmediate(resolve));
+        assert.strictEqual(statements[1], "RESET statement_timeout");
+        assert.deepStrictEqual(releases, [undefined]);
+    });
+
+    await it("cancels statements when the request is abandoned", async () => {
+        const { pool, client, statements, releases } = recordingPool();
+        const controller = new AbortController();
+        const limits = { timeoutMs: statementTimeouts.interactive, signal: controller.signal };
+        await limitClient(pool, client, limits);
+        assert.deepStrictEqual(statements, []);
+        controller.abort();
+        assert.deepStrictEqual(statements, ["pool: SELECT pg_cancel_backend($1) 42"]);
+        client.release();
+        assert.deepStrictEqual(releases, [true]);
+
+        await assert.rejects(limitClient(pool, client, limits), QueryCanceledError);
+    });
+});
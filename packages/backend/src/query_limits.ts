@@ -0,0 +1,138 @@
+import { AsyncLocalStorage } from "node:async_hooks";
+import type pg from "pg";
+
+/** Class of work, which determines how long its SQL statements may run.
+
+- "interactive": ordinary calls made while editing, the default
+- "report": statistics, history, and diffs over a ref
+- "bulk": imports and saves of several documents at once
+ */
+export type StatementClass = "interactive" | "report" | "bulk";
+
+/** Statement timeouts by class of work, in milliseconds. Zero means no timeout. */
+export type StatementTimeouts = Record<StatementClass, number>;
+
+export const defaultStatementTimeouts: StatementTimeouts = {
+    interactive: 5_000,
+    report: 30_000,
+    bulk: 120_000,
+};
+
+/** Read statement timeouts from the `STATEMENT_TIMEOUT_<CLASS>_MS` environment
+variables, falling back to the defaults.
+ */
+export function statementTimeoutsFromEnv(): StatementTimeouts {
+    const timeouts = { ...defaultStatementTimeouts };
+    for (const cls of Object.keys(timeouts) as StatementClass[]) {
+        const value = process.env[`STATEMENT_TIMEOUT_${cls.toUpperCase()}_MS`];
+        if (value) {
+            timeouts[cls] = Number(value);
+        }
+    }
+    return timeouts;
+}
+
+/** Statement timeouts configured for this process.
+
+The interactive timeout is also the default of every connection in the pool, so
+it applies to work done outside of any request, such as background jobs.
+ */
+export const statementTimeouts = statementTimeoutsFromEnv();
+
+/** Limits on the SQL statements run on behalf of a request. */
+export type QueryLimits = {
+    timeoutMs: number;
+
+    /** Signal that the request was abandoned, aborting its statements in flight. */
+    signal?: AbortSignal;
+};
+
+/** Error raised for a statement that was not run because its request was abandoned.
+
+It has the SQLSTATE code of a canceled statement, so that it is reported in the
+same way as one canceled while running.
+ */
+export class QueryCanceledError extends Error {
+    readonly code = "57014";
+}
+
+const storage = new AsyncLocalStorage<QueryLimits>();
+
+/** Run a function with limits on the statements that it runs, including those run
+asynchronously on its behalf.
+ */
+export function withQueryLimits<T>(limits: QueryLimits, f: () => T): T {
+    return storage.run(limits, f);
+}
+
+/** Run a function free of the limits of the request that started it.
+
+Used for work that outlives the request, such as autosaving a live document.
+ */
+export function withoutQueryLimits<T>(f: () => T): T {
+    return storage.exit(f);
+}
+
+/** Limits that apply to statements run now, if they differ from the defaults. */
+export function currentQueryLimits(): QueryLimits | undefined {
+    const limits = storage.getStore();
+    if (!limits || (limits.timeoutMs === statementTimeouts.interactive && !limits.signal)) {
+        return undefined;
+    }
+    return limits;
+}
+
+/** Apply limits to a client checked out from a pool, until it is released.
+
+The statement timeout is changed for the session and reset on release. When the
+signal aborts, the statement in flight is canceled from another connection and
+the client is destroyed on release, so that the cancellation cannot reach a
+statement of a later user of the connection.
+ */
+export async function limitClient(
+    pool: pg.Pool,
+    client: pg.PoolClient,
+    limits: QueryLimits,
+): Promise<void> {
+    const { timeoutMs, signal } = limits;
+    if (signal?.aborted) {
+        client.release();
+        throw new QueryCanceledError("Request was abandoned");
+    }
+    const changeTimeout = timeoutMs !== statementTimeouts.interactive;
+    try {
+        if (changeTimeout) {
+            await client.query("SELECT set_config('statement_timeout', $1, false)", [
+                String(timeoutMs),
+            ]);
+        }
+    } catch (e) {
+        client.release(true);
+        throw e;
+    }
+
+    // Set by `pg` when the connection is established, but missing from its types.
+    const { processID } = client as unknown as { processID: number };
+    let canceled = false;
+    const cancel = () => {
+        canceled = true;
+        pool.query("SELECT pg_cancel_backend($1)", [processID]).catch((e) =>
+            console.error("Failed to cancel database statement:", e),
+        );
+    };
+    signal?.addEventListener("abort", cancel, { once: true });
+
+    const release = client.release.bind(client);
+    client.release = (err?: Error | boolean) => {
+        signal?.removeEventListener("abort", cancel);
+        client.release = release;
+        if (err || canceled || !changeTimeout) {
+            release(err || canceled);
+            return;
+        }
+        client.query("RESET statement_timeout").then(
+            () => release(),
+            (e: Error) => release(e),
+        );
+    };
+}
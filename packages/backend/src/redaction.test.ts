@@ -0,0 +1,42 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { REDACTED, Redaction, redactJson } from "./redaction.js";
+
+const notebook = {
+    type: "model",
+    name: "Model",
+    apiKey: "sk-12345678",
+    notebook: {
+        cells: [
+            { tag: "rich-text", id: "c1", content: "Token sk-12345678 for the solver" },
+            { tag: "rich-text", id: "c2", content: "Private notes" },
+        ],
+    },
+};
+
+test("Redaction", async (_t) => {
+    await it("removes fields by JSON Pointer", () => {
+        const redaction = Redaction.parse({ paths: ["/apiKey", "/missing/x"], reason: "key" });
+        const { apiKey: _, ...rest } = notebook;
+        assert.deepStrictEqual(redactJson(notebook, redaction), rest);
+    });
+
+    await it("removes cells by ID wherever they are", () => {
+        const redaction = Redaction.parse({ ids: ["c2"], reason: "notes" });
+        const redacted = redactJson(notebook, redaction) as typeof notebook;
+        assert.deepStrictEqual(redacted.notebook.cells.map((cell) => cell.id), ["c1"]);
+    });
+
+    await it("replaces strings throughout the content", () => {
+        const redaction = Redaction.parse({ strings: ["sk-12345678"], reason: "secret" });
+        const redacted = redactJson(notebook, redaction) as typeof notebook;
+        assert.strictEqual(redacted.apiKey, REDACTED);
+        assert.strictEqual(redacted.notebook.cells[0]?.content, `Token ${REDACTED} for the solver`);
+        assert.ok(!JSON.stringify(redacted).includes("sk-12345678"));
+    });
+
+    await it("requires something to redact", () => {
+        assert.ok(!Redaction.safeParse({ reason: "nothing" }).success);
+        assert.ok(!Redaction.safeParse({ paths: [""], reason: "whole document" }).success);
+    });
+});
@@ -0,0 +1,88 @@
+import { z } from "zod";
+
+/** Text that replaces each redacted string. */
+export const REDACTED = "[redacted]";
+
+/** Content to remove from the history of a ref.
+
+Snapshots differ in shape over time, so content is identified in ways that do
+not depend on where it sits in any one snapshot.
+ */
+export const Redaction = z
+    .object({
+        /// JSON Pointers to fields to remove, in every snapshot that has them
+        paths: z.array(z.string().regex(/^\/.*/)).default([]),
+        /// IDs of notebook cells or other identified objects to remove from arrays
+        ids: z.array(z.string().min(1)).default([]),
+        /// Strings to replace wherever they occur in text, such as a pasted secret
+        strings: z.array(z.string().min(4)).default([]),
+        /// Why the content was redacted, recorded in the audit log
+        reason: z.string().min(1),
+    })
+    .refine((r) => r.paths.length + r.ids.length + r.strings.length > 0, {
+        message: "Nothing to redact",
+    });
+
+export type Redaction = z.infer<typeof Redaction>;
+
+/** Apply a redaction to a JSON value, returning a redacted copy. */
+export function redactJson(value: unknown, redaction: Redaction): unknown {
+    let result = redactValue(value, redaction);
+    for (const path of redaction.paths) {
+        result = removePointer(result, parsePointer(path));
+    }
+    return result;
+}
+
+function redactValue(value: unknown, redaction: Redaction): unknown {
+    if (typeof value === "string") {
+        return redaction.strings.reduce((text, s) => text.replaceAll(s, REDACTED), value);
+    }
+    if (Array.isArray(value)) {
+        return value
+            .filter((x) => !(isObject(x) && redaction.ids.includes(x.id as string)))
+            .map((x) => redactValue(x, redaction));
+    }
+    if (isObject(value)) {
+        return Object.fromEntries(
+            Object.entries(value).map(([key, x]) => [key, redactValue(x, redaction)]),
+        );
+    }
+    return value;
+}
+
+function removePointer(value: unknown, tokens: string[]): unknown {
+    const [token, ...rest] = tokens;
+    if (token === undefined) {
+        return value;
+    }
+    if (Array.isArray(value)) {
+        const index = Number(token);
+        if (!/^(0|[1-9][0-9]*)$/.test(token) || index >= value.length) {
+            return value;
+        }
+        return rest.length === 0
+            ? value.filter((_, i) => i !== index)
+            : value.map((x, i) => (i === index ? removePointer(x, rest) : x));
+    }
+    if (isObject(value) && Object.hasOwn(value, token)) {
+        if (rest.length === 0) {
+            const { [token]: _, ...remaining } = value;
+            return remaining;
+        }
+        return { ...value, [token]: removePointer(value[token], rest) };
+    }
+    return value;
+}
+
+/** Split a JSON Pointer into its unescaped tokens, as in RFC 6901. */
+function parsePointer(path: string): string[] {
+    return path
+        .split("/")
+        .slice(1)
+        .map((token) => token.replaceAll("~1", "/").replaceAll("~0", "~"));
+}
+
+function isObject(x: unknown): x is Record<string, unknown> {
+    return typeof x === "object" && x !== null && !Array.isArray(x);
+}
@@ -0,0 +1,47 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import {
+    RemoteFetchError,
+    assertPublicUrl,
+    fetchRemoteJson,
+    lookupPublic,
+} from "./remote_fetch.js";
+
+test("Remote fetch guards", async (_t) => {
+    await it("rejects non-HTTP protocols", async () => {
+        await assert.rejects(assertPublicUrl(new URL("file:///etc/passwd")), RemoteFetchError);
+    });
+
+    await it("rejects private and loopback addresses", async () => {
+        for (const url of [
+            "http://127.0.0.1/",
+            "http://10.1.2.3/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::1]/",
+            "http://[::ffff:192.168.0.1]/",
+            "http://[fd00::1]/",
+            "http://[64:ff9b::a9fe:a9fe]/",
+            "http://[64:ff9b:1::a9fe:a9fe]/",
+            "http://[::7f00:1]/",
+            "http://[2002:a9fe:a9fe::]/",
+        ]) {
+            await assert.rejects(assertPublicUrl(new URL(url)), RemoteFetchError, url);
+        }
+    });
+
+    await it("accepts public addresses", async () => {
+        await assertPublicUrl(new URL("https://1.1.1.1/doc.json"));
+        await assertPublicUrl(new URL("https://[2606:4700::1111]/doc.json"));
+    });
+
+    await it("refuses to connect to hosts that resolve to private addresses", async () => {
+        const error = await new Promise((resolve) =>
+            lookupPublic("localhost", {}, (err) => resolve(err)),
+        );
+        assert.ok(error instanceof RemoteFetchError);
+    });
+
+    await it("rejects malformed URLs before fetching", async () => {
+        await assert.rejects(fetchRemoteJson("not a url"), RemoteFetchError);
+    });
+});
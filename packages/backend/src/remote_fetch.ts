@@ -0,0 +1,182 @@
+import * as dns from "node:dns";
+import * as net from "node:net";
+import { Agent, type Response, fetch } from "undici";
+
+/** Options for fetching a resource from a user-supplied URL. */
+export type RemoteFetchOptions = {
+    /** Maximum size of the response body, in bytes. */
+    maxBytes: number;
+
+    /** Time allowed for the whole request, in milliseconds. */
+    timeoutMs: number;
+
+    /** Maximum number of redirects to follow. */
+    maxRedirects: number;
+};
+
+export const defaultRemoteFetchOptions: RemoteFetchOptions = {
+    maxBytes: 5 * 1024 * 1024,
+    timeoutMs: 10_000,
+    maxRedirects: 3,
+};
+
+/** Error raised when a remote resource cannot or may not be fetched. */
+export class RemoteFetchError extends Error {}
+
+/** Addresses that a user-supplied URL is never allowed to reach.
+
+This covers loopback, private, link-local (including cloud metadata services),
+carrier-grade NAT, and other non-public ranges, for both IPv4 and IPv6. IPv4-mapped
+IPv6 addresses are matched against the IPv4 rules by `BlockList` itself, but IPv6
+ranges that embed IPv4 addresses otherwise, such as NAT64 and 6to4, are blocked
+outright, since they can reach any of the ranges above.
+ */
+const blockedAddresses = new net.BlockList();
+blockedAddresses.addSubnet("0.0.0.0", 8, "ipv4");
+blockedAddresses.addSubnet("10.0.0.0", 8, "ipv4");
+blockedAddresses.addSubnet("100.64.0.0", 10, "ipv4");
+blockedAddresses.addSubnet("127.0.0.0", 8, "ipv4");
+blockedAddresses.addSubnet("169.254.0.0", 16, "ipv4");
+blockedAddresses.addSubnet("172.16.0.0", 12, "ipv4");
+blockedAddresses.addSubnet("192.168.0.0", 16, "ipv4");
+blockedAddresses.addSubnet("224.0.0.0", 3, "ipv4");
+blockedAddresses.addSubnet("::", 96, "ipv6");
+blockedAddresses.addSubnet("64:ff9b::", 96, "ipv6");
+blockedAddresses.addSubnet("64:ff9b:1::", 48, "ipv6");
+blockedAddresses.addSubnet("2002::", 16, "ipv6");
+blockedAddresses.addSubnet("fc00::", 7, "ipv6");
+blockedAddresses.addSubnet("fe80::", 10, "ipv6");
+blockedAddresses.addSubnet("ff00::", 8, "ipv6");
+
+function isBlocked(address: string, family: number): boolean {
+    return blockedAddresses.check(address, family === 6 ? "ipv6" : "ipv4");
+}
+
+/** Resolve a host like `dns.lookup`, failing if any of its addresses is not public.
+
+Remote requests connect through this lookup, so the address that was checked is
+the one connected to, and a host cannot rebind to a private address in between.
+ */
+export const lookupPublic: net.LookupFunction = (hostname, options, callback) => {
+    dns.lookup(hostname, { ...options, all: true }, (err, addresses) => {
+        if (err) {
+            callback(err, "");
+            return;
+        }
+        const [first] = addresses;
+        if (!first || addresses.some(({ address, family }) => isBlocked(address, family))) {
+            const message = `Host resolves to a non-public address: ${hostname}`;
+            callback(new RemoteFetchError(message), "");
+        } else if (options.all) {
+            callback(null, addresses);
+        } else {
+            callback(null, first.address, first.family);
+        }
+    });
+};
+
+/** Dispatcher for remote requests, which may only connect to public addresses. */
+const publicAgent = new Agent({ connect: { lookup: lookupPublic } });
+
+/** Check that a URL is safe to fetch on behalf of a client.
+
+Only HTTP(S) URLs are accepted, and every address that the host resolves to
+must be public. The check fails early for clearly unsafe URLs, but it is
+`lookupPublic` that guards the connection itself.
+ */
+export async function assertPublicUrl(url: URL): Promise<void> {
+    if (url.protocol !== "http:" && url.protocol !== "https:") {
+        throw new RemoteFetchError(`Unsupported protocol: ${url.protocol}`);
+    }
+    if (url.username || url.password) {
+        throw new RemoteFetchError("URLs with credentials are not allowed");
+    }
+    const host = url.hostname.replace(/^\[(.*)\]$/, "$1");
+    const addresses = net.isIP(host)
+        ? [{ address: host, family: net.isIP(host) }]
+        : await dns.promises.lookup(host, { all: true }).catch(() => {
+              throw new RemoteFetchError(`Could not resolve host: ${host}`);
+          });
+    for (const { address, family } of addresses) {
+        if (isBlocked(address, family)) {
+            throw new RemoteFetchError(`Host resolves to a non-public address: ${host}`);
+        }
+    }
+}
+
+/** Fetch and parse a JSON resource from a user-supplied URL.
+
+Redirects are followed manually so that every hop is checked by
+`assertPublicUrl`, and the body is read incrementally so that oversized
+responses are aborted as soon as they exceed the limit.
+ */
+export async function fetchRemoteJson(
+    input: string,
+    options: RemoteFetchOptions = defaultRemoteFetchOptions,
+): Promise<unknown> {
+    let url: URL;
+    try {
+        url = new URL(input);
+    } catch {
+        throw new RemoteFetchError(`Invalid URL: ${input}`);
+    }
+    const signal = AbortSignal.timeout(options.timeoutMs);
+
+    const fetchHop = async (hop: URL) => {
+        await assertPublicUrl(hop);
+        return await fetch(hop, {
+            dispatcher: publicAgent,
+            headers: { accept: "application/json" },
+            redirect: "manual",
+            signal,
+        }).catch((e) => {
+            if (e?.cause instanceof RemoteFetchError) {
+                throw e.cause;
+            }
+            throw new RemoteFetchError(`Request to ${hop} failed: ${e}`);
+        });
+    };
+
+    let response = await fetchHop(url);
+    for (let redirects = 0; isRedirect(response); redirects++) {
+        if (redirects >= options.maxRedirects) {
+            throw new RemoteFetchError("Too many redirects");
+        }
+        url = new URL(response.headers.get("location") as string, url);
+        response = await fetchHop(url);
+    }
+
+    if (!response.ok) {
+        throw new RemoteFetchError(`Request to ${url} failed with status ${response.status}`);
+    }
+    const declaredLength = Number(response.headers.get("content-length"));
+    if (declaredLength > options.maxBytes) {
+        throw new RemoteFetchError(`Response exceeds limit of ${options.maxBytes} bytes`);
+    }
+
+    const chunks: Uint8Array[] = [];
+    let size = 0;
+    const reader = response.body?.getReader();
+    while (reader) {
+        const { done, value } = await reader.read();
+        if (done) {
+            break;
+        }
+        size += value.byteLength;
+        if (size > options.maxBytes) {
+            await reader.cancel();
+            throw new RemoteFetchError(`Response exceeds limit of ${options.maxBytes} bytes`);
+        }
+        chunks.push(value);
+    }
+
+    try {
+        return JSON.parse(Buffer.concat(chunks).toString("utf-8"));
+    } catch {
+        throw new RemoteFetchError(`Response from ${url} is not valid JSON`);
+    }
+}
+
+function isRedirect(response: Response): boolean {
+    return response.status >= 300 && response.status < 400 && response.headers.has("location");
+}
@@ -0,0 +1,88 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import type pg from "pg";
+import {
+    CircuitBreaker,
+    DatabaseUnavailableError,
+    ResilientPool,
+    isConnectionError,
+    isRetryable,
+} from "./resilience.js";
+
+function pgError(code: string): Error {
+    return Object.assign(new Error(`error ${code}`), { code });
+}
+
+/** Pool whose queries fail with the given errors, then succeed. */
+function flakyPool(errors: Error[]) {
+    let calls = 0;
+    const pool = {
+        query: async () => {
+            const error = errors[calls++];
+            if (error) {
+                throw error;
+            }
+            return { rows: [] };
+        },
+    } as unknown as pg.Pool;
+    return { pool, calls: () => calls };
+}
+
+const fastPolicy = { maxAttempts: 3, baseDelayMs: 1, maxDelayMs: 1 };
+
+test("Database resilience", async (_t) => {
+    await it("only retries errors where the statement did not run", () => {
+        assert.ok(isRetryable(pgError("ECONNREFUSED")));
+        assert.ok(isRetryable(pgError("57P03")));
+        assert.ok(isRetryable(pgError("40001")));
+        assert.ok(!isRetryable(pgError("ECONNRESET")));
+        assert.ok(!isRetryable(pgError("23505")));
+        assert.ok(isConnectionError(pgError("ECONNRESET")));
+        assert.ok(!isConnectionError(pgError("23505")));
+        assert.ok(!isConnectionError(pgError("40P01")));
+    });
+
+    await it("retries transient failures", async () => {
+        const { pool, calls } = flakyPool([pgError("ECONNREFUSED"), pgError("57P03")]);
+        const conn = new ResilientPool(pool, fastPolicy);
+        await conn.query("SELECT 1");
+        assert.strictEqual(calls(), 3);
+        assert.strictEqual(conn.breaker.state, "closed");
+    });
+
+    await it("does not retry other failures", async () => {
+        const { pool, calls } = flakyPool([pgError("ECONNRESET")]);
+        const conn = new ResilientPool(pool, fastPolicy);
+        await assert.rejects(conn.query("SELECT 1"), { code: "ECONNRESET" });
+        assert.strictEqual(calls(), 1);
+    });
+
+    await it("opens the circuit after repeated failures", async () => {
+        const refused = Array.from({ length: 3 }, () => pgError("ECONNREFUSED"));
+        const { pool, calls } = flakyPool(refused);
+        const conn = new ResilientPool(pool, fastPolicy, new CircuitBreaker(3, 60_000));
+        await assert.rejects(conn.query("SELECT 1"), { code: "ECONNREFUSED" });
+        assert.strictEqual(conn.breaker.state, "open");
+        await assert.rejects(conn.query("SELECT 1"), DatabaseUnavailableError);
+        assert.strictEqual(calls(), 3);
+    });
+
+    await it("retries deadlocks without opening the circuit", async () => {
+        const deadlocks = Array.from({ length: 6 }, () => pgError("40P01"));
+        const { pool, calls } = flakyPool(deadlocks);
+        const conn = new ResilientPool(pool, fastPolicy, new CircuitBreaker(3, 60_000));
+        await assert.rejects(conn.query("SELECT 1"), { code: "40P01" });
+        await assert.rejects(conn.query("SELECT 1"), { code: "40P01" });
+        assert.strictEqual(calls(), 6);
+        assert.strictEqual(conn.breaker.state, "closed");
+    });
+
+    await it("closes the circuit after a success once the cooldown has passed", async () => {
+        const breaker = new CircuitBreaker(1, 0);
+        breaker.failure(pgError("ECONNREFUSED"));
+        assert.strictEqual(breaker.state, "half-open");
+        const conn = new ResilientPool(flakyPool([]).pool, fastPolicy, breaker);
+        await conn.query("SELECT 1");
+        assert.strictEqual(breaker.state, "closed");
+    });
+});
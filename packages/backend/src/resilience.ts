@@ -0,0 +1,218 @@
+import type pg from "pg";
+
+import { type QueryLimits, currentQueryLimits, limitClient } from "./query_limits.js";
+
+/** How database operations are retried after transient failures. */
+export type RetryPolicy = {
+    /** Total number of attempts, including the first. */
+    maxAttempts: number;
+
+    /** Delay before the first retry, doubling with each attempt. */
+    baseDelayMs: number;
+
+    /** Upper bound on the delay between attempts. */
+    maxDelayMs: number;
+};
+
+export const defaultRetryPolicy: RetryPolicy = {
+    maxAttempts: 5,
+    baseDelayMs: 100,
+    maxDelayMs: 5000,
+};
+
+/** Error raised without contacting the database while the circuit is open. */
+export class DatabaseUnavailableError extends Error {}
+
+/** SQLSTATE codes of errors after which the statement is known not to have taken
+effect, so that it is safe to run again.
+ */
+const retryableSqlStates = new Set([
+    "08001", // sqlclient_unable_to_establish_sqlconnection
+    "08004", // sqlserver_rejected_establishment_of_sqlconnection
+    "40001", // serialization_failure
+    "40P01", // deadlock_detected
+    "53300", // too_many_connections
+    "57P03", // cannot_connect_now
+]);
+
+/** Error codes from Node for failures to open a connection. */
+const retryableNodeCodes = new Set(["ECONNREFUSED", "ENOTFOUND", "EAI_AGAIN"]);
+
+/** SQLSTATE codes and Node error codes indicating that the database is unreachable. */
+const connectionErrorCodes = new Set([
+    ...retryableNodeCodes,
+    "08000",
+    "08001",
+    "08003",
+    "08004",
+    "08006",
+    "53300",
+    "57P01",
+    "57P02",
+    "57P03",
+    "ECONNRESET",
+    "EPIPE",
+    "ETIMEDOUT",
+]);
+
+function errorCode(e: unknown): string | undefined {
+    return (e as { code?: string } | null)?.code;
+}
+
+/** Whether an operation that failed with the given error can safely be retried.
+
+Errors that may have happened after a statement was executed, such as a reset
+connection, are not retried, because the statement may have taken effect.
+ */
+export function isRetryable(e: unknown): boolean {
+    const code = errorCode(e);
+    if (code && (retryableSqlStates.has(code) || retryableNodeCodes.has(code))) {
+        return true;
+    }
+    return isConnectTimeout(e);
+}
+
+/** Whether the given error indicates that the database is unreachable.
+
+Errors that the database answered with, such as deadlocks, are not among them,
+even though some of them can be retried.
+ */
+export function isConnectionError(e: unknown): boolean {
+    const code = errorCode(e);
+    if (code && connectionErrorCodes.has(code)) {
+        return true;
+    }
+    return (
+        isConnectTimeout(e) ||
+        (e instanceof Error && e.message.startsWith("Connection terminated unexpectedly"))
+    );
+}
+
+/** Whether an error was raised by `pg.Pool` because no connection could be opened in time. */
+function isConnectTimeout(e: unknown): boolean {
+    return e instanceof Error && e.message.startsWith("timeout exceeded when trying to connect");
+}
+
+/** Circuit breaker that stops sending work to an unreachable database.
+
+After `threshold` consecutive connection failures the circuit opens, and
+operations fail immediately instead of piling up. Once `cooldownMs` has
+passed, operations are let through again, and the first success closes the
+circuit.
+ */
+export class CircuitBreaker {
+    private failures = 0;
+    private openedAt: number | null = null;
+
+    /** Message of the most recent connection failure, if the circuit is not closed. */
+    lastError: string | null = null;
+
+    constructor(
+        readonly threshold = 5,
+        readonly cooldownMs = 10_000,
+    ) {}
+
+    get state(): "closed" | "open" | "half-open" {
+        if (this.openedAt === null) {
+            return "closed";
+        }
+        return Date.now() - this.openedAt < this.cooldownMs ? "open" : "half-open";
+    }
+
+    /** Throw if the circuit is open. */
+    check() {
+        if (this.state === "open") {
+            throw new DatabaseUnavailableError(`Database unavailable: ${this.lastError}`);
+        }
+    }
+
+    success() {
+        this.failures = 0;
+        this.openedAt = null;
+        this.lastError = null;
+    }
+
+    failure(e: unknown) {
+        this.failures++;
+        this.lastError = e instanceof Error ? e.message : String(e);
+        if (this.failures >= this.threshold) {
+            this.openedAt = Date.now();
+        }
+    }
+}
+
+/** Connection pool that retries transient failures and trips a circuit breaker.
+
+It can be used wherever a `pg.Pool` is used to run queries, including by the
+queries generated by pgtyped.
+ */
+export class ResilientPool {
+    constructor(
+        public pool: pg.Pool,
+        readonly policy: RetryPolicy = defaultRetryPolicy,
+        readonly breaker: CircuitBreaker = new CircuitBreaker(),
+    ) {}
+
+    query(text: string, values?: unknown[]): Promise<pg.QueryResult> {
+        const limits = currentQueryLimits();
+        if (!limits) {
+            return this.withRetry(() => this.pool.query(text, values));
+        }
+        return this.withRetry(async () => {
+            const client = await this.checkout(limits);
+            try {
+                const result = await client.query(text, values);
+                client.release();
+                return result;
+            } catch (e) {
+                client.release(e as Error);
+                throw e;
+            }
+        });
+    }
+
+    /** Check out a client for a transaction. Statements run on it are not retried. */
+    connect(): Promise<pg.PoolClient> {
+        const limits = currentQueryLimits();
+        return this.withRetry(() => (limits ? this.checkout(limits) : this.pool.connect()));
+    }
+
+    /** Check out a client subject to the limits of the current request. */
+    private async checkout(limits: QueryLimits): Promise<pg.PoolClient> {
+        const client = await this.pool.connect();
+        await limitClient(this.pool, client, limits);
+        return client;
+    }
+
+    private async withRetry<T>(f: () => Promise<T>): Promise<T> {
+        for (let attempt = 1; ; attempt++) {
+            this.breaker.check();
+            try {
+                const result = await f();
+                this.breaker.success();
+                return result;
+            } catch (e) {
+                if (isConnectionError(e)) {
+                    this.breaker.failure(e);
+                } else {
+                    // The database answered, so it is reachable.
+                    this.breaker.success();
+                }
+                if (!isRetryable(e) || attempt >= this.policy.maxAttempts) {
+                    throw e;
+                }
+                await sleep(backoff(this.policy, attempt));
+            }
+        }
+    }
+}
+
+/** Delay before the given retry, with exponential backoff and jitter. */
+export function backoff(policy: RetryPolicy, attempt: number): number {
+    const delay = Math.min(policy.maxDelayMs, policy.baseDelayMs * 2 ** (attempt - 1));
+    return delay / 2 + (Math.random() * delay) / 2;
+}
+
+function sleep(ms: number): Promise<void> {
+    return new Promise((resolve) => setTimeout(resolve, ms));
+}
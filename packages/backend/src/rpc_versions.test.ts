@@ -0,0 +1,24 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { findDeprecation } from "./rpc_versions.js";
+
+test("RPC versions", async (_t) => {
+    const unversioned = new Set(["newRef", "saveRef"]);
+
+    await it("does not flag versioned procedures", () => {
+        assert.strictEqual(findDeprecation("/v1.newRef", unversioned), undefined);
+        assert.strictEqual(findDeprecation("/v1.newRef,v1.saveRef", unversioned), undefined);
+    });
+
+    await it("flags unversioned procedures, including in batches", () => {
+        assert.strictEqual(findDeprecation("/newRef", unversioned)?.replacement, "v1.newRef");
+        assert.strictEqual(
+            findDeprecation("/v1.newRef,saveRef", unversioned)?.replacement,
+            "v1.saveRef",
+        );
+    });
+
+    await it("ignores paths that are not procedures", () => {
+        assert.strictEqual(findDeprecation("/refs/abc/content", unversioned), undefined);
+    });
+});
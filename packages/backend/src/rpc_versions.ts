@@ -0,0 +1,59 @@
+import type express from "express";
+
+/** Versions of the RPC API served by the backend, oldest first.
+
+Each version is a namespace of the router, e.g. `v1.newRef`, so that procedure
+signatures can change without breaking frontends that were loaded before a
+deploy. When a new version is added, the previous ones should be kept until no
+deployed frontend uses them.
+ */
+export const rpcVersions = ["v1"];
+
+/** Version whose procedures are also served without a namespace, e.g. `newRef`. */
+export const legacyRpcVersion = "v1";
+
+/** Date from which unversioned procedure paths are deprecated. */
+const unversionedDeprecatedSince = new Date("2026-10-15T00:00:00Z");
+
+/** Deprecation of a procedure that a client called. */
+export type Deprecation = {
+    /** Path of the deprecated procedure. */
+    procedure: string;
+    /** When the procedure was deprecated. */
+    since: Date;
+    /** Path of the procedure that replaces it. */
+    replacement: string;
+};
+
+/** Find the first deprecated procedure called by a request to the RPC endpoint.
+
+The path of a request names one procedure or, for a batched request, several
+procedures separated by commas. `unversioned` is the set of procedure paths
+served without a namespace.
+ */
+export function findDeprecation(
+    pathname: string,
+    unversioned: Set<string>,
+): Deprecation | undefined {
+    const procedures = pathname.replace(/^\/+/, "").split(",");
+    const procedure = procedures.find((path) => unversioned.has(path));
+    if (procedure === undefined) {
+        return undefined;
+    }
+    return {
+        procedure,
+        since: unversionedDeprecatedSince,
+        replacement: `${legacyRpcVersion}.${procedure}`,
+    };
+}
+
+/** Signal to a client that it called a deprecated procedure.
+
+Sets the `Deprecation` header of RFC 9745, whose value is the time of
+deprecation, along with a header naming the replacement procedure.
+ */
+export function setDeprecationHeaders(res: express.Response, deprecation: Deprecation) {
+    const seconds = Math.floor(deprecation.since.getTime() / 1000);
+    res.setHeader("Deprecation", `@${seconds}`);
+    res.setHeader("Deprecation-Replacement", deprecation.replacement);
+}
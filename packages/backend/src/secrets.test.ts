@@ -0,0 +1,100 @@
+import assert from "node:assert";
+import * as fs from "node:fs/promises";
+import * as http from "node:http";
+import type { AddressInfo } from "node:net";
+import * as os from "node:os";
+import * as path from "node:path";
+import { it, test } from "node:test";
+import {
+    EnvSecret,
+    FileSecret,
+    RotatingSecret,
+    SecretError,
+    type SecretSource,
+    VaultSecret,
+    parseSecretSource,
+} from "./secrets.js";
+
+test("Secret sources", async (_t) => {
+    await it("parses source specifications", () => {
+        assert.ok(parseSecretSource("env:DATABASE_URL") instanceof EnvSecret);
+        assert.ok(parseSecretSource("file:/run/secrets/db") instanceof FileSecret);
+        const vault = parseSecretSource("vault:secret/data/catcolab#database_url");
+        assert.ok(vault instanceof VaultSecret);
+        assert.strictEqual(vault.path, "secret/data/catcolab");
+        assert.strictEqual(vault.field, "database_url");
+        assert.throws(() => parseSecretSource("vault:secret/data/catcolab"), SecretError);
+        assert.throws(() => parseSecretSource("postgres://localhost"), SecretError);
+    });
+
+    await it("reads secrets from the environment and files", async () => {
+        process.env.SECRETS_TEST_VALUE = "hunter2";
+        assert.strictEqual(await new EnvSecret("SECRETS_TEST_VALUE").read(), "hunter2");
+        await assert.rejects(new EnvSecret("SECRETS_TEST_MISSING").read(), SecretError);
+
+        const dir = await fs.mkdtemp(path.join(os.tmpdir(), "secrets-"));
+        const file = path.join(dir, "secret");
+        await fs.writeFile(file, "hunter2\n");
+        assert.strictEqual(await new FileSecret(file).read(), "hunter2");
+        await fs.rm(dir, { recursive: true });
+    });
+
+    await it("reads fields of secrets from Vault", async () => {
+        const server = http.createServer((req, res) => {
+            if (req.headers["x-vault-token"] !== "root") {
+                res.writeHead(403).end();
+            } else if (req.url === "/v1/secret/data/catcolab") {
+                res.end(JSON.stringify({ data: { data: { database_url: "postgres://v2" } } }));
+            } else if (req.url === "/v1/kv/catcolab") {
+                res.end(JSON.stringify({ data: { database_url: "postgres://v1" } }));
+            } else {
+                res.writeHead(404).end();
+            }
+        });
+        await new Promise<void>((resolve) => server.listen(0, "127.0.0.1", resolve));
+        const addr = `http://127.0.0.1:${(server.address() as AddressInfo).port}`;
+        process.env.VAULT_TOKEN = "root";
+        try {
+            const v2 = new VaultSecret("secret/data/catcolab", "database_url", addr);
+            assert.strictEqual(await v2.read(), "postgres://v2");
+            const v1 = new VaultSecret("kv/catcolab", "database_url", addr);
+            assert.strictEqual(await v1.read(), "postgres://v1");
+            const missing = new VaultSecret("kv/catcolab", "password", addr);
+            await assert.rejects(missing.read(), SecretError);
+        } finally {
+            // biome-ignore lint/performance/noDelete: Assigning undefined would set "undefined".
+            delete process.env.VAULT_TOKEN;
+            server.close();
+        }
+    });
+
+    await it("keeps the last value when a rotated secret cannot be read", async () => {
+        let current: string | null = "first";
+        const source: SecretSource = {
+            description: "test secret",
+            read: async () => {
+                if (current === null) {
+                    throw new Error("unavailable");
+                }
+                return current;
+            },
+        };
+        const secret = new RotatingSecret(source);
+        const changes: string[] = [];
+        secret.onChange((value) => changes.push(value));
+        assert.throws(() => secret.value, SecretError);
+
+        await secret.load();
+        assert.strictEqual(secret.value, "first");
+        assert.strictEqual(await secret.refresh(), false);
+
+        current = "second";
+        assert.strictEqual(await secret.refresh(), true);
+        assert.strictEqual(secret.value, "second");
+
+        current = null;
+        assert.strictEqual(await secret.refresh(), false);
+        assert.strictEqual(secret.value, "second");
+        assert.deepStrictEqual(changes, ["second"]);
+    });
+});
@@ -0,0 +1,253 @@
+import * as fs from "node:fs/promises";
+
+import { awsCredentialsFromEnv, signRequest } from "./aws_signing.js";
+
+/** Error raised when a secret cannot be read. */
+export class SecretError extends Error {}
+
+/** A place from which a secret, such as the database URL, can be read. */
+export interface SecretSource {
+    /** Human-readable description of the source, never including the secret. */
+    readonly description: string;
+
+    /** Read the current value of the secret. */
+    read(): Promise<string>;
+}
+
+/** Parse the specification of a secret source.
+
+The supported forms are:
+
+- `env:NAME`: the environment variable `NAME`
+- `file:/path/to/file`: the contents of a file, without surrounding whitespace
+- `vault:<path>#<field>`: a field of a secret in HashiCorp Vault, read from
+  `$VAULT_ADDR/v1/<path>` with the token in `VAULT_TOKEN` or in the file at
+  `VAULT_TOKEN_PATH`. Both version 1 and 2 of the KV engine are supported.
+- `aws-secretsmanager:<secret-id>[#<field>]`: a secret in AWS Secrets Manager,
+  either the whole secret string or a field of it when it is a JSON object. The
+  region is taken from the ARN or else from `AWS_REGION`, and credentials from
+  the standard `AWS_*` variables.
+ */
+export function parseSecretSource(spec: string): SecretSource {
+    const colon = spec.indexOf(":");
+    const scheme = colon >= 0 ? spec.slice(0, colon) : "";
+    const rest = spec.slice(colon + 1);
+    switch (scheme) {
+        case "env":
+            return new EnvSecret(rest);
+        case "file":
+            return new FileSecret(rest);
+        case "vault": {
+            const [path, field] = splitField(rest);
+            if (!field) {
+                throw new SecretError(`Vault secret ${path} must name a field after '#'`);
+            }
+            return new VaultSecret(path, field);
+        }
+        case "aws-secretsmanager": {
+            const [secretId, field] = splitField(rest);
+            return new AwsSecretsManagerSecret(secretId, field);
+        }
+        default:
+            throw new SecretError(`Unknown kind of secret source: ${spec}`);
+    }
+}
+
+function splitField(s: string): [string, string | undefined] {
+    const hash = s.lastIndexOf("#");
+    return hash >= 0 ? [s.slice(0, hash), s.slice(hash + 1)] : [s, undefined];
+}
+
+/** Secret stored in an environment variable. */
+export class EnvSecret implements SecretSource {
+    constructor(readonly name: string) {}
+
+    get description() {
+        return `environment variable ${this.name}`;
+    }
+
+    async read(): Promise<string> {
+        const value = process.env[this.name];
+        if (!value) {
+            throw new SecretError(`Environment variable ${this.name} is not set`);
+        }
+        return value;
+    }
+}
+
+/** Secret stored in a file, as mounted by agenix or Kubernetes. */
+export class FileSecret implements SecretSource {
+    constructor(readonly path: string) {}
+
+    get description() {
+        return `file ${this.path}`;
+    }
+
+    async read(): Promise<string> {
+        return (await fs.readFile(this.path, { encoding: "utf-8" })).trim();
+    }
+}
+
+/** Field of a secret stored in HashiCorp Vault. */
+export class VaultSecret implements SecretSource {
+    constructor(
+        readonly path: string,
+        readonly field: string,
+        readonly addr = process.env.VAULT_ADDR,
+    ) {}
+
+    get description() {
+        return `Vault secret ${this.path}#${this.field}`;
+    }
+
+    async read(): Promise<string> {
+        if (!this.addr) {
+            throw new SecretError("VAULT_ADDR is not set");
+        }
+        const url = `${this.addr.replace(/\/+$/, "")}/v1/${this.path.replace(/^\/+/, "")}`;
+        const response = await fetch(url, { headers: { "X-Vault-Token": await vaultToken() } });
+        if (!response.ok) {
+            throw new SecretError(`Failed to read ${this.description}: ${response.status}`);
+        }
+        const body = (await response.json()) as { data?: Record<string, unknown> };
+        // Version 2 of the KV engine nests the secret under another `data` key.
+        const data = body.data?.data ?? body.data;
+        const value = (data as Record<string, unknown> | undefined)?.[this.field];
+        if (typeof value !== "string") {
+            throw new SecretError(`${this.description} has no string field ${this.field}`);
+        }
+        return value;
+    }
+}
+
+async function vaultToken(): Promise<string> {
+    if (process.env.VAULT_TOKEN) {
+        return process.env.VAULT_TOKEN;
+    } else if (process.env.VAULT_TOKEN_PATH) {
+        return (await fs.readFile(process.env.VAULT_TOKEN_PATH, { encoding: "utf-8" })).trim();
+    } else {
+        throw new SecretError("neither VAULT_TOKEN nor VAULT_TOKEN_PATH provided");
+    }
+}
+
+/** Secret stored in AWS Secrets Manager, or a field of it. */
+export class AwsSecretsManagerSecret implements SecretSource {
+    constructor(
+        readonly secretId: string,
+        readonly field?: string,
+    ) {}
+
+    get description() {
+        return `AWS secret ${this.secretId}${this.field ? `#${this.field}` : ""}`;
+    }
+
+    /** Region of the secret, from its ARN if given one. */
+    get region(): string {
+        const arnRegion = this.secretId.startsWith("arn:") ? this.secretId.split(":")[3] : "";
+        const region = arnRegion || process.env.AWS_REGION || process.env.AWS_DEFAULT_REGION;
+        if (!region) {
+            throw new SecretError(`No region given for ${this.description}`);
+        }
+        return region;
+    }
+
+    async read(): Promise<string> {
+        const region = this.region;
+        const url = new URL(`https://secretsmanager.${region}.amazonaws.com/`);
+        const body = JSON.stringify({ SecretId: this.secretId });
+        const headers = signRequest({
+            method: "POST",
+            url,
+            region,
+            service: "secretsmanager",
+            credentials: awsCredentialsFromEnv(),
+            date: new Date(),
+            headers: {
+                "content-type": "application/x-amz-json-1.1",
+                "x-amz-target": "secretsmanager.GetSecretValue",
+            },
+            body,
+        });
+        // The host header is set by `fetch` itself.
+        // biome-ignore lint/performance/noDelete: The header must not be sent twice.
+        delete headers.host;
+        const response = await fetch(url, { method: "POST", headers, body });
+        if (!response.ok) {
+            throw new SecretError(`Failed to read ${this.description}: ${response.status}`);
+        }
+        const { SecretString } = (await response.json()) as { SecretString?: string };
+        if (SecretString === undefined) {
+            throw new SecretError(`${this.description} is not a string secret`);
+        }
+        if (!this.field) {
+            return SecretString;
+        }
+        const value = (JSON.parse(SecretString) as Record<string, unknown>)[this.field];
+        if (typeof value !== "string") {
+            throw new SecretError(`${this.description} has no string field ${this.field}`);
+        }
+        return value;
+    }
+}
+
+/** Secret that is read at startup and re-read periodically to pick up rotations.
+
+If re-reading the secret fails, the last known value is kept, so that a secret
+manager being briefly unavailable does not take down the server.
+ */
+export class RotatingSecret {
+    private current?: string;
+    private listeners: ((value: string) => void)[] = [];
+    private timer?: NodeJS.Timeout;
+
+    constructor(readonly source: SecretSource) {}
+
+    /** Current value of the secret. Only available after it has been loaded. */
+    get value(): string {
+        if (this.current === undefined) {
+            throw new SecretError(`${this.source.description} has not been loaded`);
+        }
+        return this.current;
+    }
+
+    /** Read the secret, throwing if it cannot be read. */
+    async load(): Promise<string> {
+        const value = await this.source.read();
+        const changed = this.current !== undefined && this.current !== value;
+        this.current = value;
+        if (changed) {
+            for (const listener of this.listeners) {
+                listener(value);
+            }
+        }
+        return value;
+    }
+
+    /** Re-read the secret, returning whether it changed. */
+    async refresh(): Promise<boolean> {
+        const previous = this.current;
+        try {
+            return (await this.load()) !== previous;
+        } catch (e) {
+            console.error(`Failed to refresh ${this.source.description}:`, e);
+            return false;
+        }
+    }
+
+    /** Call a function whenever the secret changes after being loaded. */
+    onChange(listener: (value: string) => void) {
+        this.listeners.push(listener);
+    }
+
+    /** Start re-reading the secret at a fixed interval. */
+    start(intervalMs: number) {
+        this.stop();
+        this.timer = setInterval(() => this.refresh(), intervalMs);
+        this.timer.unref();
+    }
+
+    stop() {
+        clearInterval(this.timer);
+        this.timer = undefined;
+    }
+}
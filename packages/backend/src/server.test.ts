@@ -0,0 +1,107 @@
+import assert from "node:assert";
+import * as crypto from "node:crypto";
+import { it, test } from "node:test";
+import type * as A from "@automerge/automerge-repo";
+import type express from "express";
+import { Persistence } from "./persistence.js";
+import { Redaction } from "./redaction.js";
+import { Server } from "./server.js";
+
+function model(name: string) {
+    return { type: "model", name, notebook: { cells: [] } };
+}
+
+/** Plain copy of the content of a live document. */
+function contentOf(handle: A.DocHandle<unknown>) {
+    return JSON.parse(JSON.stringify(handle.docSync()));
+}
+
+/** Wait for a condition that the server brings about in the background. */
+async function eventually(condition: () => Promise<boolean>) {
+    for (let i = 0; i < 100 && !(await condition()); i++) {
+        await new Promise((resolve) => setTimeout(resolve, 20));
+    }
+    assert.ok(await condition());
+}
+
+// The procedures run against the server's own Automerge repo. No peer ever
+// connects to it, so it is an in-memory store of the live documents.
+test("Document procedures", async (_t) => {
+    const url = process.env.TEST_DATABASE_URL;
+    if (!url) {
+        assert.fail("must supply connection string with environment variable TEST_DATABASE_URL");
+    }
+    const p = new Persistence(url);
+    await p.teardown("./migrations");
+    await p.migrate("./migrations");
+    await p.close();
+
+    const server = new Server(url, 0);
+    const req = { headers: {}, ip: "127.0.0.1", get: () => undefined };
+    const caller = server.appRouter.createCaller({
+        req: req as unknown as express.Request,
+        signal: new AbortController().signal,
+        user: null,
+    });
+    const autosaved = async (refId: string) => JSON.parse(await server.db.getAutosave(refId));
+
+    await it("newRef adopts the document of the client and autosaves its changes", async () => {
+        const handle = server.repo.create(model("Created"));
+        const refId = await caller.newRef({ title: "Created", docId: handle.documentId });
+        assert.strictEqual(await caller.docIdFor(refId), handle.documentId);
+        handle.change((doc) => {
+            doc.name = "Renamed";
+        });
+        await eventually(async () => (await autosaved(refId)).name === "Renamed");
+    });
+
+    await it("docIdFor makes a stored ref live with its autosave", async () => {
+        const refId = await server.db.newRef("Stored");
+        await server.db.autosave(refId, JSON.stringify(model("Stored")));
+        const docId = await caller.docIdFor(refId);
+        assert.ok(docId);
+        assert.deepStrictEqual(contentOf(server.repo.find(docId)), model("Stored"));
+        assert.strictEqual(await caller.docIdFor(refId), docId);
+    });
+
+    await it("saveRef snapshots the live content, which restoreSnapshot brings back", async () => {
+        const refId = await server.newRefWithContent(model("Original"));
+        await caller.saveRef({ refId, note: "First draft" });
+        const [witness] = (await caller.refMeta(refId)).witnesses;
+        assert.strictEqual(witness?.note, "First draft");
+        const snapshotId = witness.snapshot;
+        assert.deepStrictEqual(await caller.getSnapshot({ refId, snapshotId }), model("Original"));
+
+        const docId = await caller.docIdFor(refId);
+        assert.ok(docId);
+        const handle = server.repo.find(docId);
+        handle.change((doc) => {
+            (doc as { name: string }).name = "Edited";
+        });
+        await eventually(async () => (await autosaved(refId)).name === "Edited");
+
+        await caller.restoreSnapshot({ refId, snapshotId });
+        assert.deepStrictEqual(contentOf(handle), model("Original"));
+        assert.deepStrictEqual(await autosaved(refId), model("Original"));
+    });
+
+    await it("docIdFor waits for a redaction to reopen the document", async () => {
+        const refId = await server.newRefWithContent(model("Key sk-12345678"));
+        const redaction = Redaction.parse({ strings: ["sk-12345678"], reason: "secret" });
+        const redacting = server.redact(refId, redaction);
+        const docId = await caller.docIdFor(refId);
+        await redacting;
+        assert.ok(docId);
+        const content = JSON.stringify(contentOf(server.repo.find(docId)));
+        assert.ok(!content.includes("sk-12345678"));
+        assert.ok(!(await server.db.getAutosave(refId)).includes("sk-12345678"));
+    });
+
+    await it("document procedures reject unknown refs", async () => {
+        const refId = crypto.randomUUID();
+        await assert.rejects(caller.docIdFor(refId), { code: "NOT_FOUND" });
+        await assert.rejects(caller.saveRef({ refId, note: "" }), { code: "NOT_FOUND" });
+    });
+
+    await server.close();
+});
@@ -1,69 +1,585 @@
-import type * as http from "node:http";
+import * as crypto from "node:crypto";
+import * as fs from "node:fs";
+import * as http from "node:http";
 import * as A from "@automerge/automerge-repo";
-import { NodeWSServerAdapter } from "@automerge/automerge-repo-network-websocket";
 import * as Sentry from "@sentry/node";
 import cors from "cors";
 import express from "express";
 import morgan from "morgan";
-import * as ws from "ws";
 import { z } from "zod";
-import { Persistence } from "./persistence.js";
+import {
+    type AccessLevel,
+    AuthError,
+    TokenVerifier,
+    accessLevels,
+    bearerToken,
+    hasAccess,
+} from "./auth.js";
+import { AutosaveFolder } from "./autosave_folding.js";
+import { ConsistencySweep, sameContent } from "./consistency.js";
+import { CsvError, formatCsv, parseCsv } from "./csv.js";
+import {
+    AnalysisDocument,
+    DocumentContent,
+    ModelDocument,
+    documentTypes,
+    typedDocumentError,
+} from "./document.js";
+import {
+    ExportError,
+    type ExportFormat,
+    exportDocument,
+    exportFormats,
+    negotiateFormat,
+    parseFormat,
+} from "./export.js";
+import { Federation, checkRemoteExterns, fetchRemoteDocument } from "./federation.js";
+import { ArchiveError, InstanceArchive } from "./instance_archive.js";
+import { JobQueue, PermanentJobError } from "./jobs.js";
+import { diffJson } from "./json_diff.js";
+import { BoundedJson, type LimitExceededWarning, jsonLimitViolation } from "./json_limits.js";
+import { streamLiveContent } from "./live_view.js";
+import { LongPollingServerAdapter } from "./long_polling.js";
+import { notebookToPdf } from "./notebook_pdf.js";
+import { ObjectStorage } from "./object_storage.js";
+import { ParameterError, applyParameterRows, parameterRows } from "./parameters.js";
+import { type Json, Persistence } from "./persistence.js";
+import { PluginError, PluginRegistry, PluginRejection } from "./plugins.js";
+import {
+    type StatementClass,
+    statementTimeouts,
+    withQueryLimits,
+    withoutQueryLimits,
+} from "./query_limits.js";
+import { Redaction } from "./redaction.js";
+import { RemoteFetchError } from "./remote_fetch.js";
+import { findDeprecation, rpcVersions, setDeprecationHeaders } from "./rpc_versions.js";
+import { type CollabSession, SyncSessions } from "./sessions.js";
+import {
+    type SyncAccess,
+    type SyncDecision,
+    SyncNamespaces,
+    syncProtocolVersions,
+} from "./sync_namespaces.js";
+import { loadSystemDocuments, seedSystemDocuments } from "./system_documents.js";
+import { ODESolution, downsample } from "./trajectory.js";
+import { optedOut, viewerId } from "./views.js";
 
 import * as trpc from "@trpc/server";
 import * as trpcExpress from "@trpc/server/adapters/express";
-import { getDatabaseUrl } from "./database_url.js";
+import { getHTTPStatusCodeFromError } from "@trpc/server/http";
+import { type ListenAddress, getBasePath, getListenAddresses } from "./config.js";
+import { domainError } from "./db_errors.js";
 
-const t = trpc.initTRPC.create();
+/** Context of a procedure call: the HTTP request over which it was made, a signal
+that aborts if the client abandons it, and the user who made it, if signed in.
+ */
+type Context = { req: express.Request; signal: AbortSignal; user: string | null };
+
+/** Metadata of a procedure, determining how long its SQL statements may run. */
+type Meta = { statementClass?: StatementClass };
+
+const t = trpc.initTRPC.context<Context>().meta<Meta>().create();
+
+const packageJson = JSON.parse(
+    fs.readFileSync(new URL("../package.json", import.meta.url), { encoding: "utf-8" }),
+);
+
+/** Request to run an analysis provided by a plugin, with the given parameters. */
+const AnalysisRequest = z.object({ refId: z.string(), kind: z.string(), params: BoundedJson });
+
+/** Payload of a job running an analysis on a snapshot of a ref. */
+const AnalysisJob = AnalysisRequest.extend({ snapshotId: z.number() });
+
+/** Result of an analysis job, tagged with the parameters and snapshot analyzed. */
+const AnalysisResult = AnalysisJob.pick({ params: true, snapshotId: true }).extend({
+    result: z.unknown(),
+});
+
+/** Request of a sync peer to join over long polling, as it would over a WebSocket. */
+const LongPollingJoin = z.object({
+    peerId: z.string().min(1),
+    peerMetadata: z
+        .object({ storageId: z.string().optional(), isEphemeral: z.boolean().optional() })
+        .default({}),
+});
+
+/** Maximum number of parameter sets in a single analysis sweep. */
+const MAX_SWEEP_SIZE = 100;
+
+/** Time after the last collaborator leaves a document before it is evicted from memory. */
+const EVICTION_DELAY_MS = 10 * 60 * 1000;
+
+/** Time to wait for a client to send an evicted document that it syncs again. */
+const RESUME_TIMEOUT_MS = 10 * 1000;
+
+/** Live document evicted from memory, and whether its clients may resume it. */
+type RetiredDocument = { refId: string; resumable: boolean };
+
+/** Maximum number of refs saved together in one transaction. */
+const MAX_ATOMIC_SAVE_SIZE = 20;
+
+/** Maximum length of a search query, beyond which trigram matching gets slow. */
+const MAX_SEARCH_QUERY_LENGTH = 200;
+
+/** Number of seconds for which a URL to upload or download an attachment is valid. */
+const ATTACHMENT_URL_SECONDS = 15 * 60;
 
 export const router = t.router;
-export const publicProcedure = t.procedure;
+// Database errors with a known meaning, such as constraint violations, are reported
+// to clients as such rather than as internal server errors.
+//
+// Statements run on behalf of a query are canceled if the client goes away. Those of
+// mutations run to completion, since their effects can outlive the request.
+export const publicProcedure = t.procedure.use(async (opts) => {
+    const limits = {
+        timeoutMs: statementTimeouts[opts.meta?.statementClass ?? "interactive"],
+        signal: opts.type === "query" ? opts.ctx.signal : undefined,
+    };
+    const result = await withQueryLimits(limits, () => opts.next());
+    if (!result.ok) {
+        const mapped = domainError(result.error.cause);
+        if (mapped) {
+            throw mapped;
+        }
+    }
+    return result;
+});
 
 export class Server {
     db: Persistence;
+    federation: Federation;
+    consistency: ConsistencySweep;
+    folder: AutosaveFolder;
+    plugins: Promise<PluginRegistry>;
+    jobs: JobQueue;
+    storage: ObjectStorage | null;
+    attachmentQuota: number;
+    auth: TokenVerifier | null;
 
     docMap: Map<string, A.DocHandle<unknown>>;
+    private autosaves = new Map<string, Promise<void>>();
+    private unsnapshotted = new Set<string>();
+    private knownUsers = new Set<string>();
+
+    /** Users to whom the ID of each live document was given, with null for anyone. */
+    private grants = new Map<A.DocumentId, Map<string | null, SyncAccess>>();
+
+    /** Evicted live documents, by ID, which clients may still be syncing. */
+    private retired = new Map<A.DocumentId, RetiredDocument>();
+
+    /** Evicted documents being made live again, by ref. */
+    private resumptions = new Map<string, Promise<void>>();
+
+    /** Live documents being opened from the database, by ref. */
+    private openings = new Map<string, Promise<A.DocHandle<unknown>>>();
+
+    /** Refs being redacted, whose live documents stay closed until they are done. */
+    private redactions = new Map<string, Promise<unknown>>();
     app: express.Express;
-    server: http.Server;
-    wss: ws.WebSocketServer;
+    servers: http.Server[];
+    closing = false;
+    syncNamespaces: SyncNamespaces;
+    polling: LongPollingServerAdapter;
+    sessions: SyncSessions;
     repo: A.Repo;
     appRouter;
 
-    constructor(port = process.env.PORT || 8000) {
-        const url = getDatabaseUrl();
+    constructor(databaseUrl: string, port = process.env.PORT || 8000) {
+        this.db = new Persistence(databaseUrl);
+        this.db
+            .waitForDatabase()
+            .then(async () => {
+                console.log("Connected to database");
+                for (const [refId, documentId] of await this.db.liveDocumentIds()) {
+                    this.retired.set(documentId as A.DocumentId, { refId, resumable: true });
+                }
+                const upgraded = await seedSystemDocuments(this.db, await loadSystemDocuments());
+                for (const [refId, content] of upgraded) {
+                    this.replaceLiveContent(refId, content);
+                }
+            })
+            .catch((e) => console.error("Failed to seed system documents:", e));
+
+        this.federation = new Federation(this.db, (refId, content) =>
+            this.replaceLiveContent(refId, content),
+        );
+        this.federation.start(Number(process.env.MIRROR_REFRESH_SECONDS || 600) * 1000);
+
+        this.plugins = PluginRegistry.fromEnv();
+        this.plugins.catch((e) => {
+            console.error("Failed to load plugins, shutting down:", e);
+            process.exitCode = 1;
+            this.close();
+        });
+
+        this.jobs = new JobQueue(this.db);
+        this.jobs.register("analysis", (payload) => this.analysisJob(payload));
+        this.jobs.start();
 
-        this.db = new Persistence(url);
+        this.storage = ObjectStorage.fromEnv();
+        this.auth = TokenVerifier.fromEnv();
+        this.attachmentQuota = Number(process.env.ATTACHMENT_QUOTA_MB || 100) * 1024 * 1024;
 
         this.docMap = new Map();
 
+        this.consistency = new ConsistencySweep(this.db, this.docMap, (refId, content) =>
+            this.replaceLiveContent(refId, content),
+        );
+        this.consistency.start(Number(process.env.CONSISTENCY_SWEEP_SECONDS || 300) * 1000);
+
+        this.folder = new AutosaveFolder(this.db);
+        this.folder.start(Number(process.env.AUTOSAVE_FOLD_SECONDS || 5) * 1000);
+
+        const basePath = getBasePath();
+
         this.app = express();
 
-        this.app.get("/debug-sentry", function mainHandler(_req, _res) {
+        this.polling = new LongPollingServerAdapter();
+
+        const routes = express.Router();
+
+        routes.get("/debug-sentry", function mainHandler(_req, _res) {
             throw new Error("My first Sentry error!");
         });
 
-        Sentry.setupExpressErrorHandler(this.app);
-        this.app.use(cors());
+        this.app.use(cors({ exposedHeaders: ["Deprecation", "Deprecation-Replacement"] }));
+
+        // Procedures of the current version of the API. They are served both in the
+        // `v1` namespace and, for frontends loaded before versioning was introduced,
+        // without a namespace.
+        const procedures = {
+            serverInfo: publicProcedure.query(async () => {
+                return {
+                    version: packageJson.version as string,
+                    documentTypes,
+                    syncProtocols: syncProtocolVersions,
+                    rpcVersions,
+                    analysisKinds: (await this.plugins).analysisKinds(),
+                    exportFormats: ["json", "automerge", ...Object.keys(exportFormats), "pdf"],
+                    importFormats: ["json", "automerge"],
+                    authProviders: this.auth ? [this.auth.issuer] : [],
+                    features: [
+                        "federation",
+                        "jobs",
+                        "system-documents",
+                        "search",
+                        "ref-listing",
+                        "collab-sessions",
+                        "permalinks",
+                        "snapshot-history",
+                        "live-view",
+                        "parameters-csv",
+                        "export",
+                        "pdf-export",
+                        "long-polling",
+                        ...(this.storage ? ["attachments"] : []),
+                        ...(this.auth ? ["auth"] : []),
+                    ],
+                };
+            }),
 
-        this.appRouter = router({
             newRef: publicProcedure
                 .input(z.object({ title: z.string(), docId: z.string() }))
                 .mutation(async (opts) => {
                     const {
                         input: { title, docId },
+                        ctx: { user },
                     } = opts;
-                    const refId = await this.db.newRef(title);
+                    this.assertSignedIn(user);
+                    const refId = await this.db.newRef(title, user);
                     const handle = this.repo.find(docId as A.DocumentId);
                     this.setHandleCallback(refId, handle);
                     this.docMap.set(refId, handle);
+                    this.grant(handle.documentId, user, "write");
                     return refId;
                 }),
 
+            // The ID of a live document is what lets a sync peer read and write it, so
+            // it is only given to users who may view the ref, and the peers of those
+            // users are then allowed to sync it. Only the peers of editors may send
+            // changes.
             docIdFor: publicProcedure.input(z.string()).query(async (opts) => {
-                const { input: refId } = opts;
+                const {
+                    input: refId,
+                    ctx: { user },
+                } = opts;
+                await this.assertAccess(refId, user, "viewer");
                 const handle = await this.getDocHandle(refId);
+                if (handle && this.auth) {
+                    await this.grantAccess(refId, handle.documentId, user);
+                }
+                this.recordView(refId, opts.ctx.req);
                 return handle?.documentId;
             }),
 
+            changesSince: publicProcedure
+                .meta({ statementClass: "report" })
+                .input(
+                    z.object({
+                        refId: z.string(),
+                        since: z.number().int().min(0),
+                        limit: z.number().int().min(1).max(1000).default(100),
+                        diffs: z.boolean().default(false),
+                    }),
+                )
+                .query(async (opts) => {
+                    const {
+                        input: { refId, since, limit, diffs },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "viewer");
+                    return await this.db.changesSince(refId, since, limit, diffs);
+                }),
+
+            getSnapshot: publicProcedure
+                .input(z.object({ refId: z.string(), snapshotId: z.number() }))
+                .query(async (opts) => {
+                    const {
+                        input: { refId, snapshotId },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "viewer");
+                    return await this.refSnapshot(refId, snapshotId);
+                }),
+
+            // Structured diff between two snapshots of a ref, as a JSON Patch.
+            diffSnapshots: publicProcedure
+                .meta({ statementClass: "report" })
+                .input(z.object({ refId: z.string(), from: z.number(), to: z.number() }))
+                .query(async (opts) => {
+                    const {
+                        input: { refId, from, to },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "viewer");
+                    const before = await this.refSnapshot(refId, from);
+                    const after = await this.refSnapshot(refId, to);
+                    return diffJson(before, after);
+                }),
+
+            // Roll a ref back to an earlier snapshot, as a new save on top of its
+            // history, so that the restore can itself be undone.
+            restoreSnapshot: publicProcedure
+                .input(z.object({ refId: z.string(), snapshotId: z.number() }))
+                .mutation(async (opts) => {
+                    const {
+                        input: { refId, snapshotId },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "editor");
+                    if (await this.db.isReadOnly(refId)) {
+                        throw new trpc.TRPCError({
+                            code: "FORBIDDEN",
+                            message: `Ref ${refId} is read-only`,
+                        });
+                    }
+                    const result = DocumentContent.safeParse(
+                        await this.refSnapshot(refId, snapshotId),
+                    );
+                    if (!result.success) {
+                        throw new trpc.TRPCError({
+                            code: "UNPROCESSABLE_CONTENT",
+                            message: `Snapshot ${snapshotId} is not a CatColab document`,
+                        });
+                    }
+                    const content = await this.runSavePlugins(result.data);
+                    await this.autosaves.get(refId);
+                    const note = `Restored snapshot ${snapshotId}`;
+                    const [witnessId] = await this.db.saveRefs([{ refId, content }], note);
+                    this.replaceLiveContent(refId, content);
+                    return witnessId;
+                }),
+
+            refMeta: publicProcedure.input(z.string()).query(async (opts) => {
+                const { input: refId } = opts;
+                await this.assertAccess(refId, opts.ctx.user, "viewer");
+                return await this.db.refMeta(refId);
+            }),
+
+            pinSnapshot: publicProcedure
+                .input(z.object({ refId: z.string(), snapshotId: z.number(), note: z.string() }))
+                .mutation(async (opts) => {
+                    const {
+                        input: { refId, snapshotId, note },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "editor");
+                    if (!(await this.db.pinSnapshot(refId, snapshotId, note))) {
+                        throw new trpc.TRPCError({
+                            code: "NOT_FOUND",
+                            message: `Snapshot ${snapshotId} is not part of ref ${refId}`,
+                        });
+                    }
+                }),
+
+            unpinSnapshot: publicProcedure
+                .input(z.object({ refId: z.string(), snapshotId: z.number() }))
+                .mutation(async (opts) => {
+                    const {
+                        input: { refId, snapshotId },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "editor");
+                    return await this.db.unpinSnapshot(refId, snapshotId);
+                }),
+
+            // Permalink to a snapshot of a ref, for citing a version that later edits
+            // and restores cannot change. Without a snapshot, the current content of
+            // the ref is saved and linked.
+            createPermalink: publicProcedure
+                .input(z.object({ refId: z.string(), snapshotId: z.number().optional() }))
+                .mutation(async (opts) => {
+                    const {
+                        input: { refId },
+                    } = opts;
+                    // Without a snapshot, the head of the ref is saved as a new one.
+                    const level = opts.input.snapshotId === undefined ? "editor" : "viewer";
+                    await this.assertAccess(refId, opts.ctx.user, level);
+                    let { snapshotId } = opts.input;
+                    if (snapshotId === undefined) {
+                        await this.docMap.get(refId)?.whenReady();
+                        const witnessId = await this.saveWithPlugins(refId, "Permalink");
+                        snapshotId = (await this.db.getWitness(refId, witnessId))?.snapshot;
+                    }
+                    const token =
+                        snapshotId === undefined
+                            ? null
+                            : await this.db.createPermalink(refId, snapshotId);
+                    if (token === null) {
+                        throw new trpc.TRPCError({
+                            code: "NOT_FOUND",
+                            message: `Snapshot ${snapshotId} is not part of ref ${refId}`,
+                        });
+                    }
+                    return { token, snapshotId };
+                }),
+
+            // A permalink is only readable by those who may still view its ref.
+            getPermalink: publicProcedure.input(z.string()).query(async (opts) => {
+                const { input: token } = opts;
+                const permalink = await this.db.getPermalink(token);
+                if (permalink) {
+                    await this.assertAccess(permalink.ref, opts.ctx.user, "viewer");
+                }
+                if (!permalink?.intact) {
+                    throw new trpc.TRPCError({
+                        code: "NOT_FOUND",
+                        message: permalink
+                            ? `Content of permalink ${token} was redacted`
+                            : `No permalink ${token}`,
+                    });
+                }
+                return {
+                    refId: permalink.ref,
+                    snapshotId: permalink.snapshot,
+                    createdAt: permalink.createdat,
+                    content: JSON.parse(permalink.content) as Json,
+                };
+            }),
+
+            // Record of administrative actions on a ref, such as redactions.
+            auditLog: publicProcedure.input(z.string()).query(async (opts) => {
+                const { input: refId } = opts;
+                await this.assertAccess(refId, opts.ctx.user, "viewer");
+                return await this.db.auditLog(refId);
+            }),
+
+            permissions: publicProcedure.input(z.string()).query(async (opts) => {
+                const { input: refId } = opts;
+                await this.assertAccess(refId, opts.ctx.user, "owner");
+                return await this.db.permissions(refId);
+            }),
+
+            // Share a ref with a user, or stop sharing it if the level is null. Users
+            // are identified by the subject of their ID tokens.
+            setPermission: publicProcedure
+                .input(
+                    z.object({
+                        refId: z.string(),
+                        userId: z.string().min(1),
+                        level: z.enum(accessLevels).nullable(),
+                    }),
+                )
+                .mutation(async (opts) => {
+                    const {
+                        input: { refId, userId, level },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "owner");
+                    const before = await this.db.accessLevel(refId, userId);
+                    if (!(await this.db.setPermission(refId, userId, level))) {
+                        throw new trpc.TRPCError({
+                            code: "CONFLICT",
+                            message: `Ref ${refId} must keep an owner`,
+                        });
+                    }
+                    if (before !== null && !hasAccess(level, before)) {
+                        this.revokeSync(refId, (user) => user === userId);
+                    }
+                }),
+
+            // Refs without an owner, such as those created before authentication was
+            // enabled, are open to everyone. The first signed-in user to claim one
+            // becomes its owner, and can then share it like any other ref.
+            claimRef: publicProcedure.input(z.string()).mutation(async (opts) => {
+                const {
+                    input: refId,
+                    ctx: { user },
+                } = opts;
+                if (!this.auth || user === null) {
+                    throw new trpc.TRPCError({
+                        code: "UNAUTHORIZED",
+                        message: "Sign in to claim documents",
+                    });
+                }
+                await this.assertAccess(refId, user, "editor");
+                if (await this.db.isReadOnly(refId)) {
+                    throw new trpc.TRPCError({
+                        code: "FORBIDDEN",
+                        message: `Ref ${refId} is read-only`,
+                    });
+                }
+                if (!(await this.db.claimRef(refId, user))) {
+                    throw new trpc.TRPCError({
+                        code: "CONFLICT",
+                        message: `Ref ${refId} already has an owner`,
+                    });
+                }
+                this.revokeSync(refId, (other) => other !== user);
+                const handle = this.docMap.get(refId);
+                if (handle) {
+                    this.grant(handle.documentId, user, "write");
+                }
+            }),
+
+            refStats: publicProcedure
+                .meta({ statementClass: "report" })
+                .input(z.string())
+                .query(async (opts) => {
+                    const { input: refId } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "viewer");
+                    await this.folder.flush();
+                    return await this.db.refStats(refId);
+                }),
+
+            // Collaboration sessions on a document, newest first, for studying how
+            // groups build models together.
+            collabSessions: publicProcedure
+                .meta({ statementClass: "report" })
+                .input(z.object({ refId: z.string(), days: z.number().int().min(1).max(365) }))
+                .query(async (opts) => {
+                    const {
+                        input: { refId, days },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "viewer");
+                    return await this.db.collabSessions(refId, days);
+                }),
+
+            refViews: publicProcedure
+                .meta({ statementClass: "report" })
+                .input(z.object({ refId: z.string(), days: z.number().int().min(1).max(365) }))
+                .query(async (opts) => {
+                    const {
+                        input: { refId, days },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "viewer");
+                    return await this.db.refViews(refId, days);
+                }),
+
             saveRef: publicProcedure
                 .input(z.object({ refId: z.string(), note: z.string() }))
                 .mutation(async (opts) => {
@@ -71,79 +587,1873 @@ export class Server {
                     const {
                         input: { refId, note },
                     } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "editor");
                     await this.docMap.get(refId)?.whenReady();
-                    await this.db.saveRef(refId, note);
+                    await this.saveWithPlugins(refId, note);
+                }),
+
+            // Save new content for several related documents, such as a model and the
+            // analyses that must change with it, so that either all of them advance or
+            // none do.
+            saveRefs: publicProcedure
+                .meta({ statementClass: "bulk" })
+                .input(
+                    z.object({
+                        updates: z
+                            .array(z.object({ refId: z.string(), content: DocumentContent }))
+                            .min(1)
+                            .max(MAX_ATOMIC_SAVE_SIZE),
+                        note: z.string(),
+                    }),
+                )
+                .mutation(async (opts) => {
+                    const {
+                        input: { updates, note },
+                    } = opts;
+                    if (new Set(updates.map((u) => u.refId)).size !== updates.length) {
+                        throw new trpc.TRPCError({
+                            code: "BAD_REQUEST",
+                            message: "Each ref can be updated at most once",
+                        });
+                    }
+                    const saved: { refId: string; content: DocumentContent }[] = [];
+                    for (const { refId, content } of updates) {
+                        await this.assertAccess(refId, opts.ctx.user, "editor");
+                        if (await this.db.isReadOnly(refId)) {
+                            throw new trpc.TRPCError({
+                                code: "FORBIDDEN",
+                                message: `Ref ${refId} is read-only`,
+                            });
+                        }
+                        const typeError = typedDocumentError(content);
+                        if (typeError) {
+                            throw new trpc.TRPCError({
+                                code: "BAD_REQUEST",
+                                message: `Invalid content for ref ${refId}: ${typeError}`,
+                            });
+                        }
+                        saved.push({ refId, content: await this.runSavePlugins(content) });
+                    }
+                    for (const { refId } of saved) {
+                        await this.autosaves.get(refId);
+                    }
+                    const witnesses = await this.db.saveRefs(saved, note);
+                    for (const { refId, content } of saved) {
+                        this.replaceLiveContent(refId, content);
+                    }
+                    return witnesses;
                 }),
 
-            getRefs: publicProcedure.query(async () => {
-                return await this.db.allRefs();
+            importFromUrl: publicProcedure
+                .meta({ statementClass: "bulk" })
+                .input(z.object({ url: z.string() }))
+                .mutation(async (opts) => {
+                    const {
+                        input: { url },
+                        ctx: { user },
+                    } = opts;
+                    this.assertSignedIn(user);
+                    const content = await fetchRemoteDocument(url).catch(remoteFetchFailed);
+                    await checkRemoteExterns(this.db, content).catch(remoteFetchFailed);
+                    return await this.newRefWithContent(content, user);
+                }),
+
+            subscribeMirror: publicProcedure
+                .input(z.object({ url: z.string() }))
+                .mutation(async (opts) => {
+                    const {
+                        input: { url },
+                        ctx: { user },
+                    } = opts;
+                    // Anonymous callers could otherwise make the server fetch any URL,
+                    // and keep refreshing an unowned mirror that anyone may edit.
+                    this.assertSignedIn(user);
+                    return await this.federation.subscribe(url, user).catch(remoteFetchFailed);
+                }),
+
+            refreshMirror: publicProcedure.input(z.string()).mutation(async (opts) => {
+                const { input: refId } = opts;
+                await this.assertAccess(refId, opts.ctx.user, "viewer");
+                await this.assertMirror(refId);
+                return await this.federation.refresh(refId).catch(remoteFetchFailed);
+            }),
+
+            getMirror: publicProcedure.input(z.string()).query(async (opts) => {
+                const { input: refId } = opts;
+                await this.assertAccess(refId, opts.ctx.user, "viewer");
+                return await this.db.getMirror(refId);
+            }),
+
+            systemDocuments: publicProcedure.query(async () => {
+                return await this.db.allSystemDocuments();
+            }),
+
+            // The fork belongs to the user who made it, whatever the permissions of
+            // the original.
+            forkRef: publicProcedure.input(z.string()).mutation(async (opts) => {
+                const {
+                    input: refId,
+                    ctx: { user },
+                } = opts;
+                await this.assertAccess(refId, user, "viewer");
+                this.assertSignedIn(user);
+                const content = await this.currentContent(refId);
+                // Copy out of the live document, which may be an Automerge proxy.
+                return await this.newRefWithContent(JSON.parse(JSON.stringify(content)), user);
+            }),
+
+            consistencyReport: publicProcedure.query(async (opts) => {
+                assertAdmin(opts.ctx.req);
+                return this.consistency.lastReport;
+            }),
+
+            sweepConsistency: publicProcedure
+                .meta({ statementClass: "bulk" })
+                .mutation(async (opts) => {
+                    assertAdmin(opts.ctx.req);
+                    return await this.consistency.sweep();
+                }),
+
+            conflicts: publicProcedure.query(async (opts) => {
+                assertAdmin(opts.ctx.req);
+                return await this.db.conflicts();
+            }),
+
+            // Resolve a conflict between a live document and a concurrent write to its
+            // autosave, by keeping either the live content or the stored content.
+            resolveConflict: publicProcedure
+                .input(z.object({ refId: z.string(), keep: z.enum(["live", "stored"]) }))
+                .mutation(async (opts) => {
+                    const {
+                        input: { refId, keep },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "editor");
+                    const live = (await this.getDocHandle(refId))?.docSync();
+                    if (!this.consistency.conflicts.has(refId) || live === undefined) {
+                        throw new trpc.TRPCError({
+                            code: "NOT_FOUND",
+                            message: `Ref ${refId} has no conflict to resolve`,
+                        });
+                    }
+                    if (keep === "live") {
+                        await this.db.autosaveWithExterns(refId, live);
+                        await this.consistency.markSynced(refId, JSON.stringify(live));
+                    } else {
+                        const stored = await this.db.getAutosave(refId);
+                        this.replaceLiveContent(refId, JSON.parse(stored));
+                        await this.consistency.markSynced(refId, stored);
+                    }
+                }),
+
+            analysisKinds: publicProcedure.query(async () => {
+                return (await this.plugins).analysisKinds();
+            }),
+
+            runAnalysis: publicProcedure.input(AnalysisRequest).mutation(async (opts) => {
+                const { input } = opts;
+                await this.assertAccess(input.refId, opts.ctx.user, "viewer");
+                const snapshotId = await this.snapshotForAnalysis(input.refId);
+                return await this.jobs.enqueue("analysis", { ...input, snapshotId });
+            }),
+
+            // Run an analysis once for each set of parameters, such as for a parameter
+            // sweep. All runs analyze the same version of the document.
+            runAnalysisSweep: publicProcedure
+                .meta({ statementClass: "bulk" })
+                .input(
+                    AnalysisRequest.omit({ params: true }).extend({
+                        paramSets: z.array(BoundedJson).min(1).max(MAX_SWEEP_SIZE),
+                    }),
+                )
+                .mutation(async (opts) => {
+                    const {
+                        input: { refId, kind, paramSets },
+                    } = opts;
+                    await this.assertAccess(refId, opts.ctx.user, "viewer");
+                    const snapshotId = await this.snapshotForAnalysis(refId);
+                    const jobIds: number[] = [];
+                    for (const params of paramSets) {
+                        const payload = { refId, kind, params, snapshotId };
+                        jobIds.push(await this.jobs.enqueue("analysis", payload));
+                    }
+                    return jobIds;
+                }),
+
+            // Attachments are uploaded and downloaded by the client directly from object
+            // storage, through short-lived signed URLs. Uploads must have the size that
+            // was declared, so that the quota of the ref cannot be bypassed.
+            createAttachment: publicProcedure
+                .input(
+                    z.object({
+                        refId: z.string(),
+                        filename: z.string().min(1).max(255),
+                        contentType: z.string().max(255),
+                        size: z.number().int().min(0),
+                    }),
+                )
+                .mutation(async (opts) => {
+                    const {
+                        input: { refId, filename, contentType, size },
+                    } = opts;
+                    const storage = this.assertStorage();
+                    await this.assertAccess(refId, opts.ctx.user, "editor");
+                    if (await this.db.isReadOnly(refId)) {
+                        throw new trpc.TRPCError({
+                            code: "FORBIDDEN",
+                            message: "Document is read-only",
+                        });
+                    }
+                    const quota = this.attachmentQuota;
+                    const id = await this.db.newAttachment(
+                        refId,
+                        filename,
+                        contentType,
+                        size,
+                        quota,
+                    );
+                    if (id === null) {
+                        throw new trpc.TRPCError({
+                            code: "PAYLOAD_TOO_LARGE",
+                            message: `Attachments of ref ${refId} would exceed ${quota} bytes`,
+                        });
+                    }
+                    const uploadUrl = storage.signedUrl(
+                        "PUT",
+                        attachmentKey(refId, id),
+                        ATTACHMENT_URL_SECONDS,
+                        { "Content-Length": String(size), "Content-Type": contentType },
+                    );
+                    return { id, uploadUrl };
+                }),
+
+            attachmentUrl: publicProcedure.input(z.number()).query(async (opts) => {
+                const { input: id } = opts;
+                const storage = this.assertStorage();
+                const attachment = await this.db.getAttachment(id);
+                if (!attachment) {
+                    throw new trpc.TRPCError({
+                        code: "NOT_FOUND",
+                        message: `No such attachment ${id}`,
+                    });
+                }
+                await this.assertAccess(attachment.ref, opts.ctx.user, "viewer");
+                const key = attachmentKey(attachment.ref, id);
+                const url = storage.signedUrl("GET", key, ATTACHMENT_URL_SECONDS);
+                return { ...attachment, url };
+            }),
+
+            listAttachments: publicProcedure.input(z.string()).query(async (opts) => {
+                const { input: refId } = opts;
+                await this.assertAccess(refId, opts.ctx.user, "viewer");
+                return {
+                    attachments: await this.db.getAttachments(refId),
+                    usedBytes: await this.db.attachmentUsage(refId),
+                    quotaBytes: this.attachmentQuota,
+                };
+            }),
+
+            deleteAttachment: publicProcedure.input(z.number()).mutation(async (opts) => {
+                const { input: id } = opts;
+                const storage = this.assertStorage();
+                const attachment = await this.db.getAttachment(id);
+                if (!attachment) {
+                    return false;
+                }
+                await this.assertAccess(attachment.ref, opts.ctx.user, "editor");
+                if (await this.db.isReadOnly(attachment.ref)) {
+                    throw new trpc.TRPCError({
+                        code: "FORBIDDEN",
+                        message: "Document is read-only",
+                    });
+                }
+                // Delete the object first, so that a failure leaves the record to retry with.
+                await storage.delete(attachmentKey(attachment.ref, id));
+                return await this.db.deleteAttachment(id);
+            }),
+
+            // Jobs are visible to those who may view the ref that they analyze.
+            getJob: publicProcedure.input(z.number()).query(async (opts) => {
+                const { input: id } = opts;
+                const job = await this.db.getJob(id);
+                if (job?.ref) {
+                    await this.assertAccess(job.ref, opts.ctx.user, "viewer");
+                }
+                return job;
+            }),
+
+            // Retrieve the ODE solution computed by an analysis job, downsampled for
+            // plotting instead of as the full solver output.
+            jobTrajectory: publicProcedure
+                .input(
+                    z.object({
+                        jobId: z.number(),
+                        maxPoints: z.number().int().min(2).max(100_000).default(1000),
+                        variables: z.array(z.string()).optional(),
+                    }),
+                )
+                .query(async (opts) => {
+                    const {
+                        input: { jobId, maxPoints, variables },
+                    } = opts;
+                    const job = await this.db.getJob(jobId);
+                    if (!job) {
+                        throw new trpc.TRPCError({
+                            code: "NOT_FOUND",
+                            message: `No such job ${jobId}`,
+                        });
+                    }
+                    if (job.ref) {
+                        await this.assertAccess(job.ref, opts.ctx.user, "viewer");
+                    }
+                    const output = AnalysisResult.safeParse(job.result);
+                    const solution = ODESolution.safeParse(output.data?.result);
+                    if (job.status !== "succeeded" || !output.success || !solution.success) {
+                        throw new trpc.TRPCError({
+                            code: "UNPROCESSABLE_CONTENT",
+                            message: `Job ${jobId} did not produce an ODE solution`,
+                        });
+                    }
+                    const { params, snapshotId } = output.data;
+                    const downsampled = downsample(solution.data, maxPoints, variables);
+                    return { params, snapshotId, solution: downsampled };
+                }),
+
+            deadJobs: publicProcedure.query(async (opts) => {
+                assertAdmin(opts.ctx.req);
+                return await this.db.deadJobs();
+            }),
+
+            retryJob: publicProcedure.input(z.number()).mutation(async (opts) => {
+                const { input: id } = opts;
+                assertAdmin(opts.ctx.req);
+                return await this.db.retryJob(id);
+            }),
+
+            getRefs: publicProcedure.meta({ statementClass: "report" }).query(async (opts) => {
+                return await this.db.allRefs(opts.ctx.user);
             }),
 
+            // Documents visible to the caller, for a "My documents" page. Use
+            // `owner` with the caller's own user ID to list only their documents.
+            listRefs: publicProcedure
+                .meta({ statementClass: "report" })
+                .input(
+                    z.object({
+                        owner: z.string().optional(),
+                        docType: z.string().optional(),
+                        offset: z.number().int().min(0).default(0),
+                        limit: z.number().int().min(1).max(100).default(50),
+                    }),
+                )
+                .query(async (opts) => {
+                    const {
+                        input: { owner, docType, offset, limit },
+                    } = opts;
+                    await this.folder.flush();
+                    return await this.db.listRefs(opts.ctx.user, { owner, docType }, offset, limit);
+                }),
+
+            searchRefs: publicProcedure
+                .meta({ statementClass: "report" })
+                .input(
+                    z.object({
+                        query: z.string().trim().min(1).max(MAX_SEARCH_QUERY_LENGTH),
+                        limit: z.number().int().min(1).max(100).default(20),
+                    }),
+                )
+                .query(async (opts) => {
+                    const {
+                        input: { query, limit },
+                    } = opts;
+                    await this.folder.flush();
+                    return await this.db.searchRefs(query, limit, opts.ctx.user);
+                }),
+
             getBacklinks: publicProcedure
+                .meta({ statementClass: "report" })
                 .input(z.object({ refId: z.string(), taxon: z.string() }))
                 .query(async (opts) => {
                     const {
                         input: { refId, taxon },
+                        ctx: { user },
                     } = opts;
-                    console.log(`getting backlinks for ${refId}`);
-                    return await this.db.getBacklinks(refId, taxon);
+                    await this.assertAccess(refId, user, "viewer");
+                    await this.folder.flush();
+                    const backlinks: string[] = [];
+                    for (const fromRef of await this.db.getBacklinks(refId, taxon)) {
+                        if (await this.canAccess(fromRef, user, "viewer")) {
+                            backlinks.push(fromRef);
+                        }
+                    }
+                    return backlinks;
                 }),
-        });
+        };
+        this.appRouter = router({ ...procedures, v1: router(procedures) });
+        const unversioned = new Set(Object.keys(procedures));
 
         this.app.use(morgan("tiny"));
 
-        this.app.use(
+        // Readiness probe: degrade while the database is unreachable, rather than
+        // failing requests one at a time.
+        routes.get(
+            "/readyz",
+            asyncHandler(async (_req, res) => {
+                const database = await this.db.health();
+                res.status(database.ok ? 200 : 503).json({
+                    status: database.ok ? "ok" : "degraded",
+                    database,
+                });
+            }),
+        );
+
+        routes.get(
+            "/refs/:refId/content",
+            asyncHandler(async (req, res) => {
+                const { refId } = req.params;
+                if (!(await this.db.hasRef(refId))) {
+                    res.sendStatus(404);
+                    return;
+                }
+                if (!(await this.mayAccess(req, refId, "viewer"))) {
+                    res.sendStatus(403);
+                    return;
+                }
+                res.type("json").send(await this.db.getAutosave(refId));
+            }),
+        );
+
+        // Content of a permalink, which never changes. It is cached forever if its
+        // ref is public, and otherwise revalidated on every request, so that it
+        // stops being served to users whose access to the ref is revoked.
+        routes.get(
+            "/permalinks/:token",
+            asyncHandler(async (req, res) => {
+                const permalink = await this.db.getPermalink(req.params.token);
+                if (!permalink) {
+                    res.sendStatus(404);
+                    return;
+                }
+                if (!(await this.mayAccess(req, permalink.ref, "viewer"))) {
+                    res.sendStatus(403);
+                    return;
+                }
+                if (!permalink.intact) {
+                    res.sendStatus(410);
+                    return;
+                }
+                const isPublic = await this.canAccess(permalink.ref, null, "viewer");
+                const cacheControl = isPublic
+                    ? "public, max-age=31536000, immutable"
+                    : "private, no-cache";
+                res.set("Cache-Control", cacheControl)
+                    .set("ETag", `"${req.params.token}"`)
+                    .type("json")
+                    .send(permalink.content);
+            }),
+        );
+
+        routes.get(
+            "/refs/:refId/live",
+            asyncHandler(async (req, res) => {
+                const { refId } = req.params;
+                if (!(await this.db.hasRef(refId))) {
+                    res.sendStatus(404);
+                    return;
+                }
+                if (!(await this.mayAccess(req, refId, "viewer"))) {
+                    res.sendStatus(403);
+                    return;
+                }
+                const handle = await this.getDocHandle(refId);
+                if (!handle) {
+                    res.sendStatus(404);
+                    return;
+                }
+                await handle.whenReady();
+                this.recordView(refId, req);
+                streamLiveContent(handle, req, res);
+            }),
+        );
+
+        // Export the live document of a ref as an Automerge binary, with its history.
+        // Live documents are saved with their history when their last collaborator
+        // leaves, when they are evicted, and when the server shuts down. If the
+        // server crashes, the edits since then are kept by the autosave, but without
+        // their history: they are brought back in a single change.
+        routes.get(
+            "/refs/:refId/automerge",
+            asyncHandler(async (req, res) => {
+                const { refId } = req.params;
+                if (!(await this.db.hasRef(refId))) {
+                    res.sendStatus(404);
+                    return;
+                }
+                if (!(await this.mayAccess(req, refId, "viewer"))) {
+                    res.sendStatus(403);
+                    return;
+                }
+                const handle = await this.getDocHandle(refId);
+                const binary = handle && (await this.repo.export(handle.documentId));
+                if (!binary) {
+                    res.sendStatus(404);
+                    return;
+                }
+                res.type("application/octet-stream")
+                    .attachment(`${refId}.automerge`)
+                    .send(Buffer.from(binary));
+            }),
+        );
+
+        // Reconcile an Automerge binary, such as one exported above and edited offline,
+        // into the server's copy of the document. The two share history as long as
+        // the document was saved with it, so only the fields of a binary exported
+        // before a crash, or before this history was kept, conflict rather than merge.
+        routes.post(
+            "/refs/:refId/automerge",
+            express.raw({ type: "application/octet-stream", limit: "50mb" }),
+            asyncHandler(async (req, res) => {
+                const { refId } = req.params;
+                if (!(await this.db.hasRef(refId))) {
+                    res.sendStatus(404);
+                    return;
+                }
+                if (!(await this.mayAccess(req, refId, "editor"))) {
+                    res.sendStatus(403);
+                    return;
+                }
+                if (await this.db.isReadOnly(refId)) {
+                    res.status(403).json({ error: "Document is read-only" });
+                    return;
+                }
+                if (!Buffer.isBuffer(req.body) || req.body.length === 0) {
+                    res.status(400).json({ error: "Expected an Automerge binary" });
+                    return;
+                }
+                let imported: A.DocHandle<unknown>;
+                try {
+                    imported = this.repo.import(new Uint8Array(req.body));
+                } catch {
+                    res.status(400).json({ error: "Not a valid Automerge binary" });
+                    return;
+                }
+                try {
+                    if (!DocumentContent.safeParse(imported.docSync()).success) {
+                        res.status(400).json({ error: "Not a CatColab document" });
+                        return;
+                    }
+                    const handle = await this.getDocHandle(refId);
+                    handle?.merge(imported);
+                    res.sendStatus(204);
+                } finally {
+                    this.repo.delete(imported.documentId);
+                }
+            }, "bulk"),
+        );
+
+        // Export the head of a model, i.e. its last autosave, to an interchange format.
+        // The format is named by the `format` query parameter, or else negotiated
+        // with the `Accept` header.
+        routes.get(
+            "/export/:refId",
+            asyncHandler(async (req, res) => {
+                const { refId } = req.params;
+                if (!(await this.db.hasRef(refId))) {
+                    res.sendStatus(404);
+                    return;
+                }
+                if (!(await this.mayAccess(req, refId, "viewer"))) {
+                    res.sendStatus(403);
+                    return;
+                }
+                const format = req.query.format;
+                const supported = Object.keys(exportFormats);
+                let chosen: ExportFormat | null;
+                if (format !== undefined) {
+                    chosen = typeof format === "string" ? parseFormat(format) : null;
+                    if (chosen === null) {
+                        res.status(400).json({ error: "Unknown export format", supported });
+                        return;
+                    }
+                } else {
+                    res.vary("Accept");
+                    chosen = negotiateFormat(req.get("accept"));
+                    if (chosen === null) {
+                        res.status(406).json({ error: "Unsupported export format", supported });
+                        return;
+                    }
+                }
+                const head = JSON.parse(await this.db.getAutosave(refId));
+                let exported: string;
+                try {
+                    exported = exportDocument(head, chosen, refId);
+                } catch (e) {
+                    if (e instanceof ExportError) {
+                        res.status(422).json({ error: e.message });
+                        return;
+                    }
+                    throw e;
+                }
+                res.type(exportFormats[chosen]).send(exported);
+            }),
+        );
+
+        // Render the head of a notebook, with its text and a diagram of what it
+        // declares, to a PDF that can be handed out as is.
+        routes.get(
+            "/refs/:refId/notebook.pdf",
+            asyncHandler(async (req, res) => {
+                const { refId } = req.params;
+                if (!(await this.db.hasRef(refId))) {
+                    res.sendStatus(404);
+                    return;
+                }
+                if (!(await this.mayAccess(req, refId, "viewer"))) {
+                    res.sendStatus(403);
+                    return;
+                }
+                let pdf: Buffer;
+                try {
+                    pdf = notebookToPdf(JSON.parse(await this.db.getAutosave(refId)));
+                } catch (e) {
+                    if (e instanceof ExportError) {
+                        res.status(422).json({ error: e.message });
+                        return;
+                    }
+                    throw e;
+                }
+                res.type("application/pdf").attachment(`${refId}.pdf`).send(pdf);
+            }),
+        );
+
+        routes.get(
+            "/refs/:refId/parameters.csv",
+            asyncHandler(async (req, res) => {
+                const { refId } = req.params;
+                if (!(await this.db.hasRef(refId))) {
+                    res.sendStatus(404);
+                    return;
+                }
+                if (!(await this.mayAccess(req, refId, "viewer"))) {
+                    res.sendStatus(403);
+                    return;
+                }
+                const content = AnalysisDocument.safeParse(await this.currentContent(refId));
+                if (!content.success) {
+                    res.status(400).json({ error: "Not an analysis document" });
+                    return;
+                }
+                // The names of the model's objects are only shown to those who may view it.
+                const modelRefId = content.data.modelRef.__extern__.refId;
+                const model =
+                    (await this.db.hasRef(modelRefId)) &&
+                    (await this.mayAccess(req, modelRefId, "viewer"))
+                        ? ModelDocument.safeParse(await this.currentContent(modelRefId)).data
+                        : undefined;
+                res.type("text/csv")
+                    .attachment(`${refId}-parameters.csv`)
+                    .send(formatCsv(parameterRows(content.data, model ?? null)));
+            }),
+        );
+
+        // Update the analysis parameters of a document from a CSV file, such as one
+        // exported above and edited in a spreadsheet, and save the result as a new
+        // snapshot. The update is checked, by the limits on documents and by the
+        // operator's plugins, before anything is saved or shown to collaborators.
+        routes.post(
+            "/refs/:refId/parameters.csv",
+            express.text({ type: "text/csv", limit: "5mb" }),
+            asyncHandler(async (req, res) => {
+                const { refId } = req.params;
+                if (!(await this.db.hasRef(refId))) {
+                    res.sendStatus(404);
+                    return;
+                }
+                if (!(await this.mayAccess(req, refId, "editor"))) {
+                    res.sendStatus(403);
+                    return;
+                }
+                if (await this.db.isReadOnly(refId)) {
+                    res.status(403).json({ error: "Document is read-only" });
+                    return;
+                }
+                if (typeof req.body !== "string") {
+                    res.status(400).json({ error: "Expected a CSV file" });
+                    return;
+                }
+                // Update the latest content, including live changes not yet autosaved.
+                await this.autosaves.get(refId);
+                let updated: DocumentContent | undefined;
+                let witnessId: number;
+                try {
+                    const rows = parseCsv(req.body);
+                    witnessId = await this.db.saveRef(
+                        refId,
+                        "Imported parameters from CSV",
+                        async (stored) => {
+                            const content = AnalysisDocument.safeParse(stored);
+                            if (!content.success) {
+                                throw new ParameterError("Not an analysis document");
+                            }
+                            const result = applyParameterRows(content.data, rows);
+                            const violation = jsonLimitViolation(result);
+                            if (violation) {
+                                throw new trpc.TRPCError({
+                                    code: "PAYLOAD_TOO_LARGE",
+                                    message: violation,
+                                });
+                            }
+                            updated = await this.runSavePlugins(DocumentContent.parse(result));
+                            return updated;
+                        },
+                    );
+                } catch (e) {
+                    if (e instanceof CsvError || e instanceof ParameterError) {
+                        res.status(400).json({ error: e.message });
+                        return;
+                    }
+                    if (e instanceof trpc.TRPCError) {
+                        res.status(getHTTPStatusCodeFromError(e)).json({ error: e.message });
+                        return;
+                    }
+                    throw e;
+                }
+                if (updated) {
+                    this.replaceLiveContent(refId, updated);
+                }
+                res.json({ witnessId });
+            }, "bulk"),
+        );
+
+        // Export and import the whole database, to move an instance to another server
+        // or restore it from a backup. Only available when `ADMIN_TOKEN` is set.
+        routes.get(
+            "/admin/instance",
+            asyncHandler(async (req, res) => {
+                if (!isAdmin(req)) {
+                    res.sendStatus(403);
+                    return;
+                }
+                const archive = await this.db.exportInstance();
+                res.attachment(`catcolab-${archive.exportedAt.slice(0, 10)}.json`).json(archive);
+            }, "bulk"),
+        );
+
+        routes.post(
+            "/admin/instance",
+            express.json({ limit: "1gb" }),
+            asyncHandler(async (req, res) => {
+                if (!isAdmin(req)) {
+                    res.sendStatus(403);
+                    return;
+                }
+                const archive = InstanceArchive.safeParse(req.body);
+                if (!archive.success) {
+                    res.status(400).json({ error: "Not an instance archive" });
+                    return;
+                }
+                let imported: Record<string, number>;
+                try {
+                    imported = await this.db.importInstance(archive.data);
+                } catch (e) {
+                    if (e instanceof ArchiveError) {
+                        res.status(409).json({ error: e.message });
+                        return;
+                    }
+                    throw e;
+                }
+                // The live system documents were replaced by those of the archive,
+                // which are upgraded if they are older than the bundled ones.
+                for (const refId of [...this.docMap.keys()]) {
+                    this.evict(refId, false);
+                }
+                for (const retired of this.retired.values()) {
+                    retired.resumable = false;
+                }
+                await seedSystemDocuments(this.db, await loadSystemDocuments());
+                res.json({ imported });
+            }, "bulk"),
+        );
+
+        // Remove content, such as an accidentally pasted secret, from the whole
+        // history of a ref. Available to the owner of the ref when authentication is
+        // enabled, and to the administrator when `ADMIN_TOKEN` is set.
+        routes.post(
+            "/admin/refs/:refId/redact",
+            express.json(),
+            asyncHandler(async (req, res) => {
+                const { refId } = req.params;
+                if (!(await this.db.hasRef(refId))) {
+                    res.sendStatus(404);
+                    return;
+                }
+                const isOwner = this.auth !== null && (await this.mayAccess(req, refId, "owner"));
+                if (!(isAdmin(req) || isOwner)) {
+                    res.sendStatus(403);
+                    return;
+                }
+                const redaction = Redaction.safeParse(req.body);
+                if (!redaction.success) {
+                    res.status(400).json({ error: redaction.error.issues[0]?.message });
+                    return;
+                }
+                const snapshots = await this.redact(refId, redaction.data);
+                res.json({ snapshots });
+            }, "bulk"),
+        );
+
+        // Webhook for the source of a mirror to announce a change. Sources authenticate
+        // with `MIRROR_WEBHOOK_SECRET`, and the administrator with `ADMIN_TOKEN`.
+        routes.post(
+            "/mirrors/:refId/refresh",
+            asyncHandler(async (req, res) => {
+                const { refId } = req.params;
+                if (!(isAdmin(req) || bearsSecret(req, process.env.MIRROR_WEBHOOK_SECRET))) {
+                    res.sendStatus(403);
+                    return;
+                }
+                if (!(await this.db.getMirror(refId))) {
+                    res.sendStatus(404);
+                    return;
+                }
+                try {
+                    res.json({ changed: await this.federation.refresh(refId) });
+                } catch (e) {
+                    res.status(502).json({ error: e instanceof Error ? e.message : String(e) });
+                }
+            }),
+        );
+
+        // Sync over HTTP long polling, for peers behind proxies that block WebSockets.
+        // A peer joins, authenticating as it would for procedures, and then sends and
+        // polls for CBOR-encoded messages with the ID of its session. A session that
+        // has ended, for instance because it synced a retired document, is gone.
+        routes.post(
+            "/poll/v1/join",
+            express.json(),
+            asyncHandler(async (req, res) => {
+                const join = LongPollingJoin.safeParse(req.body);
+                if (!join.success) {
+                    res.status(400).json({ error: join.error.issues[0]?.message });
+                    return;
+                }
+                let user: string | null;
+                try {
+                    user = await this.authenticate(bearerToken(req.get("authorization")));
+                } catch (e) {
+                    if (e instanceof AuthError) {
+                        res.status(401).json({ error: e.message });
+                        return;
+                    }
+                    throw e;
+                }
+                const { peerId, peerMetadata } = join.data;
+                const joined = this.polling.join(
+                    peerId as A.PeerId,
+                    peerMetadata as A.PeerMetadata,
+                    user,
+                    (documentId) => this.maySync(user, documentId as A.DocumentId),
+                );
+                if (!joined) {
+                    res.status(409).json({ error: "Peer already has a sync session" });
+                    return;
+                }
+                res.json(joined);
+            }),
+        );
+
+        routes.post(
+            "/poll/v1/:sessionId",
+            express.raw({
+                type: "application/octet-stream",
+                limit: this.polling.options.floodControl.maxMessageBytes,
+            }),
+            asyncHandler(async (req, res) => {
+                let messages: unknown;
+                try {
+                    messages = Buffer.isBuffer(req.body) && A.cbor.decode(req.body);
+                } catch {
+                    messages = undefined;
+                }
+                if (!Array.isArray(messages)) {
+                    res.status(400).json({ error: "Expected CBOR-encoded messages" });
+                    return;
+                }
+                switch (this.polling.deliver(req.params.sessionId, messages)) {
+                    case "accepted":
+                        res.sendStatus(204);
+                        break;
+                    case "unknown-session":
+                        res.sendStatus(410);
+                        break;
+                    case "retired":
+                        res.status(410).json({ error: "Document was retired" });
+                        break;
+                    case "flooded":
+                        res.status(429).json({ error: "Too many messages" });
+                        break;
+                }
+            }),
+        );
+
+        routes.get(
+            "/poll/v1/:sessionId",
+            asyncHandler(async (req, res) => {
+                const ack = Number(req.query.ack ?? 0);
+                if (!Number.isSafeInteger(ack) || ack < 0) {
+                    res.status(400).json({ error: "Invalid acknowledgement" });
+                    return;
+                }
+                const { sessionId } = req.params;
+                const result = await this.polling.poll(sessionId, ack, abandonedSignal(res));
+                if (!result) {
+                    res.sendStatus(410);
+                    return;
+                }
+                // Proxies must not answer a poll with an earlier one.
+                res.set("Cache-Control", "no-store")
+                    .type("application/octet-stream")
+                    .send(Buffer.from(A.cbor.encode(result)));
+            }),
+        );
+
+        routes.delete("/poll/v1/:sessionId", (req, res) => {
+            this.polling.leave(req.params.sessionId);
+            res.sendStatus(204);
+        });
+
+        routes.use("/", (req, res, next) => {
+            const deprecation = findDeprecation(req.path, unversioned);
+            if (deprecation) {
+                setDeprecationHeaders(res, deprecation);
+            }
+            next();
+        });
+
+        routes.use(
             "/",
             trpcExpress.createExpressMiddleware({
                 router: this.appRouter,
+                createContext: async ({ req, res }) => {
+                    // The admin token is not an ID token, so it identifies no user.
+                    const token = isAdmin(req) ? undefined : bearerToken(req.get("authorization"));
+                    const user = await this.authenticate(token).catch(authenticationFailed);
+                    return { req, signal: abandonedSignal(res), user };
+                },
             }),
         );
 
-        this.server = this.app.listen(port);
+        this.app.use(basePath || "/", routes);
 
-        this.wss = new ws.WebSocketServer({
-            noServer: true,
-        });
+        Sentry.setupExpressErrorHandler(this.app);
+
+        this.syncNamespaces = new SyncNamespaces(basePath);
+
+        this.sessions = new SyncSessions((documentId, session) =>
+            this.lastPeerLeft(documentId, session),
+        );
+
+        const adapters = [...this.syncNamespaces.adapters(), this.polling];
+        for (const adapter of adapters) {
+            this.sessions.attach(adapter);
+        }
 
         const config = {
-            network: [new NodeWSServerAdapter(this.wss)],
+            network: adapters,
             sharePolicy: async () => false,
         };
 
         this.repo = new A.Repo(config);
 
-        this.server.on("upgrade", (request, socket, head) => {
-            this.wss.handleUpgrade(request, socket, head, (socket) => {
-                this.wss.emit("connection", socket, request);
-            });
+        this.servers = getListenAddresses(port).map((address) => this.listen(address));
+    }
+
+    /** Start an HTTP server for the app on the given address.
+
+    If the server fails, for instance because the address is in use, it is
+    restarted with backoff up to `MAX_LISTEN_RESTARTS` times in a row, after which
+    the whole server is shut down instead of running without the listener. A server
+    on a socket inherited from the service manager is shut down at once, since the
+    socket is closed along with the failed server.
+     */
+    listen(address: ListenAddress): http.Server {
+        const server = http.createServer(this.app);
+
+        // Browsers cannot set headers on WebSocket connections, so sync peers pass
+        // their ID token as the `token` query parameter.
+        server.on("upgrade", (request, socket, head) => {
+            if (!this.auth) {
+                this.syncNamespaces.handleUpgrade(request, socket, head, (documentId) =>
+                    this.maySync(null, documentId as A.DocumentId),
+                );
+                return;
+            }
+            const { searchParams } = new URL(request.url ?? "/", "http://localhost");
+            this.authenticate(searchParams.get("token") ?? undefined).then(
+                (user) =>
+                    this.syncNamespaces.handleUpgrade(
+                        request,
+                        socket,
+                        head,
+                        (documentId) => this.maySync(user, documentId as A.DocumentId),
+                        user,
+                    ),
+                () => socket.end("HTTP/1.1 401 Unauthorized\r\n\r\n"),
+            );
+        });
+
+        let restarts = 0;
+        server.on("listening", () => {
+            // Only consecutive failures count towards giving up.
+            restarts = 0;
+            if ("path" in address) {
+                fs.chmodSync(address.path, 0o660);
+            }
+            const basePath = this.syncNamespaces.basePath;
+            console.log(`server running on ${describeAddress(address)}${basePath}`);
         });
 
-        this.server.on("listening", () => {
-            console.log(`server running on port ${port}`);
+        server.on("error", (e) => {
+            console.error(`server on ${describeAddress(address)} failed:`, e);
+            if (this.closing) {
+                return;
+            }
+            // Closing the server closes an inherited socket, which cannot be bound again.
+            if ("fd" in address || restarts >= MAX_LISTEN_RESTARTS) {
+                console.error("giving up on restarting the server, shutting down");
+                process.exitCode = 1;
+                this.close();
+                return;
+            }
+            restarts++;
+            server.close();
+            setTimeout(() => {
+                if (!this.closing) {
+                    bindServer(server, address);
+                }
+            }, 1000 * 2 ** restarts);
         });
+
+        bindServer(server, address);
+        return server;
     }
 
     setHandleCallback(refId: string, handle: A.DocHandle<unknown>) {
         handle.on("change", async (payload) => {
-            this.db.autosaveWithExterns(refId, payload.doc);
+            this.consistency.touch(refId);
+            this.unsnapshotted.add(refId);
+            const doc = payload.doc;
+            // A change over the limits has already been applied in memory, so it is
+            // rolled back instead of saved.
+            const violation = jsonLimitViolation(doc);
+            if (violation) {
+                this.queueLiveTask(refId, () => this.rejectLiveChange(refId, handle, violation));
+            } else {
+                this.queueAutosave(refId, doc);
+            }
         });
     }
 
+    /** Autosave the content of a live document after its pending autosaves. */
+    private queueAutosave(refId: string, doc: unknown) {
+        this.queueLiveTask(refId, () => withoutQueryLimits(() => this.autosaveLive(refId, doc)));
+    }
+
+    private queueLiveTask(refId: string, task: () => unknown) {
+        // Save changes in order, so that each save can check that it follows the last.
+        // Changes may be made while handling a request, but saving them outlives it.
+        const previous = this.autosaves.get(refId) ?? Promise.resolve();
+        this.autosaves.set(
+            refId,
+            previous
+                .then(task)
+                .catch((e) => console.error(`Failed to autosave ref ${refId}:`, e)),
+        );
+    }
+
+    /** Autosave the content of a live document, unless another writer got there first.
+
+    If the autosave was advanced by some other writer since the live document last
+    agreed with it, the two have diverged and the conflict must be resolved with
+    `resolveConflict`. Until then, live changes are only recorded with the conflict.
+     */
+    async autosaveLive(refId: string, doc: unknown) {
+        if (this.consistency.conflicts.has(refId)) {
+            await this.consistency.markConflict(refId, doc);
+            return;
+        }
+        const expected = this.consistency.syncedContent(refId);
+        if (await this.db.autosaveWithExterns(refId, doc, expected)) {
+            await this.consistency.markSynced(refId, JSON.stringify(doc));
+        } else {
+            await this.consistency.markConflict(refId, doc);
+        }
+    }
+
+    /** Roll back a change to a live document that exceeds the limits on documents.
+
+    The document is reset to the content that was last autosaved, and its clients
+    are told why their change was undone. A document in conflict has no such
+    content to return to, so the change is left unsaved until the conflict is
+    resolved.
+     */
+    rejectLiveChange(refId: string, handle: A.DocHandle<unknown>, violation: string) {
+        console.error(`Rejecting change to ref ${refId}: ${violation}`);
+        const warning: LimitExceededWarning = { type: "limit-exceeded", refId, violation };
+        handle.broadcast(warning);
+        const synced = this.consistency.syncedContent(refId);
+        const isCurrent = this.docMap.get(refId) === handle;
+        if (synced !== undefined && isCurrent && !this.consistency.conflicts.has(refId)) {
+            this.replaceLiveContent(refId, JSON.parse(synced));
+        }
+    }
+
+    /** Snapshot a document when its last collaborator disconnects, then evict it.
+
+    Unsaved live changes are flushed first, and a snapshot is only taken if the
+    document changed since it was loaded or last snapshotted this way. The
+    document is then saved with its history, and evicted from memory if no one
+    rejoins it within `EVICTION_DELAY_MS`. Documents with an unresolved conflict
+    are kept in memory, since their live content is not saved anywhere else.
+
+    Sessions in which someone edited the document are recorded for analytics.
+     */
+    async lastPeerLeft(documentId: A.DocumentId, session: CollabSession) {
+        const refId = [...this.docMap].find(([_, handle]) => handle.documentId === documentId)?.[0];
+        if (refId === undefined) {
+            return;
+        }
+        if (session.participants.some((participant) => participant.edits > 0)) {
+            this.db
+                .recordCollabSession(refId, session)
+                .catch((e) => console.error(`Failed to record session on ref ${refId}:`, e));
+        }
+        try {
+            await this.autosaves.get(refId);
+            if (this.unsnapshotted.delete(refId) && !this.consistency.conflicts.has(refId)) {
+                await this.db.saveRef(refId, "Last collaborator disconnected");
+            }
+        } catch (e) {
+            console.error(`Failed to snapshot ref ${refId} after last collaborator left:`, e);
+            return;
+        }
+        await this.saveLiveDocument(refId, documentId).catch((e) =>
+            console.error(`Failed to save the history of ref ${refId}:`, e),
+        );
+        setTimeout(() => {
+            if (this.isIdle(refId, documentId)) {
+                this.retire(refId, documentId).catch((e) =>
+                    console.error(`Failed to evict ref ${refId}:`, e),
+                );
+            }
+        }, EVICTION_DELAY_MS).unref();
+    }
+
+    /** Whether a live document has no collaborators and nothing left to save. */
+    private isIdle(refId: string, documentId: A.DocumentId): boolean {
+        return (
+            this.docMap.get(refId)?.documentId === documentId &&
+            this.sessions.peerCount(documentId) === 0 &&
+            !this.unsnapshotted.has(refId) &&
+            !this.consistency.conflicts.has(refId)
+        );
+    }
+
+    /** Save an idle live document with its history, then evict it. */
+    private async retire(refId: string, documentId: A.DocumentId) {
+        if ((await this.saveLiveDocument(refId, documentId)) && this.isIdle(refId, documentId)) {
+            this.evict(refId);
+        }
+    }
+
+    /** Save a live document with its history, for when it is next loaded.
+
+    A document in conflict is not saved, since its content is not its autosave.
+    Its content is saved with the conflict instead.
+     */
+    private async saveLiveDocument(refId: string, documentId: A.DocumentId): Promise<boolean> {
+        const content = this.consistency.syncedContent(refId);
+        if (content === undefined || this.consistency.conflicts.has(refId)) {
+            return false;
+        }
+        const binary = await this.repo.export(documentId);
+        if (!binary) {
+            return false;
+        }
+        await this.db.saveLiveDocument(refId, documentId, binary, content);
+        return true;
+    }
+
+    /** Drop a live document from memory.
+
+    Clients may still be syncing the document. If it is `resumable`, the first of
+    them to sync it again makes it live again, and otherwise they are disconnected.
+     */
+    private evict(refId: string, resumable = true) {
+        const handle = this.docMap.get(refId);
+        this.docMap.delete(refId);
+        this.autosaves.delete(refId);
+        this.unsnapshotted.delete(refId);
+        this.consistency.forget(refId);
+        if (handle) {
+            // The grants are kept so that the clients may resume syncing.
+            if (!resumable) {
+                this.grants.delete(handle.documentId);
+            }
+            this.retired.set(handle.documentId, { refId, resumable });
+            this.repo.delete(handle.documentId);
+        }
+        if (!resumable) {
+            for (const retired of this.retired.values()) {
+                if (retired.refId === refId) {
+                    retired.resumable = false;
+                }
+            }
+        }
+    }
+
+    /** Make an evicted document live again, as synced by a client that still has it.
+
+    The client's copy is merged with the document saved on eviction, and then
+    autosaved if no one else wrote to the ref in between. Otherwise it conflicts
+    with the autosave, unless the two agree. If the client does not send the
+    document in time, it stays retired.
+     */
+    private resume(refId: string, documentId: A.DocumentId) {
+        this.retired.delete(documentId);
+        const resumption = this.resumeDocument(refId, documentId)
+            .catch((e) => {
+                console.error(`Failed to resume ref ${refId}:`, e);
+                if (this.docMap.get(refId)?.documentId !== documentId) {
+                    this.repo.delete(documentId);
+                    this.retired.set(documentId, { refId, resumable: true });
+                }
+            })
+            .finally(() => this.resumptions.delete(refId));
+        this.resumptions.set(refId, resumption);
+    }
+
+    private async resumeDocument(refId: string, documentId: A.DocumentId) {
+        const handle = this.repo.find<unknown>(documentId);
+        let timeout: NodeJS.Timeout | undefined;
+        const timedOut = new Promise<never>((_, reject) => {
+            timeout = setTimeout(
+                () => reject(new Error("Client did not send the document")),
+                RESUME_TIMEOUT_MS,
+            );
+        });
+        try {
+            await Promise.race([handle.whenReady(), timedOut]);
+        } finally {
+            clearTimeout(timeout);
+        }
+
+        const saved = await this.db.getLiveDocument(refId);
+        if (saved) {
+            const previous = this.repo.import(saved.binary);
+            handle.merge(previous);
+            this.repo.delete(previous.documentId);
+        }
+        const stored = await this.db.getAutosave(refId);
+        const conflict = await this.db.getConflict(refId);
+        const doc = handle.docSync();
+        this.docMap.set(refId, handle);
+
+        if (await this.db.isReadOnly(refId)) {
+            this.consistency.markSynced(refId, stored);
+            if (!sameContent(doc, stored)) {
+                this.replaceLiveContent(refId, JSON.parse(stored));
+            }
+            return;
+        }
+        this.setHandleCallback(refId, handle);
+        if (conflict) {
+            this.consistency.restoreConflict({ refId, detectedAt: conflict.detectedAt });
+            await this.consistency.markConflict(refId, doc);
+        } else if (sameContent(doc, stored)) {
+            await this.consistency.markSynced(refId, stored);
+        } else if (saved && sameContent(JSON.parse(saved.content), stored)) {
+            await this.consistency.markSynced(refId, stored);
+            this.unsnapshotted.add(refId);
+            this.queueAutosave(refId, doc);
+        } else {
+            await this.consistency.markConflict(refId, doc);
+        }
+    }
+
+    /** Redact the history of a ref, including that of its live document.
+
+    The Automerge history of a live document holds every edit ever made to it, so
+    the document is dropped and recreated from the redacted autosave when next
+    opened. Collaborators connected at the time must reload to see the redaction,
+    and their own copies of the history are beyond the reach of the server.
+     */
+    async redact(refId: string, redaction: Redaction): Promise<number> {
+        const redacted = this.redactLive(refId, redaction);
+        // Failures are the caller's to handle, not those waiting to reopen the ref.
+        const done = redacted
+            .catch(() => {})
+            .finally(() => {
+                if (this.redactions.get(refId) === done) {
+                    this.redactions.delete(refId);
+                }
+            });
+        this.redactions.set(refId, done);
+        return await redacted;
+    }
+
+    private async redactLive(refId: string, redaction: Redaction): Promise<number> {
+        // The document may not be reopened until the redaction is done, or it would
+        // be reopened from the history that is being dropped. One being opened or
+        // resumed already is evicted once it is live.
+        await this.openings.get(refId)?.catch(() => {});
+        await this.resumptions.get(refId);
+        const pending = this.autosaves.get(refId);
+        this.evict(refId, false);
+        await pending;
+        return await this.db.redactRef(refId, redaction);
+    }
+
+    /** Create a new ref whose initial content is the given document. */
+    async newRefWithContent(
+        content: DocumentContent,
+        owner: string | null = null,
+    ): Promise<string> {
+        const refId = await this.db.newRef(content.name, owner);
+        const handle = this.repo.create(content);
+        this.setHandleCallback(refId, handle);
+        this.docMap.set(refId, handle);
+        await this.db.autosaveWithExterns(refId, content);
+        this.consistency.markSynced(refId, JSON.stringify(content));
+        return refId;
+    }
+
     async getDocHandle(refId: string): Promise<A.DocHandle<unknown> | undefined> {
+        await this.resumptions.get(refId);
+        while (this.redactions.has(refId)) {
+            await this.redactions.get(refId);
+        }
         if (this.docMap.has(refId)) {
             return this.docMap.get(refId);
+        }
+        const opening =
+            this.openings.get(refId) ??
+            this.openDocHandle(refId).finally(() => this.openings.delete(refId));
+        this.openings.set(refId, opening);
+        return await opening;
+    }
+
+    /** Open the live document of a ref that is not in memory. */
+    private async openDocHandle(refId: string): Promise<A.DocHandle<unknown>> {
+        // A document that was in conflict is restored with its live content. The
+        // document saved on eviction keeps its history, but may be out of date.
+        const stored = await this.db.getAutosave(refId);
+        const conflict = await this.db.getConflict(refId);
+        const content = conflict?.content ?? stored;
+        const saved = await this.db.getLiveDocument(refId);
+        const handle = saved
+            ? this.repo.import<unknown>(saved.binary)
+            : this.repo.create(JSON.parse(content));
+        if (!sameContent(handle.docSync(), content)) {
+            overwriteContent(handle, JSON.parse(content));
+        }
+        if (conflict) {
+            this.consistency.restoreConflict({ refId, detectedAt: conflict.detectedAt });
         } else {
-            const content = JSON.parse(await this.db.getAutosave(refId));
-            const handle = this.repo.create(content);
+            this.consistency.markSynced(refId, stored);
+        }
+        // Mirrors and system documents are read-only: their content is only ever
+        // written by `Federation` and `seedSystemDocuments`, respectively.
+        if (!(await this.db.isReadOnly(refId))) {
             this.setHandleCallback(refId, handle);
-            this.docMap.set(refId, handle);
-            return handle;
+        }
+        this.docMap.set(refId, handle);
+        return handle;
+    }
+
+    /** Get the parsed content of a snapshot of a ref. */
+    async refSnapshot(refId: string, snapshotId: number): Promise<Json> {
+        await this.assertRef(refId);
+        const content = await this.db.getRefSnapshot(refId, snapshotId);
+        if (content === null) {
+            throw new trpc.TRPCError({
+                code: "NOT_FOUND",
+                message: `Snapshot ${snapshotId} is not part of ref ${refId}`,
+            });
+        }
+        return JSON.parse(content);
+    }
+
+    /** Record that a ref was opened, unless the client has opted out of tracking. */
+    recordView(refId: string, req: express.Request) {
+        if (optedOut(req.headers)) {
+            return;
+        }
+        const viewer = viewerId(req.ip ?? "", req.get("user-agent") ?? "");
+        this.db.recordView(refId, viewer).catch((e) => {
+            console.error(`Failed to record view of ref ${refId}:`, e);
+        });
+    }
+
+    /** Save the current content of a ref as the snapshot for an analysis to run on.
+
+    The document itself is not modified, and later edits do not affect the analysis.
+     */
+    async snapshotForAnalysis(refId: string): Promise<number> {
+        const content = await this.currentContent(refId);
+        return await this.db.saveSnapshot(JSON.stringify(content));
+    }
+
+    /** Run an analysis provided by a plugin, as a job.
+
+    The result is tagged with the parameters and snapshot that produced it, so that
+    the runs of a parameter sweep can be told apart.
+     */
+    async analysisJob(payload: Json): Promise<Json> {
+        const { kind, params, snapshotId } = AnalysisJob.parse(payload);
+        const snapshot = await this.db.getSnapshot(snapshotId);
+        if (snapshot === null) {
+            throw new PermanentJobError(`No such snapshot ${snapshotId}`);
+        }
+        const content = DocumentContent.parse(JSON.parse(snapshot));
+        const plugins = await this.plugins;
+        const result = await plugins.runAnalysis(kind, content, params).catch((e) => {
+            throw e instanceof PluginError ? new PermanentJobError(e.message) : e;
+        });
+        return { params, snapshotId, result: result as Json };
+    }
+
+    /** Get the current content of a ref, from its live document if there is one. */
+    async currentContent(refId: string): Promise<DocumentContent> {
+        await this.assertRef(refId);
+        const live = this.docMap.get(refId)?.docSync();
+        const content = live ?? JSON.parse(await this.db.getAutosave(refId));
+        const result = DocumentContent.safeParse(content);
+        if (!result.success) {
+            throw new trpc.TRPCError({
+                code: "UNPROCESSABLE_CONTENT",
+                message: `Ref ${refId} does not contain a CatColab document`,
+            });
+        }
+        return result.data;
+    }
+
+    /** Replace the content of a live document, if there is one.
+
+    The content should already have been autosaved, by whoever is replacing it.
+     */
+    replaceLiveContent(refId: string, content: DocumentContent) {
+        const handle = this.docMap.get(refId);
+        if (!handle) {
+            return;
+        }
+        this.consistency.markSynced(refId, JSON.stringify(content));
+        overwriteContent(handle, content);
+    }
+
+    /** Save a ref after running the operator's plugins on its current content.
+
+    The plugins run while the ref is locked, so that the content they accept is the
+    content that is saved. If a transformer changes the content, the new content is
+    saved and pushed to the live document.
+     */
+    async saveWithPlugins(refId: string, note: string): Promise<number> {
+        const plugins = await this.plugins;
+        if (plugins.plugins.length === 0) {
+            return await this.db.saveRef(refId, note);
+        }
+        let transformed: DocumentContent | undefined;
+        const witnessId = await this.db.saveRef(refId, note, async (stored) => {
+            const content = DocumentContent.parse(stored);
+            const result = await this.runSavePlugins(content);
+            if (JSON.stringify(result) !== JSON.stringify(content)) {
+                transformed = result;
+            }
+            return result;
+        });
+        if (transformed) {
+            this.replaceLiveContent(refId, transformed);
+        }
+        return witnessId;
+    }
+
+    /** Run the operator's plugins on content about to be saved, for a client. */
+    async runSavePlugins(content: DocumentContent): Promise<DocumentContent> {
+        const plugins = await this.plugins;
+        try {
+            return await plugins.onSave(content);
+        } catch (e) {
+            if (e instanceof PluginRejection) {
+                throw new trpc.TRPCError({ code: "BAD_REQUEST", message: e.message });
+            } else if (e instanceof PluginError) {
+                throw new trpc.TRPCError({ code: "INTERNAL_SERVER_ERROR", message: e.message });
+            }
+            throw e;
+        }
+    }
+
+    async assertRef(refId: string) {
+        if (!(await this.db.hasRef(refId))) {
+            throw new trpc.TRPCError({ code: "NOT_FOUND", message: `No such ref ${refId}` });
+        }
+    }
+
+    /** Check that a ref exists and that a user has a level of access to it.
+
+    Every user has full access to every ref when authentication is disabled.
+     */
+    async assertAccess(refId: string, user: string | null, level: AccessLevel) {
+        await this.assertRef(refId);
+        if (await this.canAccess(refId, user, level)) {
+            return;
+        }
+        throw new trpc.TRPCError({
+            code: user === null ? "UNAUTHORIZED" : "FORBIDDEN",
+            message: `No ${level} access to ref ${refId}`,
+        });
+    }
+
+    /** Whether a user has a level of access to a ref. */
+    async canAccess(refId: string, user: string | null, level: AccessLevel): Promise<boolean> {
+        return !this.auth || hasAccess(await this.db.accessLevel(refId, user), level);
+    }
+
+    /** Check that a user is signed in, if authentication is enabled. */
+    assertSignedIn(user: string | null) {
+        if (this.auth && user === null) {
+            throw new trpc.TRPCError({
+                code: "UNAUTHORIZED",
+                message: "Sign in to create documents",
+            });
+        }
+    }
+
+    /** Whether the user making an HTTP request has a level of access to a ref. */
+    async mayAccess(req: express.Request, refId: string, level: AccessLevel): Promise<boolean> {
+        if (!this.auth) {
+            return true;
+        }
+        try {
+            const user = await this.authenticate(bearerToken(req.get("authorization")));
+            return hasAccess(await this.db.accessLevel(refId, user), level);
+        } catch (e) {
+            if (e instanceof AuthError) {
+                return false;
+            }
+            throw e;
+        }
+    }
+
+    /** Identify the user who holds an ID token, if authentication is enabled.
+
+    Without a token, the user is anyone signed out, represented by null. Users are
+    recorded the first time that they are seen by this process.
+     */
+    async authenticate(token: string | undefined): Promise<string | null> {
+        if (!this.auth || token === undefined) {
+            return null;
+        }
+        const { sub, email } = await this.auth.verify(token);
+        if (!this.knownUsers.has(sub)) {
+            await this.db.upsertUser(sub, email ?? null);
+            this.knownUsers.add(sub);
+        }
+        return sub;
+    }
+
+    /** Give a user, or anyone if null, the ID of a live document to sync. */
+    grant(documentId: A.DocumentId, user: string | null, access: SyncAccess) {
+        let users = this.grants.get(documentId);
+        if (!users) {
+            users = new Map();
+            this.grants.set(documentId, users);
+        }
+        users.set(user, access);
+    }
+
+    /** Give a user, and anyone if the ref is public, the ID of its live document. */
+    async grantAccess(refId: string, documentId: A.DocumentId, user: string | null) {
+        const syncAccess = (level: AccessLevel | null): SyncAccess =>
+            hasAccess(level, "editor") ? "write" : "read";
+        const level = await this.db.accessLevel(refId, user);
+        if (level) {
+            this.grant(documentId, user, syncAccess(level));
+        }
+        const anyone = await this.db.accessLevel(refId, null);
+        if (anyone) {
+            this.grant(documentId, null, syncAccess(anyone));
+        }
+    }
+
+    /** How the peer of a user may sync a document, if at all.
+
+    Only the live documents of refs are restricted, to the users to whom their IDs
+    were given. Other documents, such as one that a client has just created for a
+    new ref, hold nothing of the server's. Evicted documents are either resumed or
+    retired, by `mayResume`.
+     */
+    maySync(user: string | null, documentId: A.DocumentId): SyncDecision {
+        const retired = this.retired.get(documentId);
+        if (retired) {
+            return this.mayResume(user, documentId, retired);
+        }
+        if (!this.auth) {
+            return "write";
+        }
+        const access = this.grantedAccess(user, documentId);
+        if (access !== undefined) {
+            return access;
+        }
+        const isLive = [...this.docMap.values()].some((handle) => handle.documentId === documentId);
+        return isLive ? null : "write";
+    }
+
+    /** Stop the affected users from syncing the live document of a ref.
+
+    Their connections that synced it are closed, and their peers may sync it again
+    only if they still have access when they next ask for it.
+     */
+    revokeSync(refId: string, affects: (user: string | null) => boolean) {
+        const handle = this.docMap.get(refId);
+        if (!handle) {
+            return;
+        }
+        const users = this.grants.get(handle.documentId);
+        for (const user of users?.keys() ?? []) {
+            if (affects(user)) {
+                users?.delete(user);
+            }
+        }
+        this.syncNamespaces.revoke(handle.documentId, affects);
+        this.polling.revoke(handle.documentId, affects);
+    }
+
+    /** Access given to the peer of a user by the grants of a document, if it has any. */
+    private grantedAccess(
+        user: string | null,
+        documentId: A.DocumentId,
+    ): SyncAccess | null | undefined {
+        const users = this.grants.get(documentId);
+        if (!users) {
+            return undefined;
+        }
+        const access = [users.get(user), users.get(null)];
+        return access.includes("write") ? "write" : access.includes("read") ? "read" : null;
+    }
+
+    /** How the peer of a user may sync an evicted document.
+
+    A client that was editing a document when it was evicted, for instance while
+    offline, makes it live again by syncing it, so that its edits are saved. The
+    document is retired instead if its ref was made live again under another ID in
+    the meantime, or if it may not be resumed at all, and the client must reload.
+    So must clients whose access was forgotten in a restart, and it is looked up
+    again for when they reconnect. Clients that may only read never resume.
+     */
+    private mayResume(
+        user: string | null,
+        documentId: A.DocumentId,
+        retired: RetiredDocument,
+    ): SyncDecision {
+        const { refId, resumable } = retired;
+        if (
+            !resumable ||
+            this.docMap.has(refId) ||
+            this.resumptions.has(refId) ||
+            this.redactions.has(refId)
+        ) {
+            return "retired";
+        }
+        const access = this.auth ? this.grantedAccess(user, documentId) : "write";
+        if (!access) {
+            this.grantAccess(refId, documentId, user).catch((e) =>
+                console.error(`Failed to look up access to ref ${refId}:`, e),
+            );
+            return "retired";
+        }
+        if (access === "read") {
+            return "retired";
+        }
+        this.resume(refId, documentId);
+        return access;
+    }
+
+    assertStorage(): ObjectStorage {
+        if (!this.storage) {
+            throw new trpc.TRPCError({
+                code: "PRECONDITION_FAILED",
+                message: "Attachments are not enabled on this server",
+            });
+        }
+        return this.storage;
+    }
+
+    async assertMirror(refId: string) {
+        if (!(await this.db.getMirror(refId))) {
+            throw new trpc.TRPCError({
+                code: "NOT_FOUND",
+                message: `Ref ${refId} is not a mirror`,
+            });
         }
     }
 
     async close() {
-        this.wss.close();
-        this.server.close();
+        this.closing = true;
+        this.federation.stop();
+        this.consistency.stop();
+        this.folder.stop();
+        await this.jobs.stop();
+        this.syncNamespaces.close();
+        this.polling.disconnect();
+        for (const server of this.servers) {
+            server.close();
+        }
+        // Keep the history of live documents across the restart.
+        await Promise.all(
+            [...this.docMap].map(async ([refId, handle]) => {
+                await this.autosaves.get(refId);
+                await this.saveLiveDocument(refId, handle.documentId).catch((e) =>
+                    console.error(`Failed to save the history of ref ${refId}:`, e),
+                );
+            }),
+        );
         await this.db.close();
     }
 }
+
+/** Report an invalid ID token as a failure to authenticate. */
+function authenticationFailed(e: unknown): never {
+    if (e instanceof AuthError) {
+        throw new trpc.TRPCError({ code: "UNAUTHORIZED", message: e.message });
+    }
+    throw e;
+}
+
+/** Report a failure to fetch a remote document as a client error. */
+function remoteFetchFailed(e: unknown): never {
+    if (e instanceof RemoteFetchError) {
+        throw new trpc.TRPCError({ code: "BAD_REQUEST", message: e.message });
+    }
+    throw e;
+}
+
+/** Replace the content of a live document, without saving it. */
+function overwriteContent(handle: A.DocHandle<unknown>, content: unknown) {
+    handle.change((doc) => {
+        const fields = doc as Record<string, unknown>;
+        for (const key of Object.keys(fields)) {
+            // biome-ignore lint/performance/noDelete: Automerge requires deleting keys
+            delete fields[key];
+        }
+        Object.assign(fields, content);
+    });
+}
+
+/** Key of the object holding the content of an attachment. */
+function attachmentKey(refId: string, id: number): string {
+    return `attachments/${refId}/${id}`;
+}
+
+/** Number of times a failed listener is restarted before the server shuts down. */
+const MAX_LISTEN_RESTARTS = 3;
+
+function bindServer(server: http.Server, address: ListenAddress) {
+    if ("fd" in address) {
+        server.listen({ fd: address.fd });
+    } else if ("path" in address) {
+        removeStaleSocket(address.path);
+        server.listen({ path: address.path });
+    } else {
+        server.listen({
+            host: address.host,
+            port: address.port,
+            ipv6Only: address.host?.includes(":"),
+        });
+    }
+}
+
+function describeAddress(address: ListenAddress): string {
+    if ("fd" in address) {
+        return `inherited socket ${address.fd}`;
+    } else if ("path" in address) {
+        return `unix:${address.path}`;
+    }
+    const where = address.host?.includes(":") ? `[${address.host}]` : address.host;
+    return `${where ?? "*"}:${address.port}`;
+}
+
+/** Remove a Unix domain socket left behind by a previous process, if any.
+
+Only sockets are removed, so that a misconfigured path cannot delete other files.
+ */
+function removeStaleSocket(path: string) {
+    try {
+        if (fs.statSync(path).isSocket()) {
+            fs.unlinkSync(path);
+        }
+    } catch (e) {
+        if ((e as NodeJS.ErrnoException).code !== "ENOENT") {
+            throw e;
+        }
+    }
+}
+
+/** Adapt an async request handler so that its failures reach Express.
+
+Its SQL statements are limited as for procedures of the given class, and those of
+GET requests are canceled if the client goes away.
+ */
+function asyncHandler(
+    f: (req: express.Request, res: express.Response) => Promise<void>,
+    statementClass: StatementClass = "interactive",
+): express.RequestHandler {
+    return (req, res, next) => {
+        const limits = {
+            timeoutMs: statementTimeouts[statementClass],
+            signal: req.method === "GET" ? abandonedSignal(res) : undefined,
+        };
+        withQueryLimits(limits, () => f(req, res)).catch((e) => {
+            const mapped = domainError(e);
+            if (mapped) {
+                res.status(getHTTPStatusCodeFromError(mapped)).json({ error: mapped.message });
+            } else {
+                next(e);
+            }
+        });
+    };
+}
+
+/** Check that a request bears the admin token. */
+function assertAdmin(req: express.Request) {
+    if (!isAdmin(req)) {
+        throw new trpc.TRPCError({
+            code: "FORBIDDEN",
+            message: "Only available to the administrator",
+        });
+    }
+}
+
+/** Whether a request bears the admin token, which must be configured. */
+function isAdmin(req: express.Request): boolean {
+    return bearsSecret(req, process.env.ADMIN_TOKEN);
+}
+
+/** Whether a request bears a secret as its bearer token, if the secret is set. */
+function bearsSecret(req: express.Request, secret: string | undefined): boolean {
+    const given = req.get("authorization")?.match(/^Bearer (.+)$/)?.[1];
+    if (!secret || !given) {
+        return false;
+    }
+    const [a, b] = [Buffer.from(secret), Buffer.from(given)];
+    return a.length === b.length && crypto.timingSafeEqual(a, b);
+}
+
+/** Signal that aborts if the connection closes before the response is complete. */
+function abandonedSignal(res: express.Response): AbortSignal {
+    const controller = new AbortController();
+    res.on("close", () => {
+        if (!res.writableFinished) {
+            controller.abort();
+        }
+    });
+    return controller.signal;
+}
@@ -0,0 +1,83 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import type * as A from "@automerge/automerge-repo";
+import { type CollabSession, SyncSessions, syncMessageChangeCount } from "./sessions.js";
+
+test("Sync sessions", async (_t) => {
+    const left: string[] = [];
+    const sessions = new SyncSessions((documentId) => left.push(documentId));
+    const [alice, bob] = ["alice", "bob"] as A.PeerId[];
+    const [doc1, doc2] = ["doc1", "doc2"] as A.DocumentId[];
+
+    await it("reports a document once its last peer leaves", () => {
+        sessions.join(alice, doc1);
+        sessions.join(bob, doc1);
+        sessions.join(bob, doc2);
+        assert.strictEqual(sessions.peerCount(doc1), 2);
+        sessions.leave(alice);
+        assert.deepStrictEqual(left, []);
+        sessions.leave(bob);
+        assert.deepStrictEqual(left, [doc1, doc2]);
+        assert.strictEqual(sessions.peerCount(doc1), 0);
+    });
+
+    await it("ignores peers that never joined a document", () => {
+        sessions.leave(alice);
+        assert.deepStrictEqual(left, [doc1, doc2]);
+    });
+
+    await it("summarizes a session when it ends", () => {
+        let time = 0;
+        const ended: CollabSession[] = [];
+        const timed = new SyncSessions((_, session) => ended.push(session), () => new Date(time));
+        timed.join(alice, doc1, 2);
+        time = 1000;
+        timed.join(bob, doc1, 1);
+        timed.join(alice, doc1, 3);
+        time = 3000;
+        timed.leave(bob);
+        time = 4000;
+        timed.leave(alice);
+
+        assert.strictEqual(ended.length, 1);
+        const [session] = ended;
+        assert.strictEqual(session.endedAt.getTime() - session.startedAt.getTime(), 4000);
+        assert.strictEqual(session.peakPeers, 2);
+        assert.strictEqual(session.meanPeers, 1.5);
+        assert.deepStrictEqual(
+            session.participants.map((p) => [p.peerId, p.edits, p.activeMs]),
+            [
+                [alice, 5, 4000],
+                [bob, 1, 2000],
+            ],
+        );
+    });
+
+    await it("counts the changes in sync messages", () => {
+        const hash = new Array(32).fill(7);
+        // Type, one head, no needs, one have with one hash and a 3-byte bloom filter,
+        // and two changes.
+        const message = new Uint8Array([
+            0x42,
+            1,
+            ...hash,
+            0,
+            1,
+            1,
+            ...hash,
+            3,
+            0,
+            0,
+            0,
+            2,
+            2,
+            0xaa,
+            0xbb,
+            1,
+            0xcc,
+        ]);
+        assert.strictEqual(syncMessageChangeCount(message), 2);
+        assert.strictEqual(syncMessageChangeCount(message.slice(0, 10)), 0);
+        assert.strictEqual(syncMessageChangeCount(new Uint8Array([0x85, 0x6f])), 0);
+    });
+});
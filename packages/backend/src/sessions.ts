@@ -0,0 +1,189 @@
+import type * as A from "@automerge/automerge-repo";
+
+/** Participation of one peer in a collaboration session.
+
+Peers are sync connections, such as a browser tab, not user accounts.
+ */
+export type Participant = {
+    peerId: A.PeerId;
+    joinedAt: Date;
+    leftAt: Date;
+
+    /** Time spent connected to the document, excluding any gaps. */
+    activeMs: number;
+
+    /** Number of Automerge changes sent by the peer. */
+    edits: number;
+};
+
+/** Period during which a document had at least one peer connected. */
+export type CollabSession = {
+    documentId: A.DocumentId;
+    startedAt: Date;
+    endedAt: Date;
+
+    /** Largest number of peers connected at once. */
+    peakPeers: number;
+
+    /** Average number of peers connected over the session. */
+    meanPeers: number;
+
+    participants: Participant[];
+};
+
+type ActiveParticipant = Omit<Participant, "leftAt"> & { connectedSince: Date | null };
+
+type ActiveSession = {
+    startedAt: Date;
+    peakPeers: number;
+    participants: Map<A.PeerId, ActiveParticipant>;
+};
+
+/** Tracks which sync peers are collaborating on which documents.
+
+A peer joins a document when it sends a message about it and leaves every
+document when it disconnects. When the last peer leaves a document, the
+`onLastPeerLeft` callback is called with its ID and a summary of the session
+that just ended.
+ */
+export class SyncSessions {
+    private peersByDoc = new Map<A.DocumentId, Set<A.PeerId>>();
+    private docsByPeer = new Map<A.PeerId, Set<A.DocumentId>>();
+    private sessions = new Map<A.DocumentId, ActiveSession>();
+
+    constructor(
+        readonly onLastPeerLeft: (documentId: A.DocumentId, session: CollabSession) => void,
+        readonly now: () => Date = () => new Date(),
+    ) {}
+
+    /** Follow the peers of a network adapter, alongside the repo that uses it. */
+    attach(adapter: A.NetworkAdapter) {
+        adapter.on("message", (message) => {
+            const { documentId } = message as { documentId?: A.DocumentId };
+            if (documentId) {
+                const { data } = message as { data?: Uint8Array };
+                const isSync = message.type === "sync" || message.type === "request";
+                const edits = isSync && data ? syncMessageChangeCount(data) : 0;
+                this.join(message.senderId, documentId, edits);
+            }
+        });
+        adapter.on("peer-disconnected", ({ peerId }) => this.leave(peerId));
+    }
+
+    /** Record a message from a peer about a document, carrying the given number of edits. */
+    join(peerId: A.PeerId, documentId: A.DocumentId, edits = 0) {
+        let peers = this.peersByDoc.get(documentId);
+        if (!peers) {
+            peers = new Set();
+            this.peersByDoc.set(documentId, peers);
+        }
+        peers.add(peerId);
+        let docs = this.docsByPeer.get(peerId);
+        if (!docs) {
+            docs = new Set();
+            this.docsByPeer.set(peerId, docs);
+        }
+        docs.add(documentId);
+
+        const now = this.now();
+        let session = this.sessions.get(documentId);
+        if (!session) {
+            session = { startedAt: now, peakPeers: 0, participants: new Map() };
+            this.sessions.set(documentId, session);
+        }
+        session.peakPeers = Math.max(session.peakPeers, peers.size);
+        let participant = session.participants.get(peerId);
+        if (!participant) {
+            participant = { peerId, joinedAt: now, activeMs: 0, edits: 0, connectedSince: now };
+            session.participants.set(peerId, participant);
+        }
+        participant.connectedSince ??= now;
+        participant.edits += edits;
+    }
+
+    leave(peerId: A.PeerId) {
+        const docs = this.docsByPeer.get(peerId) ?? new Set();
+        this.docsByPeer.delete(peerId);
+        const now = this.now();
+        for (const documentId of docs) {
+            const peers = this.peersByDoc.get(documentId);
+            peers?.delete(peerId);
+            const participant = this.sessions.get(documentId)?.participants.get(peerId);
+            if (participant?.connectedSince) {
+                participant.activeMs += now.getTime() - participant.connectedSince.getTime();
+                participant.connectedSince = null;
+            }
+            if (peers?.size === 0) {
+                this.peersByDoc.delete(documentId);
+                this.onLastPeerLeft(documentId, this.endSession(documentId, now));
+            }
+        }
+    }
+
+    private endSession(documentId: A.DocumentId, endedAt: Date): CollabSession {
+        const session = this.sessions.get(documentId);
+        this.sessions.delete(documentId);
+        const startedAt = session?.startedAt ?? endedAt;
+        const participants = [...(session?.participants.values() ?? [])].map(
+            ({ connectedSince: _, ...participant }) => ({ ...participant, leftAt: endedAt }),
+        );
+        const durationMs = endedAt.getTime() - startedAt.getTime();
+        const presenceMs = participants.reduce((total, p) => total + p.activeMs, 0);
+        return {
+            documentId,
+            startedAt,
+            endedAt,
+            peakPeers: session?.peakPeers ?? 0,
+            meanPeers: durationMs > 0 ? presenceMs / durationMs : participants.length,
+            participants,
+        };
+    }
+
+    /** Number of peers currently collaborating on a document. */
+    peerCount(documentId: A.DocumentId): number {
+        return this.peersByDoc.get(documentId)?.size ?? 0;
+    }
+}
+
+/** Number of changes carried by an encoded Automerge sync message.
+
+A sync message consists of a type byte, the sender's heads, the hashes it needs,
+its bloom filters of what it has, and then the changes themselves, each list
+prefixed with its length as an unsigned LEB128 integer. Returns zero for
+messages that cannot be parsed.
+ */
+export function syncMessageChangeCount(data: Uint8Array): number {
+    let offset = 1;
+    const uint = (): number => {
+        let result = 0;
+        for (let shift = 0; ; shift += 7) {
+            const byte = data[offset++];
+            if (byte === undefined || shift > 28) {
+                throw new RangeError("Truncated sync message");
+            }
+            result += (byte & 0x7f) * 2 ** shift;
+            if (byte < 0x80) {
+                return result;
+            }
+        }
+    };
+    const skipHashes = () => {
+        offset += uint() * 32;
+    };
+    try {
+        if (data[0] !== 0x42 && data[0] !== 0x43) {
+            return 0;
+        }
+        skipHashes(); // heads
+        skipHashes(); // need
+        const haves = uint();
+        for (let i = 0; i < haves; i++) {
+            skipHashes(); // last sync
+            offset += uint(); // bloom filter
+        }
+        const changes = uint();
+        return offset <= data.length ? changes : 0;
+    } catch {
+        return 0;
+    }
+}
@@ -0,0 +1,110 @@
+import assert from "node:assert";
+import { EventEmitter } from "node:events";
+import { it, test } from "node:test";
+import { cbor } from "@automerge/automerge-repo";
+import type * as ws from "ws";
+import {
+    SyncNamespaces,
+    restrictDocuments,
+    retiredDocumentCloseCode,
+    revokedAccessCloseCode,
+} from "./sync_namespaces.js";
+
+test("Sync namespaces", async (_t) => {
+    const namespaces = new SyncNamespaces("", ["v1", "v2"]);
+
+    await it("routes versioned paths to their namespace", () => {
+        assert.strictEqual(namespaces.versionFor("/v1"), "v1");
+        assert.strictEqual(namespaces.versionFor("/v2/"), "v2");
+    });
+
+    await it("routes unversioned clients to the legacy namespace", () => {
+        assert.strictEqual(namespaces.versionFor("/"), "v1");
+    });
+
+    await it("rejects unknown versions", () => {
+        assert.strictEqual(namespaces.versionFor("/v3"), undefined);
+        assert.strictEqual(namespaces.versionFor("/v1/extra"), undefined);
+    });
+
+    await it("routes paths under the base path", () => {
+        const prefixed = new SyncNamespaces("/catcolab", ["v1"]);
+        assert.strictEqual(prefixed.versionFor("/catcolab/v1"), "v1");
+        assert.strictEqual(prefixed.versionFor("/catcolab"), "v1");
+        assert.strictEqual(prefixed.versionFor("/v1"), undefined);
+        assert.strictEqual(prefixed.versionFor("/catcolabs/v1"), undefined);
+        prefixed.close();
+    });
+
+    await it("drops messages about documents that may not be synced", () => {
+        const socket = new EventEmitter() as unknown as ws.WebSocket;
+        const received: unknown[] = [];
+        socket.on("message", (data) => received.push(cbor.decode(data as Uint8Array)));
+        restrictDocuments(socket, (documentId) => (documentId === "public" ? "write" : null));
+
+        const send = (message: object) => socket.emit("message", cbor.encode(message), true);
+        send({ type: "join", senderId: "peer" });
+        send({ type: "request", senderId: "peer", documentId: "private" });
+        send({ type: "request", senderId: "peer", documentId: "public" });
+        assert.deepStrictEqual(
+            received.map((message) => (message as { type: string }).type),
+            ["join", "request"],
+        );
+        assert.strictEqual((received[1] as { documentId: string }).documentId, "public");
+    });
+
+    await it("drops changes sent by peers that may only read a document", () => {
+        const socket = new EventEmitter() as unknown as ws.WebSocket;
+        const received: unknown[] = [];
+        socket.on("message", (data) => received.push(cbor.decode(data as Uint8Array)));
+        restrictDocuments(socket, () => "read");
+
+        // Sync messages with no heads, needs, or haves, and then a list of changes.
+        const withChanges = (count: number) => new Uint8Array([0x42, 0, 0, 0, count]);
+        const send = (data: Uint8Array) => {
+            const message = { type: "sync", senderId: "peer", documentId: "doc", data };
+            socket.emit("message", cbor.encode(message), true);
+        };
+        send(withChanges(0));
+        send(withChanges(1));
+        assert.strictEqual(received.length, 1);
+        assert.deepStrictEqual((received[0] as { data: Uint8Array }).data, withChanges(0));
+    });
+
+    await it("closes connections that sync retired documents", () => {
+        const socket = new EventEmitter() as unknown as ws.WebSocket;
+        const closed: unknown[] = [];
+        socket.close = (code?: number) => closed.push(code);
+        const received: unknown[] = [];
+        socket.on("message", (data) => received.push(data));
+        restrictDocuments(socket, () => "retired");
+
+        const message = { type: "request", senderId: "peer", documentId: "old" };
+        socket.emit("message", cbor.encode(message), true);
+        assert.strictEqual(received.length, 0);
+        assert.deepStrictEqual(closed, [retiredDocumentCloseCode]);
+    });
+
+    await it("closes connections of users whose access was revoked", () => {
+        const connect = (user: string) => {
+            const socket = new EventEmitter() as unknown as ws.WebSocket;
+            const closed: unknown[] = [];
+            socket.close = (code?: number) => closed.push(code);
+            namespaces.restrict(socket, user, () => "write");
+            return { socket, closed };
+        };
+        const alice = connect("alice");
+        const bob = connect("bob");
+        const message = { type: "request", senderId: "peer", documentId: "doc" };
+        alice.socket.emit("message", cbor.encode(message), true);
+        bob.socket.emit("message", cbor.encode({ ...message, documentId: "other" }), true);
+
+        namespaces.revoke("doc", (user) => user === "bob");
+        namespaces.revoke("other", (user) => user === "alice");
+        assert.deepStrictEqual([alice.closed, bob.closed], [[], []]);
+        namespaces.revoke("doc", (user) => user === "alice");
+        assert.deepStrictEqual(alice.closed, [revokedAccessCloseCode]);
+    });
+
+    namespaces.close();
+});
@@ -0,0 +1,219 @@
+import type * as http from "node:http";
+import type * as stream from "node:stream";
+import { cbor } from "@automerge/automerge-repo";
+import { NodeWSServerAdapter } from "@automerge/automerge-repo-network-websocket";
+import * as ws from "ws";
+import {
+    type FloodControlOptions,
+    MessageRateLimiter,
+    defaultFloodControlOptions,
+    limitMessages,
+} from "./flood_control.js";
+import { syncMessageChangeCount } from "./sessions.js";
+
+/** Versions of the sync wire protocol served by the backend, oldest first.
+
+Each version is served on its own path, e.g. `/v1`, so that the protocol can
+change without breaking frontends that were loaded before a deploy. When a new
+version is added, the previous ones should be kept until no deployed frontend
+uses them.
+ */
+export const syncProtocolVersions = ["v1"];
+
+/** Access of a sync connection to a document.
+
+Automerge sync is symmetric, so a peer that may only read a document can still
+send changes to it. The changes of such a peer are dropped.
+ */
+export type SyncAccess = "read" | "write";
+
+/** How a sync connection may sync a document, if at all.
+
+A document is `"retired"` if the server no longer serves it under its ID, for
+instance because its history was redacted. The connection is then closed with
+`retiredDocumentCloseCode`, since its peer must load the document anew.
+ */
+export type SyncDecision = SyncAccess | "retired" | null;
+
+/** WebSocket close code for connections that sync a retired document. */
+export const retiredDocumentCloseCode = 4000;
+
+/** WebSocket close code for connections whose access to a document was revoked. */
+export const revokedAccessCloseCode = 4001;
+
+/** Version served to clients that connect without choosing one. */
+const legacyVersion = "v1";
+
+/** WebSocket servers for the versions of the sync protocol, routed by path.
+
+Paths are relative to the base path of the backend, if it has one. Every
+connection is subject to flood control, so that one misbehaving client cannot
+degrade a document for its other collaborators.
+ */
+export class SyncNamespaces {
+    readonly servers = new Map<string, ws.WebSocketServer>();
+
+    /** Users of the restricted connections, and the documents that they synced. */
+    private connections = new Map<ws.WebSocket, { user: string | null; documents: Set<string> }>();
+
+    constructor(
+        readonly basePath = "",
+        versions: string[] = syncProtocolVersions,
+        readonly floodControl: FloodControlOptions = defaultFloodControlOptions,
+    ) {
+        for (const version of versions) {
+            const maxPayload = floodControl.maxMessageBytes;
+            this.servers.set(version, new ws.WebSocketServer({ noServer: true, maxPayload }));
+        }
+    }
+
+    /** Network adapters for the Automerge repo, one per protocol version. */
+    adapters(): NodeWSServerAdapter[] {
+        return [...this.servers.values()].map((wss) => new NodeWSServerAdapter(wss));
+    }
+
+    /** Get the version of the sync protocol requested by a URL path, if any. */
+    versionFor(pathname: string): string | undefined {
+        if (!pathname.startsWith(this.basePath)) {
+            return undefined;
+        }
+        const relative = pathname.slice(this.basePath.length);
+        if (relative && !relative.startsWith("/")) {
+            return undefined;
+        }
+        const version = relative.replace(/^\/+|\/+$/g, "") || legacyVersion;
+        return this.servers.has(version) ? version : undefined;
+    }
+
+    /** Route an HTTP upgrade request to the server for its protocol version.
+
+    If given, `maySync` decides which documents the connection of the given user,
+    or of anyone signed out if null, may sync, and whether it may change them.
+     */
+    handleUpgrade(
+        request: http.IncomingMessage,
+        socket: stream.Duplex,
+        head: Buffer,
+        maySync?: (documentId: string) => SyncDecision,
+        user: string | null = null,
+    ) {
+        const { pathname } = new URL(request.url ?? "/", "http://localhost");
+        const version = this.versionFor(pathname);
+        const wss = version && this.servers.get(version);
+        if (!wss) {
+            socket.end("HTTP/1.1 404 Not Found\r\n\r\n");
+            return;
+        }
+        wss.handleUpgrade(request, socket, head, (socket) => {
+            limitMessages(socket, new MessageRateLimiter(this.floodControl));
+            if (maySync) {
+                this.restrict(socket, user, maySync);
+            }
+            wss.emit("connection", socket, request);
+        });
+    }
+
+    /** Restrict the documents that a connection may sync, remembering which ones it
+    synced on behalf of which user, so that its access to them can be revoked.
+     */
+    restrict(
+        socket: ws.WebSocket,
+        user: string | null,
+        maySync: (documentId: string) => SyncDecision,
+    ) {
+        const documents = new Set<string>();
+        this.connections.set(socket, { user, documents });
+        socket.on("close", () => this.connections.delete(socket));
+        restrictDocuments(socket, (documentId) => {
+            documents.add(documentId);
+            return maySync(documentId);
+        });
+    }
+
+    /** Close the connections that synced a document on behalf of the affected users.
+
+    Their peers cannot be stopped from syncing one document only, so they must
+    connect again, after which they may only sync the documents they still may.
+     */
+    revoke(documentId: string, affects: (user: string | null) => boolean) {
+        for (const [socket, { user, documents }] of this.connections) {
+            if (documents.has(documentId) && affects(user)) {
+                socket.close(revokedAccessCloseCode, "Access was revoked");
+            }
+        }
+    }
+
+    close() {
+        for (const wss of this.servers.values()) {
+            wss.close();
+        }
+    }
+}
+
+/** Drop the messages of a sync connection about documents that it may not sync.
+
+The server only sends a document to peers that ask for it, so a peer that cannot
+ask cannot read it either. A peer with read access may ask, but its messages that
+carry changes are dropped. A connection that syncs a retired document is closed.
+Messages that cannot be decoded are passed on, for the network adapter to reject.
+ */
+export function restrictDocuments(
+    socket: ws.WebSocket,
+    maySync: (documentId: string) => SyncDecision,
+) {
+    const emit = socket.emit.bind(socket);
+    socket.emit = ((event: string | symbol, ...args: unknown[]) => {
+        if (event === "message") {
+            const message = decodeMessage(args[0]);
+            const verdict = message ? screenMessage(message, maySync) : "accept";
+            if (verdict === "retired") {
+                socket.close(retiredDocumentCloseCode, "Document was retired");
+                return false;
+            }
+            if (verdict === "drop") {
+                return false;
+            }
+        }
+        return emit(event, ...args);
+    }) as typeof socket.emit;
+}
+
+/** Decide whether a decoded sync message may pass, given the documents that its
+connection may sync. Messages about no document in particular always pass.
+ */
+export function screenMessage(
+    message: object,
+    maySync: (documentId: string) => SyncDecision,
+): "accept" | "drop" | "retired" {
+    const { documentId } = message as Message;
+    if (typeof documentId !== "string") {
+        return "accept";
+    }
+    const access = maySync(documentId);
+    if (access === "retired") {
+        return "retired";
+    }
+    if (access === null || (access === "read" && carriesChanges(message as Message))) {
+        return "drop";
+    }
+    return "accept";
+}
+
+type Message = { type?: unknown; documentId?: unknown; data?: unknown };
+
+function decodeMessage(data: unknown): Message | undefined {
+    if (!(data instanceof Uint8Array)) {
+        return undefined;
+    }
+    try {
+        const message = cbor.decode(data) as Message | null;
+        return typeof message === "object" && message !== null ? message : undefined;
+    } catch {
+        return undefined;
+    }
+}
+
+function carriesChanges(message: Message): boolean {
+    const isSync = message.type === "sync" || message.type === "request";
+    return isSync && message.data instanceof Uint8Array && syncMessageChangeCount(message.data) > 0;
+}
@@ -0,0 +1,12 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { loadSystemDocuments } from "./system_documents.js";
+
+test("System documents", async (_t) => {
+    await it("bundled documents are valid and have unique slugs", async () => {
+        const docs = await loadSystemDocuments();
+        assert.ok(docs.length > 0);
+        const slugs = new Set(docs.map((doc) => doc.slug));
+        assert.strictEqual(slugs.size, docs.length);
+    });
+});
@@ -0,0 +1,75 @@
+import * as fs from "node:fs/promises";
+import * as path from "node:path";
+import { fileURLToPath } from "node:url";
+import * as z from "zod";
+
+import { DocumentContent, typedDocumentError } from "./document.js";
+import type { Persistence } from "./persistence.js";
+
+/** A read-only document shipped with the server, such as an example model.
+
+System documents are identified by a stable slug. Increasing the version
+upgrades the document in place when the server next starts, recording the
+previous content in its history.
+ */
+export const SystemDocument = z.object({
+    /// Stable identifier of the document
+    slug: z.string().regex(/^[a-z0-9-]+$/),
+    /// Version of the content, increased whenever the content changes
+    version: z.number().int().positive(),
+    /// Content of the document
+    content: DocumentContent,
+});
+
+export type SystemDocument = z.infer<typeof SystemDocument>;
+
+/** Directory of system documents bundled with the server. */
+const bundledDirectory = fileURLToPath(new URL("../system/", import.meta.url));
+
+/** Load the system documents in a directory, one JSON file per document. */
+export async function loadSystemDocuments(
+    dir: string = process.env.SYSTEM_DOCUMENTS_PATH || bundledDirectory,
+): Promise<SystemDocument[]> {
+    const files = (await fs.readdir(dir)).filter((file) => file.endsWith(".json")).sort();
+    const docs: SystemDocument[] = [];
+    for (const file of files) {
+        const text = await fs.readFile(path.join(dir, file), { encoding: "utf-8" });
+        const result = SystemDocument.safeParse(JSON.parse(text));
+        if (!result.success) {
+            throw new Error(`Invalid system document ${file}: ${result.error.message}`);
+        }
+        const typeError = typedDocumentError(result.data.content);
+        if (typeError) {
+            throw new Error(`Malformed system document ${file}: ${typeError}`);
+        }
+        docs.push(result.data);
+    }
+    return docs;
+}
+
+/** Create system documents that do not exist yet and upgrade outdated ones.
+
+Returns the refs whose content was replaced by an upgrade.
+ */
+export async function seedSystemDocuments(
+    db: Persistence,
+    docs: SystemDocument[],
+): Promise<Map<string, DocumentContent>> {
+    const existing = new Map((await db.allSystemDocuments()).map((doc) => [doc.slug, doc]));
+    const upgraded = new Map<string, DocumentContent>();
+    for (const { slug, version, content } of docs) {
+        const current = existing.get(slug);
+        if (!current) {
+            const refId = await db.newRef(content.name);
+            await db.autosaveWithExterns(refId, content);
+            await db.saveRef(refId, `system document, version ${version}`);
+            await db.newSystemDocument(slug, refId, version);
+        } else if (current.version < version) {
+            await db.autosaveWithExterns(current.ref, content);
+            await db.saveRef(current.ref, `system document, version ${version}`);
+            await db.setSystemDocumentVersion(slug, version);
+            upgraded.set(current.ref, content);
+        }
+    }
+    return upgraded;
+}
@@ -0,0 +1,32 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { ODESolution, downsample } from "./trajectory.js";
+
+test("Trajectories", async (_t) => {
+    const time = Array.from({ length: 101 }, (_, i) => i / 10);
+    const solution = {
+        time,
+        states: { x: time.map((t) => 2 * t), y: time.map((t) => -t) },
+    };
+
+    await it("keeps evenly spaced steps including the endpoints", () => {
+        const small = downsample(solution, 5);
+        assert.deepStrictEqual(small.time, [0, 2.5, 5, 7.5, 10]);
+        assert.deepStrictEqual(small.states.x, [0, 5, 10, 15, 20]);
+    });
+
+    await it("selects variables", () => {
+        const small = downsample(solution, 3, ["y", "z"]);
+        assert.deepStrictEqual(Object.keys(small.states), ["y"]);
+    });
+
+    await it("leaves short solutions alone", () => {
+        assert.deepStrictEqual(downsample(solution, 1000), solution);
+        const single = { time: [0], states: { x: [1] } };
+        assert.deepStrictEqual(downsample(single, 10), single);
+    });
+
+    await it("rejects ragged solutions", () => {
+        assert.ok(!ODESolution.safeParse({ time: [0, 1], states: { x: [0] } }).success);
+    });
+});
@@ -0,0 +1,41 @@
+import * as z from "zod";
+
+/** Solution of an ODE, as serialized by `catlog`'s `ODESolution`. */
+export const ODESolution = z
+    .object({
+        /// Values of time variable for the duration of the simulation
+        time: z.array(z.number()),
+        /// Values of state variables for the duration of the simulation
+        states: z.record(z.array(z.number())),
+    })
+    .refine((sol) => Object.values(sol.states).every((xs) => xs.length === sol.time.length), {
+        message: "State trajectories must have one value per time step",
+    });
+
+export type ODESolution = z.infer<typeof ODESolution>;
+
+/** Reduce an ODE solution to at most `maxPoints` time steps, for plotting.
+
+The time steps kept are evenly spaced by index and always include the first and
+last steps. If `variables` is given, only those state variables are kept.
+ */
+export function downsample(
+    solution: ODESolution,
+    maxPoints: number,
+    variables?: string[],
+): ODESolution {
+    const n = solution.time.length;
+    const m = Math.min(n, Math.max(2, maxPoints));
+    const indices = Array.from({ length: m }, (_, k) =>
+        m === 1 ? 0 : Math.round((k * (n - 1)) / (m - 1)),
+    );
+    const pick = (xs: number[]) => indices.map((i) => xs[i]);
+    const states: Record<string, number[]> = {};
+    for (const v of variables ?? Object.keys(solution.states)) {
+        const xs = solution.states[v];
+        if (xs) {
+            states[v] = pick(xs);
+        }
+    }
+    return { time: pick(solution.time), states };
+}
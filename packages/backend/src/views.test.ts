@@ -0,0 +1,19 @@
+import assert from "node:assert";
+import { it, test } from "node:test";
+import { optedOut, viewerId } from "./views.js";
+
+test("View tracking", async (_t) => {
+    await it("honors Do Not Track and Global Privacy Control", () => {
+        assert.ok(optedOut({ dnt: "1" }));
+        assert.ok(optedOut({ "sec-gpc": "1" }));
+        assert.ok(!optedOut({ dnt: "0" }));
+        assert.ok(!optedOut({}));
+    });
+
+    await it("identifies viewers anonymously", () => {
+        const id = viewerId("192.0.2.1", "Firefox");
+        assert.strictEqual(viewerId("192.0.2.1", "Firefox"), id);
+        assert.notStrictEqual(viewerId("192.0.2.2", "Firefox"), id);
+        assert.ok(!id.includes("192.0.2.1"));
+    });
+});
@@ -0,0 +1,40 @@
+import { createHash, randomBytes } from "node:crypto";
+import type * as http from "node:http";
+
+/** Whether a client has asked not to be tracked.
+
+Both the Do Not Track header and its successor, Global Privacy Control, are
+honored.
+ */
+export function optedOut(headers: http.IncomingHttpHeaders): boolean {
+    return headers["dnt"] === "1" || headers["sec-gpc"] === "1";
+}
+
+let salt = randomBytes(16);
+let saltDay = currentDay();
+
+function currentDay(): string {
+    return new Date().toISOString().slice(0, 10);
+}
+
+/** Anonymous identifier for the client making a request.
+
+The identifier is a hash of the client's address and user agent with a salt that
+is kept only in memory and replaced every day. Views from the same client can
+therefore be counted as one within a day, but cannot be linked across days or
+traced back to the client from the database.
+ */
+export function viewerId(address: string, userAgent: string): string {
+    const today = currentDay();
+    if (today !== saltDay) {
+        salt = randomBytes(16);
+        saltDay = today;
+    }
+    return createHash("sha256")
+        .update(salt)
+        .update(address)
+        .update("\0")
+        .update(userAgent)
+        .digest("hex")
+        .slice(0, 32);
+}
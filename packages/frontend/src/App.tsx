@@ -1,5 +1,4 @@
 import { Repo } from "@automerge/automerge-repo";
-import { BrowserWebSocketClientAdapter } from "@automerge/automerge-repo-network-websocket";
 import { IndexedDBStorageAdapter } from "@automerge/automerge-repo-storage-indexeddb";
 import * as trpc from "@trpc/client";
 import invariant from "tiny-invariant";
@@ -14,6 +13,7 @@ import { RPCContext, RepoContext } from "./api";
 import { newModelDocument } from "./document/types";
 import { HelperContainer, lazyMdx } from "./page/help_page";
 import { TheoryLibraryContext, stdTheories } from "./stdlib";
+import { syncNetworkAdapter } from "./util/sync_network";
 
 const serverUrl: string = import.meta.env.VITE_BACKEND_HOST;
 
@@ -21,7 +21,8 @@ const useHttps = serverUrl.match(/^https:\/\//)?.length === 1;
 const serverHost = serverUrl.replace(/^https?:\/\//, "");
 
 const httpUrl = `http${useHttps ? "s" : ""}://${serverHost}`;
-const wsUrl = `ws${useHttps ? "s" : ""}://${serverHost}`;
+const wsUrl = `ws${useHttps ? "s" : ""}://${serverHost}/v1`;
+const pollUrl = `${httpUrl}/poll/v1`;
 
 const Root = (props: RouteSectionProps<unknown>) => {
     invariant(serverHost, "Must set environment variable VITE_BACKEND_HOST");
@@ -36,7 +37,7 @@ const Root = (props: RouteSectionProps<unknown>) => {
 
     const repo = new Repo({
         storage: new IndexedDBStorageAdapter("catcolab-demo"),
-        network: [new BrowserWebSocketClientAdapter(wsUrl)],
+        network: [syncNetworkAdapter(import.meta.env.VITE_SYNC_TRANSPORT, wsUrl, pollUrl)],
     });
 
     return (
@@ -61,7 +62,7 @@ function CreateModel() {
     const doc = repo.create(init);
 
     const [ref] = createResource<string>(async () => {
-        return await client.newRef.mutate({ title: init.name, docId: doc.documentId });
+        return await client.v1.newRef.mutate({ title: init.name, docId: doc.documentId });
     });
 
     return (
@@ -32,7 +32,7 @@ export async function retrieveDoc<T extends object>(
     let docId: DocumentId;
 
     if (uuid.validate(ref)) {
-        const res = await client.docIdFor.query(ref);
+        const res = await client.v1.docIdFor.query(ref);
         if (!res) {
             throw new Error(`Failed to get document ID for ref ${ref}`);
         }
@@ -173,7 +173,7 @@ export function ModelDocumentEditor(props: {
 
     /* TODO: Restore this action once saving properly integrated into UI.
     const snapshotModel = () =>
-        client.saveRef.mutate({
+        client.v1.saveRef.mutate({
             refId: props.liveDoc.refId,
             note: "",
         });
@@ -184,7 +184,10 @@ export function ModelDocumentEditor(props: {
     const createAnalysis = async () => {
         const init = newAnalysisDocument(props.liveDoc.refId);
         const newDoc = repo.create(init);
-        const newRef = await client.newRef.mutate({ title: init.name, docId: newDoc.documentId });
+        const newRef = await client.v1.newRef.mutate({
+            title: init.name,
+            docId: newDoc.documentId,
+        });
 
         navigate(`/analysis/${newRef}`);
     };
@@ -0,0 +1,281 @@
+import {
+    type Message,
+    NetworkAdapter,
+    type PeerId,
+    type PeerMetadata,
+    cbor,
+} from "@automerge/automerge-repo";
+import { BrowserWebSocketClientAdapter } from "@automerge/automerge-repo-network-websocket";
+
+/** Network adapter for syncing with the backend over the configured transport.
+
+With `"auto"`, the default, documents are synced over a WebSocket if one can be
+opened, and otherwise over HTTP long polling. The transports `"websocket"` and
+`"long-polling"` force one or the other.
+ */
+export function syncNetworkAdapter(
+    transport: string | undefined,
+    wsUrl: string,
+    pollUrl: string,
+): NetworkAdapter {
+    switch (transport || "auto") {
+        case "websocket":
+            return new BrowserWebSocketClientAdapter(wsUrl);
+        case "long-polling":
+            return new LongPollingClientAdapter(pollUrl);
+        case "auto":
+            return new FallbackClientAdapter(wsUrl, pollUrl);
+        default:
+            throw new Error(`Unknown sync transport: ${transport}`);
+    }
+}
+
+/** Network adapter that syncs with the backend over HTTP long polling.
+
+Useful behind proxies that block WebSockets. The adapter joins a session on the
+backend, then sends messages and polls for those addressed to it, acknowledging
+each batch with the next poll. When the session is lost, for instance because
+the backend restarted, the server peer is disconnected and the adapter joins again.
+ */
+export class LongPollingClientAdapter extends NetworkAdapter {
+    private session?: { id: string; peerId: PeerId };
+    private seq = 0;
+    private outbox: Message[] = [];
+    private sending = false;
+    private ready = false;
+    private closed = false;
+    private poller?: AbortController;
+
+    constructor(
+        readonly url: string,
+        readonly retryInterval = 5000,
+    ) {
+        super();
+    }
+
+    isReady(): boolean {
+        return this.ready;
+    }
+
+    whenReady(): Promise<void> {
+        return this.ready
+            ? Promise.resolve()
+            : new Promise((resolve) => this.once("ready", () => resolve()));
+    }
+
+    connect(peerId: PeerId, peerMetadata?: PeerMetadata) {
+        this.peerId = peerId;
+        this.peerMetadata = peerMetadata ?? {};
+        this.closed = false;
+        this.run();
+    }
+
+    send(message: Message) {
+        this.outbox.push(message);
+        this.flush();
+    }
+
+    disconnect() {
+        this.closed = true;
+        this.poller?.abort();
+        const session = this.session;
+        if (session) {
+            const url = `${this.url}/${session.id}`;
+            fetch(url, { method: "DELETE", keepalive: true }).catch(() => {});
+            this.lose(session);
+        }
+        this.emit("close");
+    }
+
+    /** Poll for as long as the adapter is connected, joining again whenever the
+    session is lost and waiting a while after each failure. */
+    private async run() {
+        while (!this.closed) {
+            try {
+                if (!this.session) {
+                    await this.join();
+                }
+                await this.poll();
+                continue;
+            } catch {
+                // Failures are retried below.
+            }
+            if (!this.closed) {
+                await new Promise((resolve) => setTimeout(resolve, this.retryInterval));
+            }
+        }
+    }
+
+    private async join() {
+        const response = await fetch(`${this.url}/join`, {
+            method: "POST",
+            headers: { "Content-Type": "application/json" },
+            body: JSON.stringify({ peerId: this.peerId, peerMetadata: this.peerMetadata }),
+        });
+        if (!response.ok) {
+            throw new Error(`Failed to join sync session: ${response.status}`);
+        }
+        const { sessionId, peerId, peerMetadata } = await response.json();
+        this.session = { id: sessionId, peerId };
+        this.seq = 0;
+        this.emit("peer-candidate", { peerId, peerMetadata });
+        if (!this.ready) {
+            this.ready = true;
+            this.emit("ready", { network: this });
+        }
+        this.flush();
+    }
+
+    private async poll() {
+        const session = this.session;
+        if (!session) {
+            return;
+        }
+        this.poller = new AbortController();
+        const response = await fetch(`${this.url}/${session.id}?ack=${this.seq}`, {
+            cache: "no-store",
+            signal: this.poller.signal,
+        });
+        if (response.status === 410) {
+            this.lose(session);
+            throw new Error("Sync session ended");
+        }
+        if (!response.ok) {
+            throw new Error(`Failed to poll sync session: ${response.status}`);
+        }
+        const body = new Uint8Array(await response.arrayBuffer());
+        const { seq, messages } = cbor.decode(body) as { seq: number; messages: Message[] };
+        this.seq = seq;
+        for (const message of messages) {
+            this.emit("message", message);
+        }
+    }
+
+    /** Send the queued messages in order, one batch at a time. */
+    private async flush() {
+        if (this.sending) {
+            return;
+        }
+        this.sending = true;
+        try {
+            while (this.outbox.length > 0 && this.session && !this.closed) {
+                const session = this.session;
+                const batch = this.outbox.slice();
+                const response = await fetch(`${this.url}/${session.id}`, {
+                    method: "POST",
+                    headers: { "Content-Type": "application/octet-stream" },
+                    body: cbor.encode(batch),
+                });
+                if (response.status === 410) {
+                    this.lose(session);
+                    break;
+                }
+                if (!response.ok) {
+                    throw new Error(`Failed to send sync messages: ${response.status}`);
+                }
+                this.outbox.splice(0, batch.length);
+            }
+        } catch {
+            setTimeout(() => this.flush(), this.retryInterval);
+        } finally {
+            this.sending = false;
+        }
+    }
+
+    /** Forget a session that has ended, along with the messages meant for it. */
+    private lose(session: { id: string; peerId: PeerId }) {
+        if (this.session !== session) {
+            return;
+        }
+        this.session = undefined;
+        this.outbox = [];
+        this.emit("peer-disconnected", { peerId: session.peerId });
+    }
+}
+
+/** Network adapter that syncs over a WebSocket if one can be opened, and
+otherwise falls back to long polling.
+
+The transport is chosen once, when the repo connects, by trying to open a
+WebSocket to the backend.
+ */
+export class FallbackClientAdapter extends NetworkAdapter {
+    private adapter?: NetworkAdapter;
+    private ready = false;
+    private closed = false;
+
+    constructor(
+        readonly wsUrl: string,
+        readonly pollUrl: string,
+        readonly probeTimeout = 5000,
+    ) {
+        super();
+    }
+
+    isReady(): boolean {
+        return this.ready;
+    }
+
+    whenReady(): Promise<void> {
+        return this.ready
+            ? Promise.resolve()
+            : new Promise((resolve) => this.once("ready", () => resolve()));
+    }
+
+    connect(peerId: PeerId, peerMetadata?: PeerMetadata) {
+        this.peerId = peerId;
+        this.peerMetadata = peerMetadata;
+        probeWebSocket(this.wsUrl, this.probeTimeout).then((canOpen) => {
+            if (this.closed) {
+                return;
+            }
+            const adapter = canOpen
+                ? new BrowserWebSocketClientAdapter(this.wsUrl)
+                : new LongPollingClientAdapter(this.pollUrl);
+            adapter.on("ready", () => {
+                this.ready = true;
+                this.emit("ready", { network: this });
+            });
+            adapter.on("close", () => this.emit("close"));
+            adapter.on("peer-candidate", (payload) => this.emit("peer-candidate", payload));
+            adapter.on("peer-disconnected", (payload) => this.emit("peer-disconnected", payload));
+            adapter.on("message", (message) => this.emit("message", message));
+            this.adapter = adapter;
+            adapter.connect(peerId, peerMetadata);
+        });
+    }
+
+    send(message: Message) {
+        this.adapter?.send(message);
+    }
+
+    disconnect() {
+        this.closed = true;
+        this.adapter?.disconnect();
+    }
+}
+
+/** Whether a WebSocket to the given URL opens within the timeout. */
+function probeWebSocket(url: string, timeoutMs: number): Promise<boolean> {
+    return new Promise((resolve) => {
+        let socket: WebSocket;
+        try {
+            socket = new WebSocket(url);
+        } catch {
+            resolve(false);
+            return;
+        }
+        const timeout = setTimeout(() => finish(false), timeoutMs);
+        function finish(opened: boolean) {
+            clearTimeout(timeout);
+            socket.onopen = null;
+            socket.onerror = null;
+            socket.onclose = null;
+            socket.close();
+            resolve(opened);
+        }
+        socket.onopen = () => finish(true);
+        socket.onerror = () => finish(false);
+        socket.onclose = () => finish(false);
+    });
+}
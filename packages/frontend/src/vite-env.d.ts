@@ -2,6 +2,8 @@
 
 interface ImportMetaEnv {
     readonly VITE_APP_TITLE: string;
+    /** Transport for syncing documents: `auto` (default), `websocket`, or `long-polling`. */
+    readonly VITE_SYNC_TRANSPORT?: string;
 }
 
 interface ImportMeta {